@@ -1,3 +1,5 @@
+#![allow(clippy::unwrap_used)] // Examples favour brevity over error handling
+
 use cast::config::CastConfig;
 use std::fs;
 use tempdir::TempDir;
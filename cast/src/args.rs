@@ -1,13 +1,31 @@
+use crate::executor::{self, CastCommand, CommandOutput, ExecuteError, OutputFormat};
+use crate::issues::{GraphFormat, IssueFilter};
+use crate::release::{Bump, ReleaseOptions};
 use crate::sessions::SessionStartOptions;
-use crate::{build, cd, ci, deploy, projects, run, serve, sessions, test};
-use clap::{Parser, Subcommand};
+use crate::targets::TargetArgs;
+use crate::workflow_runner::RunOptions;
+use crate::{
+    audit, build, cd, ci, daemon, deploy, hooks, issues, plugins, projects, release, run, serve,
+    sessions, test, workflow_runner, workflows,
+};
+use clap::{CommandFactory, Parser, Subcommand};
+use serde_json::{json, Value};
+use std::ffi::OsString;
 use std::fs;
-use std::path::Path;
-use thiserror::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 #[derive(Parser)]
 #[command(author, version, about = "Highly opinionated tooling for rust monorepos.", long_about = None)]
 pub struct Args {
+    /// Print the result as text or as a JSON object
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    output: OutputFormat,
+
+    /// Also write the JSON result to this file, where tool output can't be mistaken for it
+    #[arg(long, global = true, hide = true)]
+    result_file: Option<PathBuf>,
+
     #[command(subcommand)]
     cmd: Commands,
 }
@@ -19,19 +37,202 @@ enum Commands {
     #[command(subcommand)]
     Project(ProjectCommands),
     /// Run build
-    Build,
+    Build(BuildCommand),
     /// Run CI checks
-    Ci,
+    Ci(CiCommand),
     /// Run CD (Continuous Deployment)
-    Cd,
+    Cd(CdCommand),
     /// Run tests
-    Test,
+    Test(TestCommand),
     /// Run server (dx serve for dioxus, cargo run otherwise)
-    Run,
+    Run(RunCommand),
     /// Serve static files from current directory
     Serve,
+    /// Serve session, project, CI and issue state as JSON-RPC over stdio for editor integrations
+    Daemon,
     /// Deploy an IAC project
-    Deploy,
+    Deploy(DeployCommand),
+    /// Release a project (bump version, update changelog, tag)
+    Release(ReleaseCommand),
+    /// Audit dependencies across the monorepo (duplicates, licenses, advisories)
+    Audit(AuditCommand),
+    /// List TODO items from ISSUES.md files and source comments
+    Issues(IssuesCommand),
+    #[command(subcommand)]
+    Workflows(WorkflowsCommands),
+    #[command(subcommand)]
+    Hooks(HooksCommands),
+    #[command(subcommand)]
+    Plugins(PluginsCommands),
+    /// Any other subcommand runs the `cast-<name>` plugin
+    #[command(external_subcommand)]
+    External(Vec<OsString>),
+}
+
+#[derive(Parser, Default)]
+pub struct BuildCommand {
+    /// Build in release mode for every configured target and collect artifacts
+    #[arg(long)]
+    release: bool,
+
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(Parser, Default)]
+pub struct CiCommand {
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(Parser, Default)]
+pub struct CdCommand {
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(Parser, Default)]
+pub struct TestCommand {
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(Parser, Default)]
+pub struct RunCommand {
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(Parser, Default)]
+pub struct DeployCommand {
+    #[command(flatten)]
+    target: TargetArgs,
+}
+
+#[derive(Parser)]
+pub struct ReleaseCommand {
+    /// Path to the project to release
+    project: PathBuf,
+
+    /// Which part of the version to increment
+    #[arg(long, value_enum)]
+    bump: Bump,
+
+    /// Show the next version and changes without writing, committing or tagging
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Parser)]
+pub struct AuditCommand {}
+
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct IssuesCommand {
+    #[command(subcommand)]
+    cmd: Option<IssuesCommands>,
+
+    #[command(flatten)]
+    list: ListIssuesCommand,
+}
+
+#[derive(Subcommand)]
+pub enum IssuesCommands {
+    /// List issues (the default when no subcommand is given)
+    List(ListIssuesCommand),
+    /// Show the first open, unblocked issue in priority order
+    Next,
+    /// Print the issue dependency graph and the currently unblocked issues
+    Graph(GraphIssuesCommand),
+    /// Mark an ISSUES.md item as completed in place
+    Done(DoneIssueCommand),
+}
+
+#[derive(Parser)]
+pub struct ListIssuesCommand {
+    /// Only show issues attributed to this project (relative to the monorepo root)
+    #[arg(long)]
+    project: Option<PathBuf>,
+
+    /// Only show agent-generated issues
+    #[arg(long)]
+    agent_generated: bool,
+
+    /// Only show completed issues
+    #[arg(long, conflicts_with = "all")]
+    completed: bool,
+
+    /// Show open and completed issues
+    #[arg(long)]
+    all: bool,
+}
+
+#[derive(Parser)]
+pub struct GraphIssuesCommand {
+    /// Graph output format
+    #[arg(long, value_enum, default_value = "mermaid")]
+    format: GraphFormat,
+
+    /// Only include issues attributed to this project (relative to the monorepo root)
+    #[arg(long)]
+    project: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct DoneIssueCommand {
+    /// Issue id (declared with `[id: name]`) or `<file>:<line>` location
+    id: String,
+}
+
+#[derive(Subcommand)]
+pub enum WorkflowsCommands {
+    /// Render the GitHub Actions workflows from the Cast project graph
+    Generate,
+    /// Fail when the committed workflows differ from what would be generated
+    Check,
+    /// Run a workflow locally, replacing `uses:` actions with configured substitutes
+    Run(RunWorkflowCommand),
+}
+
+#[derive(Parser)]
+pub struct RunWorkflowCommand {
+    /// Path to the workflow file
+    file: PathBuf,
+
+    /// Event that triggers the workflow
+    #[arg(long, default_value = "pull_request")]
+    event: String,
+
+    /// JSON file with the event payload (built from the local git history by default)
+    #[arg(long)]
+    event_path: Option<PathBuf>,
+
+    /// Only run this job and the jobs it needs (can be repeated)
+    #[arg(long = "job")]
+    jobs: Vec<String>,
+}
+
+#[derive(Subcommand)]
+pub enum HooksCommands {
+    /// Install a git pre-commit hook that checks the projects touched by staged changes
+    Install(InstallHooksCommand),
+    /// Remove the pre-commit hook installed by `cast hooks install`
+    Uninstall,
+    /// Run the pre-commit checks for the staged changes (called by the installed hook)
+    Run,
+}
+
+#[derive(Parser)]
+pub struct InstallHooksCommand {
+    /// Replace an existing pre-commit hook that wasn't installed by cast
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Subcommand)]
+pub enum PluginsCommands {
+    /// List `cast-<name>` plugins in .cast/plugins and on the PATH
+    List,
 }
 
 #[derive(Subcommand)]
@@ -39,6 +240,8 @@ pub enum SessionCommands {
     Start(StartSessionCommand),
     Pause,
     Stop,
+    /// Convert legacy session logs to JSON Lines
+    Migrate,
 }
 
 #[derive(Parser)]
@@ -71,145 +274,530 @@ pub struct WithChangesCommand {
     head: String,
 }
 
-#[derive(Error, Debug)]
-pub enum ExecuteError {
-    #[error("cast toml not found")]
-    CastTomlNotFound,
-    #[error("with-changes error: {0}")]
-    WithChangesError(String),
-    #[error("ci error: {0}")]
-    CiError(#[from] ci::CiError),
-    #[error("cd error: {0}")]
-    CdError(#[from] cd::CdError),
-    #[error("build error: {0}")]
-    BuildError(#[from] build::BuildError),
-    #[error("test error: {0}")]
-    TestError(#[from] test::TestError),
-    #[error("run error: {0}")]
-    RunError(#[from] run::RunError),
-    #[error("serve error: {0}")]
-    ServeError(#[from] serve::ServeError),
-    #[error("deploy error: {0}")]
-    DeployError(#[from] deploy::DeployError),
-}
-
-pub fn execute(args: Args, entry_directory: &Path) -> Result<String, ExecuteError> {
-    // Handle commands that don't require Cast.toml
-    match &args.cmd {
-        Commands::Project(ProjectCommands::WithChanges(cmd)) => {
-            let changed_projects = projects::with_changes(entry_directory, &cmd.base, &cmd.head)
-                .map_err(|e| ExecuteError::WithChangesError(e.to_string()))?;
-
-            // Return newline-separated list of project paths
-            let output = changed_projects
-                .iter()
-                .map(|p| p.display().to_string())
-                .collect::<Vec<_>>()
-                .join("\n");
+struct ServeCommand;
+struct DaemonCommand;
+
+/// Runs the `cast-<name>` plugin with the remaining arguments
+struct PluginCommand(Vec<OsString>);
 
-            return Ok(output);
+impl Commands {
+    fn into_command(self) -> Box<dyn CastCommand> {
+        match self {
+            Commands::Session(cmd) => Box::new(cmd),
+            Commands::Project(cmd) => Box::new(cmd),
+            Commands::Build(cmd) => Box::new(cmd),
+            Commands::Ci(cmd) => Box::new(cmd),
+            Commands::Cd(cmd) => Box::new(cmd),
+            Commands::Test(cmd) => Box::new(cmd),
+            Commands::Run(cmd) => Box::new(cmd),
+            Commands::Serve => Box::new(ServeCommand),
+            Commands::Daemon => Box::new(DaemonCommand),
+            Commands::Deploy(cmd) => Box::new(cmd),
+            Commands::Release(cmd) => Box::new(cmd),
+            Commands::Audit(cmd) => Box::new(cmd),
+            Commands::Issues(cmd) => Box::new(cmd),
+            Commands::Workflows(cmd) => Box::new(cmd),
+            Commands::Hooks(cmd) => Box::new(cmd),
+            Commands::Plugins(cmd) => Box::new(cmd),
+            Commands::External(args) => Box::new(PluginCommand(args)),
         }
-        Commands::Serve => {
-            // Serve command doesn't require Cast.toml - it can serve any directory
-            serve::run(entry_directory)?;
-            return Ok("Static file server started".into());
+    }
+}
+
+/// Execute the parsed command, finding the Cast.toml first when the command needs one
+pub fn execute(args: Args, entry_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+    executor::execute(args.cmd.into_command().as_ref(), entry_directory)
+}
+
+/// Execute the parsed command and print its result in the `--output` format
+pub fn run(args: Args, entry_directory: &Path) -> ExitCode {
+    let format = args.output;
+    let result_file = args.result_file.clone();
+    let result = execute(args, entry_directory);
+    if let Some(path) = result_file {
+        if let Err(e) = fs::write(&path, executor::result_json(&result).to_string()) {
+            eprintln!("Error: failed to write {}: {}", path.display(), e);
         }
-        _ => {} // Other commands require Cast.toml
-    }
-
-    // Other commands require Cast.toml
-    if let Some(working_directory) = find_cast_toml(entry_directory) {
-        match args.cmd {
-            Commands::Session(session_command) => match session_command {
-                SessionCommands::Start(start_session_command) => {
-                    // TODO (agent-generated): Propagate errors from session operations instead of ignoring them
-                    let _ = sessions::start(
-                        working_directory,
-                        Some(SessionStartOptions {
-                            name: start_session_command.name,
-                        }),
+    }
+    executor::report(result, format)
+}
+
+impl CastCommand for SessionCommands {
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        match self {
+            SessionCommands::Start(cmd) => {
+                sessions::start(
+                    working_directory,
+                    Some(SessionStartOptions {
+                        name: cmd.name.clone(),
+                    }),
+                )?;
+                Ok(CommandOutput::new("Starting session."))
+            }
+            SessionCommands::Pause => {
+                sessions::pause(working_directory)?;
+                Ok(CommandOutput::new("Pausing session."))
+            }
+            SessionCommands::Stop => {
+                sessions::stop(working_directory)?;
+                Ok(CommandOutput::new("Stopping session."))
+            }
+            SessionCommands::Migrate => {
+                let migration = sessions::migrate(working_directory);
+                if !migration.failed.is_empty() {
+                    return Err(
+                        sessions::SessionLogError::MigrationFailed(Box::new(migration)).into(),
                     );
-                    Ok("Starting session.".into())
-                }
-                SessionCommands::Pause => {
-                    // TODO (agent-generated): Propagate errors from session operations instead of ignoring them
-                    let _ = sessions::pause(working_directory);
-                    Ok("Pausing session.".into())
-                }
-                SessionCommands::Stop => {
-                    // TODO (agent-generated): Propagate errors from session operations instead of ignoring them
-                    let _ = sessions::stop(working_directory);
-                    Ok("Stopping session.".into())
                 }
+                Ok(CommandOutput::new(migration.to_string().trim_end())
+                    .with_data(path_list(&migration.migrated)))
+            }
+        }
+    }
+}
+
+impl CastCommand for ProjectCommands {
+    fn requires_cast_toml(&self) -> bool {
+        // Changed projects are found from the monorepo root, wherever cast is run
+        !matches!(self, ProjectCommands::WithChanges(_))
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        match self {
+            ProjectCommands::New(cmd) => {
+                projects::new(working_directory, &cmd.name)?;
+                Ok(CommandOutput::new("Creating project.").with_data(
+                    json!({ "path": working_directory.join(&cmd.name).display().to_string() }),
+                ))
+            }
+            ProjectCommands::WithChanges(cmd) => {
+                let changed_projects =
+                    projects::with_changes(working_directory, &cmd.base, &cmd.head)?;
+
+                // Newline-separated list of project paths
+                Ok(CommandOutput::new(join_paths(&changed_projects, ""))
+                    .with_data(path_list(&changed_projects)))
+            }
+        }
+    }
+}
+
+impl CastCommand for BuildCommand {
+    fn targets(&self) -> Option<&TargetArgs> {
+        Some(&self.target)
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        if !self.release {
+            build::run(working_directory)?;
+            return Ok(CommandOutput::new("Build passed"));
+        }
+
+        let artifacts = build::release(working_directory)?;
+        let mut output = vec!["Build passed".to_string()];
+        output.extend(
+            artifacts
+                .iter()
+                .map(|artifact| format!("{}  {}", artifact.sha256, artifact.path.display())),
+        );
+        let data = artifacts
+            .iter()
+            .map(|artifact| {
+                json!({
+                    "target": artifact.target,
+                    "path": artifact.path.display().to_string(),
+                    "sha256": artifact.sha256,
+                })
+            })
+            .collect();
+        Ok(CommandOutput::new(output.join("\n")).with_data(Value::Array(data)))
+    }
+}
+
+impl CastCommand for CiCommand {
+    fn targets(&self) -> Option<&TargetArgs> {
+        Some(&self.target)
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        ci::run(working_directory)?;
+        Ok(CommandOutput::new("CI passed"))
+    }
+}
+
+impl CastCommand for CdCommand {
+    fn targets(&self) -> Option<&TargetArgs> {
+        Some(&self.target)
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        cd::run(working_directory)?;
+        Ok(CommandOutput::new("CD completed"))
+    }
+}
+
+impl CastCommand for TestCommand {
+    fn targets(&self) -> Option<&TargetArgs> {
+        Some(&self.target)
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        test::run(working_directory)?;
+        Ok(CommandOutput::new("Tests passed"))
+    }
+}
+
+impl CastCommand for RunCommand {
+    fn targets(&self) -> Option<&TargetArgs> {
+        Some(&self.target)
+    }
+
+    fn runs_in_many_projects(&self) -> bool {
+        // The server blocks until it is stopped
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        run::run(working_directory)?;
+        Ok(CommandOutput::new("Server started"))
+    }
+}
+
+impl CastCommand for ServeCommand {
+    fn requires_cast_toml(&self) -> bool {
+        // Any directory can be served
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        serve::run(working_directory)?;
+        Ok(CommandOutput::new("Static file server started"))
+    }
+}
+
+impl CastCommand for DaemonCommand {
+    fn requires_cast_toml(&self) -> bool {
+        // Editors start the daemon in the workspace folder, which may be the monorepo root
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        daemon::run(working_directory)?;
+        // stdout belongs to the JSON-RPC stream, so nothing is printed on exit
+        Ok(CommandOutput::default())
+    }
+}
+
+impl CastCommand for DeployCommand {
+    fn targets(&self) -> Option<&TargetArgs> {
+        Some(&self.target)
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        deploy::run(working_directory)?;
+        Ok(CommandOutput::new("Deploy completed"))
+    }
+}
+
+impl CastCommand for ReleaseCommand {
+    fn requires_cast_toml(&self) -> bool {
+        // Release targets the given project rather than the current directory
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        let summary = release::run(
+            working_directory.join(&self.project),
+            ReleaseOptions {
+                bump: self.bump,
+                dry_run: self.dry_run,
             },
-            Commands::Project(project_command) => match project_command {
-                ProjectCommands::New(new_project_command) => {
-                    let _ = projects::new(working_directory, &new_project_command.name);
-                    Ok("Creating project.".into())
-                }
-                ProjectCommands::WithChanges(_) => {
-                    // This case should never be reached because WithChanges is handled
-                    // at the top of execute() before the Cast.toml check. If we reach
-                    // this point, there's a bug in the control flow logic.
-                    unreachable!(
-                        "WithChanges command should be handled before Cast.toml check. \
-                         This indicates a bug in the execute() function's control flow."
-                    )
-                }
+        )?;
+
+        let mut output = vec![format!(
+            "{} {} -> {} (tag {})",
+            if self.dry_run {
+                "Would release"
+            } else {
+                "Released"
             },
-            Commands::Ci => {
-                ci::run(working_directory)?;
-                Ok("CI passed".into())
+            summary.previous_version,
+            summary.version,
+            summary.tag
+        )];
+        output.extend(summary.changes.iter().map(|change| format!("- {}", change)));
+
+        let data = json!({
+            "previous_version": summary.previous_version.to_string(),
+            "version": summary.version.to_string(),
+            "tag": summary.tag,
+            "dry_run": self.dry_run,
+            "changes": summary
+                .changes
+                .iter()
+                .map(|change| change.to_string())
+                .collect::<Vec<_>>(),
+        });
+        Ok(CommandOutput::new(output.join("\n")).with_data(data))
+    }
+}
+
+impl CastCommand for AuditCommand {
+    fn requires_cast_toml(&self) -> bool {
+        // Audit covers the whole monorepo
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        let report = audit::run(working_directory)?;
+        if report.has_failures() {
+            return Err(audit::AuditError::Failed(Box::new(report)).into());
+        }
+        Ok(CommandOutput::new(report.to_string()).with_data(serde_json::to_value(&report)?))
+    }
+}
+
+impl CastCommand for IssuesCommand {
+    fn requires_cast_toml(&self) -> bool {
+        // Issues are indexed across the whole monorepo
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        let index = issues::run(working_directory)?;
+        match &self.cmd {
+            Some(IssuesCommands::Next) => match index.next() {
+                Some(issue) => Ok(CommandOutput::new(format_issue_with_headings(issue))
+                    .with_data(serde_json::to_value(issue)?)),
+                None => Ok(CommandOutput::new("No open issues found")),
+            },
+            Some(IssuesCommands::Graph(graph_command)) => {
+                let output = index.graph(
+                    &IssueFilter {
+                        project: graph_command.project.clone(),
+                        ..Default::default()
+                    },
+                    graph_command.format,
+                );
+                for (issue, id) in index.unknown_dependencies() {
+                    eprintln!("Warning: {} depends on unknown issue '{}'", issue.id, id);
+                }
+                Ok(CommandOutput::new(output.clone()).with_data(json!({ "graph": output })))
             }
-            Commands::Build => {
-                build::run(working_directory)?;
-                Ok("Build passed".into())
+            Some(IssuesCommands::Done(done_command)) => {
+                let issue = issues::mark_done(working_directory, &done_command.id)?;
+                Ok(CommandOutput::new(format!("Marked done: {}", issue))
+                    .with_data(serde_json::to_value(&issue)?))
             }
-            Commands::Test => {
-                test::run(working_directory)?;
-                Ok("Tests passed".into())
+            Some(IssuesCommands::List(list_command)) => list_issues(&index, list_command),
+            None => list_issues(&index, &self.list),
+        }
+    }
+}
+
+impl CastCommand for WorkflowsCommands {
+    fn requires_cast_toml(&self) -> bool {
+        // Workflows belong to the monorepo rather than a project
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        match self {
+            WorkflowsCommands::Generate => {
+                let written = workflows::generate(working_directory)?;
+                let message = if written.is_empty() {
+                    "Workflows are up to date".to_string()
+                } else {
+                    format!("Generated workflows:\n{}", join_paths(&written, "  "))
+                };
+                Ok(CommandOutput::new(message).with_data(path_list(&written)))
             }
-            Commands::Run => {
-                run::run(working_directory)?;
-                Ok("Server started".into())
+            WorkflowsCommands::Check => {
+                workflows::check(working_directory)?;
+                Ok(CommandOutput::new("Workflows are up to date"))
             }
-            Commands::Serve => {
-                // This case should never be reached because Serve is handled
-                // at the top of execute() before the Cast.toml check. If we reach
-                // this point, there's a bug in the control flow logic.
-                unreachable!(
-                    "Serve command should be handled before Cast.toml check. \
-                     This indicates a bug in the execute() function's control flow."
+            WorkflowsCommands::Run(cmd) => {
+                let options = RunOptions {
+                    event: cmd.event.clone(),
+                    event_path: cmd
+                        .event_path
+                        .as_ref()
+                        .map(|path| working_directory.join(path)),
+                    jobs: cmd.jobs.clone(),
+                };
+                let report = workflow_runner::run(
+                    working_directory,
+                    working_directory.join(&cmd.file),
+                    &options,
+                )?;
+                if report.has_failures() {
+                    return Err(workflow_runner::WorkflowRunError::Failed(Box::new(report)).into());
+                }
+                if !report.is_complete() {
+                    return Err(
+                        workflow_runner::WorkflowRunError::Incomplete(Box::new(report)).into(),
+                    );
+                }
+                Ok(
+                    CommandOutput::new(report.to_string())
+                        .with_data(serde_json::to_value(&report)?),
                 )
             }
-            Commands::Deploy => {
-                deploy::run(working_directory)?;
-                Ok("Deploy completed".into())
+        }
+    }
+}
+
+impl CastCommand for HooksCommands {
+    fn requires_cast_toml(&self) -> bool {
+        // Hooks belong to the git repository rather than a project
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        match self {
+            HooksCommands::Install(cmd) => {
+                let hook_path = hooks::install(working_directory, cmd.force)?;
+                Ok(CommandOutput::new(format!(
+                    "Installed pre-commit hook at {} (set {}=1 to bypass it)",
+                    hook_path.display(),
+                    hooks::BYPASS_ENV_VAR
+                ))
+                .with_data(json!({ "path": hook_path.display().to_string() })))
             }
-            Commands::Cd => {
-                cd::run(working_directory)?;
-                Ok("CD completed".into())
+            HooksCommands::Uninstall => match hooks::uninstall(working_directory)? {
+                Some(hook_path) => Ok(CommandOutput::new(format!(
+                    "Removed pre-commit hook at {}",
+                    hook_path.display()
+                ))
+                .with_data(json!({ "path": hook_path.display().to_string() }))),
+                None => Ok(CommandOutput::new("No cast pre-commit hook installed")),
+            },
+            HooksCommands::Run => {
+                if hooks::is_bypassed() {
+                    return Ok(CommandOutput::new(format!(
+                        "Skipping pre-commit checks ({} is set)",
+                        hooks::BYPASS_ENV_VAR
+                    )));
+                }
+                let projects = hooks::run(working_directory)?;
+                let message = if projects.is_empty() {
+                    "No staged changes in Cast projects".to_string()
+                } else {
+                    format!("Pre-commit checks passed:\n{}", join_paths(&projects, "  "))
+                };
+                Ok(CommandOutput::new(message).with_data(path_list(&projects)))
             }
         }
-    } else {
-        Err(ExecuteError::CastTomlNotFound)
     }
 }
 
-fn find_cast_toml(working_directory: &Path) -> Option<&Path> {
-    let mut current_directory = Some(working_directory);
-    while let Some(current_path) = current_directory {
-        if let Ok(entries) = fs::read_dir(current_path) {
-            for entry in entries.flatten() {
-                if entry.file_name() == "Cast.toml" {
-                    return current_directory;
+impl CastCommand for PluginsCommands {
+    fn requires_cast_toml(&self) -> bool {
+        false
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        match self {
+            PluginsCommands::List => {
+                let plugins = plugins::find_plugins(working_directory);
+                if plugins.is_empty() {
+                    return Ok(CommandOutput::new("No plugins found").with_data(json!([])));
+                }
+                let builtins: Vec<String> = Args::command()
+                    .get_subcommands()
+                    .map(|command| command.get_name().to_string())
+                    .collect();
+
+                let mut lines = Vec::new();
+                let mut data = Vec::new();
+                for plugin in &plugins {
+                    let shadowed = builtins.contains(&plugin.name);
+                    lines.push(format!(
+                        "{}\t{}\t{}{}",
+                        plugin.name,
+                        plugin.source,
+                        plugin.path.display(),
+                        if shadowed {
+                            " (shadowed by the built-in command)"
+                        } else {
+                            ""
+                        }
+                    ));
+                    data.push(json!({
+                        "name": plugin.name,
+                        "source": plugin.source.to_string(),
+                        "path": plugin.path.display().to_string(),
+                        "shadowed": shadowed,
+                    }));
                 }
+                Ok(CommandOutput::new(lines.join("\n")).with_data(Value::Array(data)))
             }
-            current_directory = current_path.parent();
         }
     }
+}
+
+impl CastCommand for PluginCommand {
+    fn requires_cast_toml(&self) -> bool {
+        // Plugins decide for themselves whether they need a Cast.toml
+        false
+    }
 
-    None
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+        let (name, plugin_args) = self
+            .0
+            .split_first()
+            .ok_or_else(|| plugins::PluginsError::NotFound(String::new()))?;
+        plugins::run(working_directory, &name.to_string_lossy(), plugin_args)?;
+        // Plugins print their own output
+        Ok(CommandOutput::default())
+    }
+}
+
+fn list_issues(
+    index: &issues::IssueIndex,
+    cmd: &ListIssuesCommand,
+) -> Result<CommandOutput, ExecuteError> {
+    let filter = IssueFilter {
+        project: cmd.project.clone(),
+        agent_generated: cmd.agent_generated,
+        completed: match (cmd.completed, cmd.all) {
+            (true, _) => Some(true),
+            (false, true) => None,
+            (false, false) => Some(false),
+        },
+    };
+    let matching = index.filter(&filter);
+    let data = serde_json::to_value(&matching)?;
+    let message = matching
+        .iter()
+        .map(|issue| issue.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(CommandOutput::new(message).with_data(data))
+}
+
+/// Format an issue with the headings it is nested under, for handing to an agent
+fn format_issue_with_headings(issue: &issues::Issue) -> String {
+    if issue.headings.is_empty() {
+        return issue.to_string();
+    }
+    format!("{}\nUnder: {}", issue, issue.headings.join(" > "))
+}
+
+/// One path per line, each prefixed with `indent`
+fn join_paths(paths: &[PathBuf], indent: &str) -> String {
+    paths
+        .iter()
+        .map(|path| format!("{}{}", indent, path.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn path_list(paths: &[PathBuf]) -> Value {
+    paths
+        .iter()
+        .map(|path| Value::String(path.display().to_string()))
+        .collect()
 }
 
 #[cfg(test)]
@@ -218,16 +806,44 @@ mod tests {
     use std::fs;
     use tempdir::TempDir;
 
+    fn args(cmd: Commands) -> Args {
+        Args {
+            output: OutputFormat::Text,
+            result_file: None,
+            cmd,
+        }
+    }
+
     #[test]
     fn it_exits_if_cast_toml_is_missing() {
         let tmp_dir = TempDir::new("test").unwrap();
         let result = execute(
-            Args {
-                cmd: Commands::Session(SessionCommands::Start(StartSessionCommand { name: None })),
-            },
+            args(Commands::Session(SessionCommands::Start(
+                StartSessionCommand { name: None },
+            ))),
             tmp_dir.path(),
         );
-        assert!(result.is_err());
+        assert!(matches!(result, Err(ExecuteError::CastTomlNotFound)));
+    }
+
+    #[test]
+    fn it_writes_the_json_result_to_the_result_file() {
+        let tmp_dir = TempDir::new("test").unwrap();
+        fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        let result_file = tmp_dir.path().join("result.json");
+        let args = Args {
+            output: OutputFormat::Text,
+            result_file: Some(result_file.clone()),
+            cmd: Commands::Session(SessionCommands::Start(StartSessionCommand { name: None })),
+        };
+        run(args, tmp_dir.path());
+
+        let result: Value =
+            serde_json::from_str(&fs::read_to_string(result_file).unwrap()).unwrap();
+        assert_eq!(
+            result,
+            json!({ "ok": true, "message": "Starting session.", "data": null })
+        );
     }
 
     #[test]
@@ -235,54 +851,102 @@ mod tests {
         let tmp_dir = TempDir::new("test").unwrap();
         fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
         let result = execute(
-            Args {
-                cmd: Commands::Session(SessionCommands::Start(StartSessionCommand { name: None })),
-            },
+            args(Commands::Session(SessionCommands::Start(
+                StartSessionCommand { name: None },
+            ))),
             tmp_dir.path(),
         )
         .unwrap();
-        assert_eq!(result, "Starting session.");
+        assert_eq!(result.message, "Starting session.");
     }
     #[test]
     fn it_pauses_session() {
         let tmp_dir = TempDir::new("test").unwrap();
         fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        sessions::start(tmp_dir.path(), None).unwrap();
         let result = execute(
-            Args {
-                cmd: Commands::Session(SessionCommands::Pause),
-            },
+            args(Commands::Session(SessionCommands::Pause)),
             tmp_dir.path(),
         )
         .unwrap();
-        assert_eq!(result, "Pausing session.");
+        assert_eq!(result.message, "Pausing session.");
     }
     #[test]
     fn it_stops_session() {
         let tmp_dir = TempDir::new("test").unwrap();
         fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        sessions::start(tmp_dir.path(), None).unwrap();
         let result = execute(
-            Args {
-                cmd: Commands::Session(SessionCommands::Stop),
-            },
+            args(Commands::Session(SessionCommands::Stop)),
+            tmp_dir.path(),
+        )
+        .unwrap();
+        assert_eq!(result.message, "Stopping session.");
+    }
+    #[test]
+    fn it_migrates_sessions() {
+        let tmp_dir = TempDir::new("test").unwrap();
+        fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        sessions::start(tmp_dir.path(), None).unwrap();
+        let result = execute(
+            args(Commands::Session(SessionCommands::Migrate)),
             tmp_dir.path(),
         )
         .unwrap();
-        assert_eq!(result, "Stopping session.");
+        assert_eq!(result.message, "No session logs to migrate");
+    }
+    #[test]
+    fn it_propagates_session_errors() {
+        let tmp_dir = TempDir::new("test").unwrap();
+        fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        let result = execute(
+            args(Commands::Session(SessionCommands::Pause)),
+            tmp_dir.path(),
+        );
+        assert!(matches!(
+            result,
+            Err(ExecuteError::PauseSessionError(
+                sessions::PauseSessionError::NoActiveSession
+            ))
+        ));
     }
     #[test]
     fn it_news_project() {
         let tmp_dir = TempDir::new("test").unwrap();
         fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        fs::create_dir_all(tmp_dir.path().join("exemplar")).unwrap();
+        fs::write(
+            tmp_dir.path().join("exemplar/Cast.toml"),
+            "exemplar = true\n",
+        )
+        .unwrap();
         let result = execute(
-            Args {
-                cmd: Commands::Project(ProjectCommands::New(NewProjectCommand {
-                    name: "test".into(),
-                })),
-            },
+            args(Commands::Project(ProjectCommands::New(NewProjectCommand {
+                name: "test".into(),
+            }))),
             tmp_dir.path(),
         )
         .unwrap();
-        assert_eq!(result, "Creating project.");
+        assert_eq!(result.message, "Creating project.");
+        assert!(tmp_dir.path().join("test/Cast.toml").exists());
+    }
+
+    #[test]
+    fn it_fails_to_new_project_without_exemplars() {
+        let tmp_dir = TempDir::new("test").unwrap();
+        fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        let result = execute(
+            args(Commands::Project(ProjectCommands::New(NewProjectCommand {
+                name: "test".into(),
+            }))),
+            tmp_dir.path(),
+        );
+        assert!(matches!(
+            result,
+            Err(ExecuteError::NewProjectError(
+                projects::NewProjectError::NoExemplarProjects
+            ))
+        ));
     }
 
     #[test]
@@ -293,7 +957,10 @@ mod tests {
             .join("test_level_two/test_level_three/test_level_four");
         fs::create_dir_all(&child_dir).unwrap();
         fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
-        assert_eq!(find_cast_toml(child_dir.as_path()).unwrap(), tmp_dir.path())
+        assert_eq!(
+            executor::find_cast_toml(child_dir.as_path()).unwrap(),
+            tmp_dir.path()
+        )
     }
 
     #[test]
@@ -310,8 +977,8 @@ mod tests {
         fs::create_dir_all(tmp_dir.path().join("src")).unwrap();
         fs::write(tmp_dir.path().join("src/lib.rs"), "pub fn test() {}\n").unwrap();
 
-        let result = execute(Args { cmd: Commands::Ci }, tmp_dir.path()).unwrap();
-        assert_eq!(result, "CI passed");
+        let result = execute(args(Commands::Ci(CiCommand::default())), tmp_dir.path()).unwrap();
+        assert_eq!(result.message, "CI passed");
     }
 
     #[test]
@@ -329,13 +996,11 @@ mod tests {
         fs::write(tmp_dir.path().join("src/lib.rs"), "pub fn test() {}\n").unwrap();
 
         let result = execute(
-            Args {
-                cmd: Commands::Build,
-            },
+            args(Commands::Build(BuildCommand::default())),
             tmp_dir.path(),
         )
         .unwrap();
-        assert_eq!(result, "Build passed");
+        assert_eq!(result.message, "Build passed");
     }
 
     #[test]
@@ -347,8 +1012,64 @@ mod tests {
 
         fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
 
-        let result = execute(Args { cmd: Commands::Cd }, tmp_dir.path()).unwrap();
-        assert_eq!(result, "CD completed");
+        let result = execute(args(Commands::Cd(CdCommand::default())), tmp_dir.path()).unwrap();
+        assert_eq!(result.message, "CD completed");
+    }
+
+    #[test]
+    fn it_reports_results_per_target_project() {
+        let tmp_dir = TempDir::new("test").unwrap();
+        fs::create_dir(tmp_dir.path().join(".git")).unwrap();
+        for project in ["cahokia/web", "cahokia/api", "pane"] {
+            fs::create_dir_all(tmp_dir.path().join(project)).unwrap();
+            fs::write(tmp_dir.path().join(project).join("Cast.toml"), "").unwrap();
+        }
+
+        // Runs from the monorepo root, where there is no Cast.toml
+        let result = execute(
+            args(Commands::Cd(CdCommand {
+                target: TargetArgs {
+                    projects: vec!["cahokia/*".into()],
+                    all: false,
+                },
+            })),
+            tmp_dir.path(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.message,
+            "✓ cahokia/api: CD completed\n✓ cahokia/web: CD completed"
+        );
+        assert_eq!(result.data[1]["project"], "cahokia/web");
+
+        // A project failing doesn't stop the others
+        fs::write(
+            tmp_dir.path().join("pane/Cast.toml"),
+            "project_type = \"iac\"\nframework = \"cloudflare-pages\"",
+        )
+        .unwrap();
+        let result = execute(
+            args(Commands::Cd(CdCommand {
+                target: TargetArgs {
+                    projects: Vec::new(),
+                    all: true,
+                },
+            })),
+            tmp_dir.path(),
+        );
+        match result {
+            Err(ExecuteError::ProjectsFailed {
+                failed,
+                total,
+                results,
+                ..
+            }) => {
+                assert_eq!((failed, total), (1, 3));
+                assert_eq!(results[2]["project"], "pane");
+                assert_eq!(results[2]["ok"], false);
+            }
+            _ => panic!("expected pane to fail"),
+        }
     }
 
     #[test]
@@ -369,14 +1090,8 @@ mod tests {
         )
         .unwrap();
 
-        let result = execute(
-            Args {
-                cmd: Commands::Test,
-            },
-            tmp_dir.path(),
-        )
-        .unwrap();
-        assert_eq!(result, "Tests passed");
+        let result = execute(args(Commands::Test(TestCommand::default())), tmp_dir.path()).unwrap();
+        assert_eq!(result.message, "Tests passed");
     }
 
     #[test]
@@ -397,8 +1112,8 @@ mod tests {
         )
         .unwrap();
 
-        let result = execute(Args { cmd: Commands::Run }, tmp_dir.path()).unwrap();
-        assert_eq!(result, "Server started");
+        let result = execute(args(Commands::Run(RunCommand::default())), tmp_dir.path()).unwrap();
+        assert_eq!(result.message, "Server started");
     }
 
     #[test]
@@ -432,9 +1147,7 @@ mod tests {
         .unwrap();
 
         let result = execute(
-            Args {
-                cmd: Commands::Deploy,
-            },
+            args(Commands::Deploy(DeployCommand::default())),
             tmp_dir.path(),
         );
         // Deploy will fail without wrangler.toml or wrangler installed, but it should
@@ -0,0 +1,722 @@
+use crate::cd::find_monorepo_root;
+use crate::config::{AuditConfig, CastConfig};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AuditError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] crate::config::ConfigError),
+    #[error("CD error: {0}")]
+    CdError(#[from] crate::cd::CdError),
+    #[error("Failed to parse {0}: {1}")]
+    ParseError(PathBuf, String),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Audit failed\n{0}")]
+    Failed(Box<AuditReport>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ecosystem::Cargo => write!(f, "cargo"),
+            Ecosystem::Npm => write!(f, "npm"),
+        }
+    }
+}
+
+/// A package pinned in a project's lockfile
+#[derive(Debug, Clone, PartialEq)]
+struct LockedPackage {
+    ecosystem: Ecosystem,
+    name: String,
+    version: String,
+    license: Option<String>,
+    project: PathBuf,
+}
+
+/// A package that resolves to more than one version across the monorepo
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Duplicate {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    /// Each version mapped to the projects that use it
+    pub versions: BTreeMap<String, BTreeSet<PathBuf>>,
+}
+
+/// A package whose license is not in the allowlist (or unknown)
+#[derive(Debug, PartialEq, Serialize)]
+pub struct LicenseFinding {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    pub projects: BTreeSet<PathBuf>,
+}
+
+/// A package version matched by an advisory
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Vulnerability {
+    pub advisory: Advisory,
+    pub version: String,
+    pub projects: BTreeSet<PathBuf>,
+}
+
+/// An entry in the local advisory database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub ecosystem: Ecosystem,
+    pub package: String,
+    /// Semver requirement matching the affected versions (e.g., ">=1.0.0, <1.2.3")
+    pub vulnerable: String,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryDatabase {
+    #[serde(default)]
+    advisories: Vec<Advisory>,
+}
+
+/// Result of auditing every lockfile in the monorepo
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct AuditReport {
+    pub lockfiles: Vec<PathBuf>,
+    pub packages: usize,
+    pub duplicates: Vec<Duplicate>,
+    pub license_violations: Vec<LicenseFinding>,
+    /// Packages whose license could not be determined (reported, but not a failure)
+    pub unknown_licenses: Vec<LicenseFinding>,
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+impl AuditReport {
+    /// Whether the audit should fail (license violations or known vulnerabilities)
+    pub fn has_failures(&self) -> bool {
+        !self.license_violations.is_empty() || !self.vulnerabilities.is_empty()
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Audited {} packages in {} lockfiles",
+            self.packages,
+            self.lockfiles.len()
+        )?;
+
+        if !self.vulnerabilities.is_empty() {
+            writeln!(f, "\nVulnerabilities ({}):", self.vulnerabilities.len())?;
+            for vulnerability in &self.vulnerabilities {
+                let advisory = &vulnerability.advisory;
+                writeln!(
+                    f,
+                    "  ✗ {} {} {}@{}{} [{}]",
+                    advisory.id,
+                    advisory.ecosystem,
+                    advisory.package,
+                    vulnerability.version,
+                    advisory
+                        .title
+                        .as_ref()
+                        .map(|t| format!(": {}", t))
+                        .unwrap_or_default(),
+                    join_paths(&vulnerability.projects)
+                )?;
+            }
+        }
+
+        if !self.license_violations.is_empty() {
+            writeln!(
+                f,
+                "\nLicense violations ({}):",
+                self.license_violations.len()
+            )?;
+            for finding in &self.license_violations {
+                writeln!(f, "  ✗ {}", format_license_finding(finding))?;
+            }
+        }
+
+        if !self.unknown_licenses.is_empty() {
+            writeln!(f, "\nUnknown licenses ({}):", self.unknown_licenses.len())?;
+            for finding in &self.unknown_licenses {
+                writeln!(f, "  ? {}", format_license_finding(finding))?;
+            }
+        }
+
+        if !self.duplicates.is_empty() {
+            writeln!(f, "\nDuplicate versions ({}):", self.duplicates.len())?;
+            for duplicate in &self.duplicates {
+                writeln!(f, "  {} {}", duplicate.ecosystem, duplicate.name)?;
+                for (version, projects) in &duplicate.versions {
+                    writeln!(f, "    {} [{}]", version, join_paths(projects))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn format_license_finding(finding: &LicenseFinding) -> String {
+    format!(
+        "{} {}@{} ({}) [{}]",
+        finding.ecosystem,
+        finding.name,
+        finding.version,
+        finding.license.as_deref().unwrap_or("unknown"),
+        join_paths(&finding.projects)
+    )
+}
+
+fn join_paths(paths: &BTreeSet<PathBuf>) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Audit every Cargo.lock and package-lock.json in the monorepo
+///
+/// This:
+/// 1. Reports packages resolved to different versions across projects
+/// 2. Checks licenses against `allowed_licenses` in the root Cast configuration
+/// 3. Checks versions against the local advisory database configured as `advisory_db`
+///
+/// Cargo licenses are read from the local registry sources (run `cargo fetch` first),
+/// npm licenses are read from package-lock.json.
+pub fn run(working_directory: impl AsRef<Path>) -> Result<AuditReport, AuditError> {
+    let monorepo_root = find_monorepo_root(working_directory.as_ref())?;
+    let config = CastConfig::load_from_dir(&monorepo_root)?
+        .audit
+        .unwrap_or_default();
+
+    let mut lockfiles = Vec::new();
+    find_lockfiles_recursive(&monorepo_root, &mut lockfiles)?;
+    lockfiles.sort();
+
+    let registry_sources = cargo_registry_sources();
+    let mut packages = Vec::new();
+    for lockfile in &lockfiles {
+        let project = lockfile
+            .parent()
+            .and_then(|p| p.strip_prefix(&monorepo_root).ok())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let contents = fs::read_to_string(lockfile)?;
+        let parsed = if lockfile.ends_with("Cargo.lock") {
+            parse_cargo_lock(&contents, &project, &registry_sources)
+        } else {
+            parse_package_lock(&contents, &project)
+        };
+        packages.extend(parsed.map_err(|e| AuditError::ParseError(lockfile.clone(), e))?);
+    }
+
+    let advisories = match &config.advisory_db {
+        Some(path) => load_advisories(&monorepo_root.join(path))?,
+        None => Vec::new(),
+    };
+
+    let mut report = audit_packages(&packages, &config, &advisories);
+    report.lockfiles = lockfiles
+        .iter()
+        .filter_map(|p| p.strip_prefix(&monorepo_root).ok())
+        .map(Path::to_path_buf)
+        .collect();
+    Ok(report)
+}
+
+fn find_lockfiles_recursive(dir: &Path, lockfiles: &mut Vec<PathBuf>) -> io::Result<()> {
+    if let Some(dir_name) = dir.file_name() {
+        let dir_name = dir_name.to_string_lossy();
+        if dir_name == "target" || dir_name == "node_modules" || dir_name == ".git" {
+            return Ok(());
+        }
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_lockfiles_recursive(&path, lockfiles)?;
+        } else if path.ends_with("Cargo.lock") || path.ends_with("package-lock.json") {
+            lockfiles.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn load_advisories(path: &Path) -> Result<Vec<Advisory>, AuditError> {
+    let contents = fs::read_to_string(path)?;
+    let database: AdvisoryDatabase = toml::from_str(&contents)
+        .map_err(|e| AuditError::ParseError(path.to_path_buf(), e.to_string()))?;
+    Ok(database.advisories)
+}
+
+/// Unpacked crate sources in the local cargo registry (used to look up licenses offline)
+fn cargo_registry_sources() -> Vec<PathBuf> {
+    let cargo_home = env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+
+    cargo_home
+        .and_then(|home| fs::read_dir(home.join("registry/src")).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .collect()
+}
+
+fn cargo_license(registry_sources: &[PathBuf], name: &str, version: &str) -> Option<String> {
+    registry_sources.iter().find_map(|source| {
+        let manifest = source
+            .join(format!("{}-{}", name, version))
+            .join("Cargo.toml");
+        let value: toml::Value = toml::from_str(&fs::read_to_string(manifest).ok()?).ok()?;
+        value
+            .get("package")?
+            .get("license")?
+            .as_str()
+            .map(String::from)
+    })
+}
+
+fn parse_cargo_lock(
+    contents: &str,
+    project: &Path,
+    registry_sources: &[PathBuf],
+) -> Result<Vec<LockedPackage>, String> {
+    let value: toml::Value = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+    Ok(value
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        // Packages without a source are the workspace's own crates
+        .filter(|package| package.get("source").is_some())
+        .filter_map(|package| {
+            let name = package.get("name")?.as_str()?.to_string();
+            let version = package.get("version")?.as_str()?.to_string();
+            Some(LockedPackage {
+                ecosystem: Ecosystem::Cargo,
+                license: cargo_license(registry_sources, &name, &version),
+                name,
+                version,
+                project: project.to_path_buf(),
+            })
+        })
+        .collect())
+}
+
+fn parse_package_lock(contents: &str, project: &Path) -> Result<Vec<LockedPackage>, String> {
+    let value: serde_json::Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let mut packages = Vec::new();
+
+    if let Some(entries) = value.get("packages").and_then(|p| p.as_object()) {
+        // lockfileVersion 2 and 3: flat map of install paths
+        for (path, package) in entries {
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                continue; // The root project itself
+            };
+            if package.get("link").and_then(|l| l.as_bool()) == Some(true) {
+                continue;
+            }
+            if let Some(version) = package.get("version").and_then(|v| v.as_str()) {
+                packages.push(LockedPackage {
+                    ecosystem: Ecosystem::Npm,
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    license: package
+                        .get("license")
+                        .and_then(|l| l.as_str())
+                        .map(String::from),
+                    project: project.to_path_buf(),
+                });
+            }
+        }
+    } else if let Some(dependencies) = value.get("dependencies") {
+        // lockfileVersion 1: nested dependency tree without license information
+        collect_v1_dependencies(dependencies, project, &mut packages);
+    }
+
+    Ok(packages)
+}
+
+fn collect_v1_dependencies(
+    dependencies: &serde_json::Value,
+    project: &Path,
+    packages: &mut Vec<LockedPackage>,
+) {
+    for (name, dependency) in dependencies.as_object().into_iter().flatten() {
+        if let Some(version) = dependency.get("version").and_then(|v| v.as_str()) {
+            packages.push(LockedPackage {
+                ecosystem: Ecosystem::Npm,
+                name: name.clone(),
+                version: version.to_string(),
+                license: None,
+                project: project.to_path_buf(),
+            });
+        }
+        if let Some(nested) = dependency.get("dependencies") {
+            collect_v1_dependencies(nested, project, packages);
+        }
+    }
+}
+
+/// Ecosystem, name and version of a locked package
+type PackageKey = (Ecosystem, String, String);
+
+fn audit_packages(
+    packages: &[LockedPackage],
+    config: &AuditConfig,
+    advisories: &[Advisory],
+) -> AuditReport {
+    // Group by package and version so each finding lists every project that uses it
+    let mut grouped: BTreeMap<PackageKey, (Option<String>, BTreeSet<PathBuf>)> = BTreeMap::new();
+    for package in packages {
+        let entry = grouped
+            .entry((
+                package.ecosystem,
+                package.name.clone(),
+                package.version.clone(),
+            ))
+            .or_default();
+        if entry.0.is_none() {
+            entry.0 = package.license.clone();
+        }
+        entry.1.insert(package.project.clone());
+    }
+
+    let mut report = AuditReport {
+        packages: grouped.len(),
+        ..Default::default()
+    };
+
+    let mut versions_by_package: BTreeMap<
+        (Ecosystem, String),
+        BTreeMap<String, BTreeSet<PathBuf>>,
+    > = BTreeMap::new();
+    let advisories_by_package: HashMap<(Ecosystem, &str), Vec<&Advisory>> =
+        advisories.iter().fold(HashMap::new(), |mut map, advisory| {
+            map.entry((advisory.ecosystem, advisory.package.as_str()))
+                .or_default()
+                .push(advisory);
+            map
+        });
+
+    for ((ecosystem, name, version), (license, projects)) in grouped {
+        versions_by_package
+            .entry((ecosystem, name.clone()))
+            .or_default()
+            .insert(version.clone(), projects.clone());
+
+        for advisory in advisories_by_package
+            .get(&(ecosystem, name.as_str()))
+            .into_iter()
+            .flatten()
+        {
+            if is_vulnerable(advisory, &version) {
+                report.vulnerabilities.push(Vulnerability {
+                    advisory: (*advisory).clone(),
+                    version: version.clone(),
+                    projects: projects.clone(),
+                });
+            }
+        }
+
+        if let Some(allowed) = &config.allowed_licenses {
+            let finding = LicenseFinding {
+                ecosystem,
+                name,
+                version,
+                license: license.clone(),
+                projects,
+            };
+            match license {
+                None => report.unknown_licenses.push(finding),
+                Some(license) if !is_license_allowed(&license, allowed) => {
+                    report.license_violations.push(finding)
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    report.duplicates = versions_by_package
+        .into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|((ecosystem, name), versions)| Duplicate {
+            ecosystem,
+            name,
+            versions,
+        })
+        .collect();
+
+    report
+}
+
+fn is_vulnerable(advisory: &Advisory, version: &str) -> bool {
+    match (
+        VersionReq::parse(&advisory.vulnerable),
+        Version::parse(version),
+    ) {
+        (Ok(requirement), Ok(version)) => requirement.matches(&version),
+        _ => false,
+    }
+}
+
+/// Evaluate an SPDX license expression (e.g., "(MIT OR Apache-2.0) AND Unicode-3.0") against an allowlist
+///
+/// The legacy `MIT/Apache-2.0` form is treated as `OR`.
+pub fn is_license_allowed(expression: &str, allowed: &[String]) -> bool {
+    let spaced = expression
+        .replace('/', " OR ")
+        .replace('(', " ( ")
+        .replace(')', " ) ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut position = 0;
+    parse_or(&tokens, &mut position, allowed).unwrap_or(false) && position == tokens.len()
+}
+
+fn parse_or(tokens: &[&str], position: &mut usize, allowed: &[String]) -> Option<bool> {
+    let mut result = parse_and(tokens, position, allowed)?;
+    while tokens.get(*position) == Some(&"OR") {
+        *position += 1;
+        result |= parse_and(tokens, position, allowed)?;
+    }
+    Some(result)
+}
+
+fn parse_and(tokens: &[&str], position: &mut usize, allowed: &[String]) -> Option<bool> {
+    let mut result = parse_license(tokens, position, allowed)?;
+    while tokens.get(*position) == Some(&"AND") {
+        *position += 1;
+        result &= parse_license(tokens, position, allowed)?;
+    }
+    Some(result)
+}
+
+fn parse_license(tokens: &[&str], position: &mut usize, allowed: &[String]) -> Option<bool> {
+    let token = *tokens.get(*position)?;
+    *position += 1;
+
+    if token == "(" {
+        let result = parse_or(tokens, position, allowed)?;
+        if tokens.get(*position) != Some(&")") {
+            return None;
+        }
+        *position += 1;
+        return Some(result);
+    }
+
+    let is_allowed = |id: &str| allowed.iter().any(|a| a.eq_ignore_ascii_case(id));
+    if tokens.get(*position) == Some(&"WITH") {
+        let exception = *tokens.get(*position + 1)?;
+        *position += 2;
+        return Some(is_allowed(&format!("{} WITH {}", token, exception)) || is_allowed(token));
+    }
+
+    Some(is_allowed(token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn allowed(licenses: &[&str]) -> Vec<String> {
+        licenses.iter().map(|l| l.to_string()).collect()
+    }
+
+    fn package(ecosystem: Ecosystem, name: &str, version: &str, project: &str) -> LockedPackage {
+        LockedPackage {
+            ecosystem,
+            name: name.to_string(),
+            version: version.to_string(),
+            license: Some("MIT".to_string()),
+            project: PathBuf::from(project),
+        }
+    }
+
+    #[test]
+    fn test_is_license_allowed_evaluates_expressions() {
+        let list = allowed(&["MIT", "Apache-2.0"]);
+        assert!(is_license_allowed("MIT", &list));
+        assert!(is_license_allowed("MIT OR GPL-3.0", &list));
+        assert!(is_license_allowed("MIT/Apache-2.0", &list));
+        assert!(is_license_allowed("Apache-2.0 WITH LLVM-exception", &list));
+        assert!(is_license_allowed("(MIT OR Apache-2.0) AND MIT", &list));
+        assert!(!is_license_allowed(
+            "(MIT OR Apache-2.0) AND Unicode-3.0",
+            &list
+        ));
+        assert!(!is_license_allowed("GPL-3.0", &list));
+        assert!(!is_license_allowed("MIT AND", &list));
+    }
+
+    #[test]
+    fn test_parse_cargo_lock_skips_local_crates() {
+        let lock = r#"
+version = 4
+
+[[package]]
+name = "cast"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+        let packages = parse_cargo_lock(lock, Path::new("cast"), &[]).unwrap();
+        assert_eq!(
+            packages,
+            vec![LockedPackage {
+                ecosystem: Ecosystem::Cargo,
+                name: "serde".to_string(),
+                version: "1.0.0".to_string(),
+                license: None,
+                project: PathBuf::from("cast"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_package_lock_reads_nested_packages_and_licenses() {
+        let lock = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "ext", "version": "0.0.1" },
+                "node_modules/@scope/a": { "version": "1.0.0", "license": "MIT" },
+                "node_modules/@scope/a/node_modules/b": { "version": "2.0.0" },
+                "node_modules/linked": { "link": true }
+            }
+        }"#;
+        let packages = parse_package_lock(lock, Path::new("ext")).unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "@scope/a");
+        assert_eq!(packages[0].license, Some("MIT".to_string()));
+        assert_eq!(packages[1].name, "b");
+        assert_eq!(packages[1].license, None);
+    }
+
+    #[test]
+    fn test_audit_packages_reports_duplicates_licenses_and_vulnerabilities() {
+        let mut gpl = package(Ecosystem::Cargo, "gpl-crate", "1.0.0", "app");
+        gpl.license = Some("GPL-3.0".to_string());
+        let mut unknown = package(Ecosystem::Npm, "mystery", "1.0.0", "web");
+        unknown.license = None;
+        let packages = vec![
+            package(Ecosystem::Cargo, "serde", "1.0.0", "app"),
+            package(Ecosystem::Cargo, "serde", "1.0.1", "lib"),
+            package(Ecosystem::Cargo, "serde", "1.0.1", "app"),
+            package(Ecosystem::Npm, "serde", "2.0.0", "web"),
+            gpl,
+            unknown,
+        ];
+        let config = AuditConfig {
+            allowed_licenses: Some(allowed(&["MIT"])),
+            advisory_db: None,
+        };
+        let advisories = vec![Advisory {
+            id: "TEST-0001".to_string(),
+            ecosystem: Ecosystem::Cargo,
+            package: "serde".to_string(),
+            vulnerable: "<1.0.1".to_string(),
+            title: Some("Bad".to_string()),
+        }];
+
+        let report = audit_packages(&packages, &config, &advisories);
+        assert_eq!(report.packages, 5);
+        assert!(report.has_failures());
+
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].name, "serde");
+        assert_eq!(report.duplicates[0].ecosystem, Ecosystem::Cargo);
+        assert_eq!(
+            report.duplicates[0].versions["1.0.1"],
+            BTreeSet::from([PathBuf::from("app"), PathBuf::from("lib")])
+        );
+
+        assert_eq!(report.license_violations.len(), 1);
+        assert_eq!(report.license_violations[0].name, "gpl-crate");
+        assert_eq!(report.unknown_licenses.len(), 1);
+        assert_eq!(report.unknown_licenses[0].name, "mystery");
+
+        assert_eq!(report.vulnerabilities.len(), 1);
+        assert_eq!(report.vulnerabilities[0].version, "1.0.0");
+    }
+
+    #[test]
+    fn test_audit_passes_without_allowlist_or_advisories() {
+        let packages = vec![package(Ecosystem::Cargo, "serde", "1.0.0", "app")];
+        let report = audit_packages(&packages, &AuditConfig::default(), &[]);
+        assert!(!report.has_failures());
+        assert!(report.unknown_licenses.is_empty());
+    }
+
+    #[test]
+    fn test_run_reads_lockfiles_and_advisory_database() {
+        let tmp_dir = TempDir::new("test_audit").unwrap();
+        fs::create_dir(tmp_dir.path().join(".git")).unwrap();
+        fs::write(
+            tmp_dir.path().join("Cast.toml"),
+            "[audit]\nadvisory_db = \"advisories.toml\"\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp_dir.path().join("advisories.toml"),
+            "[[advisories]]\nid = \"TEST-0002\"\necosystem = \"npm\"\npackage = \"left-pad\"\nvulnerable = \"<1.3.0\"\n",
+        )
+        .unwrap();
+        let web = tmp_dir.path().join("web");
+        fs::create_dir_all(web.join("node_modules/ignored")).unwrap();
+        fs::write(
+            web.join("package-lock.json"),
+            r#"{"lockfileVersion": 3, "packages": {"node_modules/left-pad": {"version": "1.2.0"}}}"#,
+        )
+        .unwrap();
+        fs::write(
+            web.join("node_modules/ignored/package-lock.json"),
+            "not json",
+        )
+        .unwrap();
+
+        let report = run(&web).unwrap();
+        assert_eq!(
+            report.lockfiles,
+            vec![PathBuf::from("web/package-lock.json")]
+        );
+        assert_eq!(report.vulnerabilities.len(), 1);
+        assert_eq!(
+            report.vulnerabilities[0].projects,
+            BTreeSet::from([PathBuf::from("web")])
+        );
+        assert!(report.to_string().contains("TEST-0002 npm left-pad@1.2.0"));
+        assert!(serde_json::to_string(&report)
+            .unwrap()
+            .contains("\"TEST-0002\""));
+    }
+}
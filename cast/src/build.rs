@@ -1,13 +1,39 @@
-use std::path::Path;
-use std::process::Command;
+use crate::config::CastConfig;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use thiserror::Error;
 
+/// Default directory (relative to the project) where release artifacts are copied
+const DEFAULT_ARTIFACTS_DIRECTORY: &str = "artifacts";
+
+/// Checksum file written alongside the binaries of each target (compatible with `sha256sum -c`)
+const CHECKSUMS_FILE_NAME: &str = "SHA256SUMS";
+
 #[derive(Error, Debug)]
 pub enum BuildError {
     #[error("Cargo build failed")]
     BuildFailed,
+    #[error("Build failed for target: {0}")]
+    TargetBuildFailed(String),
+    #[error("Binary not found after build: {0}")]
+    BinaryNotFound(PathBuf),
+    #[error("Failed to read cargo metadata: {0}")]
+    MetadataError(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] crate::config::ConfigError),
+}
+
+/// A release binary copied into the artifacts tree
+#[derive(Debug, PartialEq, Clone)]
+pub struct BuildArtifact {
+    pub target: String,
+    pub path: PathBuf,
+    pub sha256: String,
 }
 
 /// Run cargo build for a Rust project
@@ -17,6 +43,7 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), BuildError> {
     let status = Command::new("cargo")
         .arg("build")
         .current_dir(working_directory)
+        .stdout(io::stderr())
         .status()?;
 
     if !status.success() {
@@ -26,6 +53,207 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), BuildError> {
     Ok(())
 }
 
+/// Run a release build for every target in the Cast configuration
+///
+/// Without `targets` configured this is a plain `cargo build --release`.
+/// With `targets` configured, each target is built with `cargo` (host target) or
+/// `cross` (other targets, when installed) and every binary is copied to
+/// `<artifacts_dir>/<target>/` together with a `SHA256SUMS` file.
+pub fn release(working_directory: impl AsRef<Path>) -> Result<Vec<BuildArtifact>, BuildError> {
+    let working_directory = working_directory.as_ref();
+    let config = CastConfig::load_from_dir(working_directory)?;
+
+    let targets = config.targets.unwrap_or_default();
+    if targets.is_empty() {
+        let status = Command::new("cargo")
+            .arg("build")
+            .arg("--release")
+            .current_dir(working_directory)
+            .stdout(io::stderr())
+            .status()?;
+
+        if !status.success() {
+            return Err(BuildError::BuildFailed);
+        }
+
+        return Ok(Vec::new());
+    }
+
+    let artifacts_directory = working_directory.join(
+        config
+            .artifacts_dir
+            .as_deref()
+            .unwrap_or(DEFAULT_ARTIFACTS_DIRECTORY),
+    );
+    let metadata = read_cargo_metadata(working_directory)?;
+    let host = host_target()?;
+
+    let mut artifacts = Vec::new();
+    for target in targets {
+        build_target(working_directory, &target, &host)?;
+        artifacts.extend(copy_target_artifacts(
+            &metadata,
+            &target,
+            &artifacts_directory,
+        )?);
+    }
+
+    Ok(artifacts)
+}
+
+/// Build a single target in release mode, using cross for non-host targets when available
+fn build_target(working_directory: &Path, target: &str, host: &str) -> Result<(), BuildError> {
+    let program = if target != host && is_cross_installed() {
+        "cross"
+    } else {
+        "cargo"
+    };
+
+    let status = Command::new(program)
+        .arg("build")
+        .arg("--release")
+        .arg("--target")
+        .arg(target)
+        .current_dir(working_directory)
+        .stdout(io::stderr())
+        .status()?;
+
+    if !status.success() {
+        return Err(BuildError::TargetBuildFailed(target.to_string()));
+    }
+
+    Ok(())
+}
+
+fn is_cross_installed() -> bool {
+    Command::new("cross")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Determine the host target triple from `rustc -vV`
+fn host_target() -> Result<String, BuildError> {
+    let output = Command::new("rustc").arg("-vV").output()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_string())
+        .ok_or_else(|| BuildError::MetadataError("unable to determine host target".to_string()))
+}
+
+/// The parts of `cargo metadata` needed to locate release binaries
+#[derive(Debug, PartialEq)]
+struct BinaryMetadata {
+    target_directory: PathBuf,
+    binaries: Vec<String>,
+}
+
+fn read_cargo_metadata(working_directory: &Path) -> Result<BinaryMetadata, BuildError> {
+    let output = Command::new("cargo")
+        .arg("metadata")
+        .arg("--no-deps")
+        .arg("--format-version")
+        .arg("1")
+        .current_dir(working_directory)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(BuildError::MetadataError(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    parse_cargo_metadata(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Extract the target directory and all binary target names from `cargo metadata` output
+fn parse_cargo_metadata(metadata: &str) -> Result<BinaryMetadata, BuildError> {
+    let json: serde_json::Value =
+        serde_json::from_str(metadata).map_err(|e| BuildError::MetadataError(e.to_string()))?;
+
+    let target_directory = json["target_directory"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or_else(|| BuildError::MetadataError("missing target_directory".to_string()))?;
+
+    let mut binaries: Vec<String> = json["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|package| package["targets"].as_array().into_iter().flatten())
+        .filter(|target| {
+            target["kind"]
+                .as_array()
+                .is_some_and(|kinds| kinds.iter().any(|kind| kind == "bin"))
+        })
+        .filter_map(|target| target["name"].as_str().map(String::from))
+        .collect();
+    binaries.sort();
+    binaries.dedup();
+
+    Ok(BinaryMetadata {
+        target_directory,
+        binaries,
+    })
+}
+
+/// Copy every binary built for a target into `<artifacts_directory>/<target>/` and write checksums
+fn copy_target_artifacts(
+    metadata: &BinaryMetadata,
+    target: &str,
+    artifacts_directory: &Path,
+) -> Result<Vec<BuildArtifact>, BuildError> {
+    let release_directory = metadata.target_directory.join(target).join("release");
+    let destination_directory = artifacts_directory.join(target);
+    fs::create_dir_all(&destination_directory)?;
+
+    let mut artifacts = Vec::new();
+    for binary in &metadata.binaries {
+        let file_name = binary_file_name(binary, target);
+        let source = release_directory.join(&file_name);
+        if !source.exists() {
+            return Err(BuildError::BinaryNotFound(source));
+        }
+
+        let destination = destination_directory.join(&file_name);
+        fs::copy(&source, &destination)?;
+
+        artifacts.push(BuildArtifact {
+            target: target.to_string(),
+            sha256: sha256_file(&destination)?,
+            path: destination,
+        });
+    }
+
+    let checksums: String = artifacts
+        .iter()
+        .filter_map(|artifact| {
+            let file_name = artifact.path.file_name()?.to_string_lossy();
+            Some(format!("{}  {}\n", artifact.sha256, file_name))
+        })
+        .collect();
+    fs::write(destination_directory.join(CHECKSUMS_FILE_NAME), checksums)?;
+
+    Ok(artifacts)
+}
+
+fn binary_file_name(binary: &str, target: &str) -> String {
+    if target.contains("windows") {
+        format!("{}.exe", binary)
+    } else {
+        binary.to_string()
+    }
+}
+
+fn sha256_file(path: &Path) -> Result<String, BuildError> {
+    let digest = Sha256::digest(fs::read(path)?);
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,4 +309,108 @@ mod tests {
             panic!("Expected BuildFailed error");
         }
     }
+
+    #[test]
+    fn test_parse_cargo_metadata_finds_binaries() {
+        let metadata = r#"{
+            "target_directory": "/repo/target",
+            "packages": [{
+                "targets": [
+                    {"name": "tool", "kind": ["bin"]},
+                    {"name": "tool_lib", "kind": ["lib"]},
+                    {"name": "helper", "kind": ["bin"]}
+                ]
+            }]
+        }"#;
+        let parsed = parse_cargo_metadata(metadata).unwrap();
+        assert_eq!(parsed.target_directory, PathBuf::from("/repo/target"));
+        assert_eq!(parsed.binaries, vec!["helper", "tool"]);
+    }
+
+    #[test]
+    fn test_binary_file_name_adds_exe_for_windows() {
+        assert_eq!(
+            binary_file_name("tool", "x86_64-pc-windows-gnu"),
+            "tool.exe"
+        );
+        assert_eq!(binary_file_name("tool", "x86_64-unknown-linux-gnu"), "tool");
+    }
+
+    #[test]
+    fn test_copy_target_artifacts_writes_binaries_and_checksums() {
+        let tmp_dir = TempDir::new("test_build_artifacts").unwrap();
+        let target = "x86_64-unknown-linux-gnu";
+        let release_dir = tmp_dir.path().join("target").join(target).join("release");
+        fs::create_dir_all(&release_dir).unwrap();
+        fs::write(release_dir.join("tool"), "hello").unwrap();
+
+        let metadata = BinaryMetadata {
+            target_directory: tmp_dir.path().join("target"),
+            binaries: vec!["tool".to_string()],
+        };
+        let artifacts_dir = tmp_dir.path().join("artifacts");
+        let artifacts = copy_target_artifacts(&metadata, target, &artifacts_dir).unwrap();
+
+        let expected_sha = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(
+            artifacts,
+            vec![BuildArtifact {
+                target: target.to_string(),
+                path: artifacts_dir.join(target).join("tool"),
+                sha256: expected_sha.to_string(),
+            }]
+        );
+        assert_eq!(
+            fs::read_to_string(artifacts_dir.join(target).join(CHECKSUMS_FILE_NAME)).unwrap(),
+            format!("{}  tool\n", expected_sha)
+        );
+    }
+
+    #[test]
+    fn test_copy_target_artifacts_fails_when_binary_missing() {
+        let tmp_dir = TempDir::new("test_build_artifacts_missing").unwrap();
+        let metadata = BinaryMetadata {
+            target_directory: tmp_dir.path().join("target"),
+            binaries: vec!["tool".to_string()],
+        };
+        let result = copy_target_artifacts(
+            &metadata,
+            "x86_64-unknown-linux-gnu",
+            &tmp_dir.path().join("artifacts"),
+        );
+        assert!(matches!(result, Err(BuildError::BinaryNotFound(_))));
+    }
+
+    #[test]
+    fn test_release_builds_configured_host_target_into_artifacts() {
+        let tmp_dir = TempDir::new("test_build_release").unwrap();
+        let host = host_target().unwrap();
+
+        fs::write(
+            tmp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"tool\"\nversion = \"0.1.0\"\nedition = \"2021\"",
+        )
+        .unwrap();
+        fs::write(
+            tmp_dir.path().join("Cast.toml"),
+            format!("targets = [\"{}\"]\nartifacts_dir = \"dist\"", host),
+        )
+        .unwrap();
+        fs::create_dir_all(tmp_dir.path().join("src")).unwrap();
+        fs::write(tmp_dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let artifacts = release(tmp_dir.path()).unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].target, host);
+        assert!(artifacts[0]
+            .path
+            .starts_with(tmp_dir.path().join("dist").join(&host)));
+        assert!(artifacts[0].path.exists());
+        assert!(tmp_dir
+            .path()
+            .join("dist")
+            .join(&host)
+            .join(CHECKSUMS_FILE_NAME)
+            .exists());
+    }
 }
@@ -50,7 +50,7 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), CdError> {
 }
 
 /// Find the monorepo root by walking up the directory tree looking for a .git directory
-fn find_monorepo_root(working_directory: &Path) -> Result<PathBuf, CdError> {
+pub(crate) fn find_monorepo_root(working_directory: &Path) -> Result<PathBuf, CdError> {
     let mut current = Some(working_directory);
 
     while let Some(dir) = current {
@@ -1,5 +1,8 @@
 use crate::build;
+use crate::config::CastConfig;
+use crate::coverage;
 use crate::test;
+use std::io;
 use std::path::Path;
 use std::process::Command;
 use thiserror::Error;
@@ -14,6 +17,12 @@ pub enum CiError {
     BuildError(#[from] build::BuildError),
     #[error("Cargo test failed: {0}")]
     TestError(#[from] test::TestError),
+    #[error("Coverage failed: {0}")]
+    CoverageError(#[from] coverage::CoverageError),
+    #[error("Line coverage {actual:.2}% is below the minimum of {minimum:.2}%")]
+    CoverageBelowThreshold { actual: f64, minimum: f64 },
+    #[error("Config error: {0}")]
+    ConfigError(#[from] crate::config::ConfigError),
     #[error("npm install failed")]
     NpmInstallError,
     #[error("npm lint failed")]
@@ -58,6 +67,7 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), CiError> {
 /// 2. cargo clippy -- -D warnings
 /// 3. cast build (cargo build)
 /// 4. cast test (cargo test)
+/// 5. coverage (if a `[coverage]` section is present in the Cast configuration)
 fn run_rust_ci(working_directory: &Path) -> Result<(), CiError> {
     // Run cargo fmt --check
     run_fmt_check(working_directory)?;
@@ -71,6 +81,18 @@ fn run_rust_ci(working_directory: &Path) -> Result<(), CiError> {
     // Run cast test
     test::run(working_directory)?;
 
+    // Run coverage if the project opted in
+    let config = CastConfig::load_from_dir(working_directory)?;
+    if let Some(coverage_config) = config.coverage {
+        let summary = coverage::run(working_directory)?;
+        if !summary.meets_threshold(&coverage_config) {
+            return Err(CiError::CoverageBelowThreshold {
+                actual: summary.line_percent(),
+                minimum: coverage_config.min_line_coverage.unwrap_or_default(),
+            });
+        }
+    }
+
     Ok(())
 }
 
@@ -121,6 +143,7 @@ fn run_npm_command(working_directory: &Path, command: &str) -> Result<(), std::i
         .arg("run")
         .arg(command)
         .current_dir(working_directory)
+        .stdout(io::stderr())
         .status()?;
 
     if !status.success() {
@@ -136,6 +159,7 @@ fn run_npm_install(working_directory: &Path) -> Result<(), std::io::Error> {
     let status = Command::new("npm")
         .arg("ci")
         .current_dir(working_directory)
+        .stdout(io::stderr())
         .status()?;
 
     if !status.success() {
@@ -145,11 +169,12 @@ fn run_npm_install(working_directory: &Path) -> Result<(), std::io::Error> {
     Ok(())
 }
 
-fn run_fmt_check(working_directory: &Path) -> Result<(), CiError> {
+pub(crate) fn run_fmt_check(working_directory: &Path) -> Result<(), CiError> {
     let status = Command::new("cargo")
         .arg("fmt")
         .arg("--check")
         .current_dir(working_directory)
+        .stdout(io::stderr())
         .status()?;
 
     if !status.success() {
@@ -159,13 +184,14 @@ fn run_fmt_check(working_directory: &Path) -> Result<(), CiError> {
     Ok(())
 }
 
-fn run_clippy(working_directory: &Path) -> Result<(), CiError> {
+pub(crate) fn run_clippy(working_directory: &Path) -> Result<(), CiError> {
     let status = Command::new("cargo")
         .arg("clippy")
         .arg("--")
         .arg("-D")
         .arg("warnings")
         .current_dir(working_directory)
+        .stdout(io::stderr())
         .status()?;
 
     if !status.success() {
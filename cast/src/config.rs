@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 use thiserror::Error;
@@ -20,6 +21,46 @@ pub struct CastConfig {
     /// The type of project (e.g., "static_website", "web_app", "iac", "library", "binary")
     #[serde(default)]
     pub project_type: Option<String>,
+    /// Target triples built by `cast build --release` (e.g., "x86_64-unknown-linux-gnu")
+    #[serde(default)]
+    pub targets: Option<Vec<String>>,
+    /// Directory (relative to the project) where release binaries are copied, defaults to "artifacts"
+    #[serde(default)]
+    pub artifacts_dir: Option<String>,
+    /// Code coverage settings for `cast ci` (coverage only runs when this section is present)
+    #[serde(default)]
+    pub coverage: Option<CoverageConfig>,
+    /// Dependency audit settings for `cast audit` (read from the monorepo root configuration)
+    #[serde(default)]
+    pub audit: Option<AuditConfig>,
+    /// Local workflow runner settings for `cast workflows run` (read from the monorepo root configuration)
+    #[serde(default)]
+    pub workflows: Option<WorkflowsConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Default, Clone)]
+pub struct CoverageConfig {
+    /// Minimum percentage of lines that must be covered by tests (e.g., 80.0)
+    #[serde(default)]
+    pub min_line_coverage: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Default, Clone)]
+pub struct AuditConfig {
+    /// SPDX license identifiers dependencies may use (licenses are not checked when unset)
+    #[serde(default)]
+    pub allowed_licenses: Option<Vec<String>>,
+    /// Path (relative to the monorepo root) to a TOML file of `[[advisories]]`
+    #[serde(default)]
+    pub advisory_db: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Default, Clone)]
+pub struct WorkflowsConfig {
+    /// Shell scripts run in place of `uses:` actions, keyed by action name without the `@ref`
+    /// (an empty script skips the action)
+    #[serde(default)]
+    pub substitutes: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -55,6 +96,11 @@ impl CastConfig {
             || self.framework.is_some()
             || self.deploys.is_some()
             || self.project_type.is_some()
+            || self.targets.is_some()
+            || self.artifacts_dir.is_some()
+            || self.coverage.is_some()
+            || self.audit.is_some()
+            || self.workflows.is_some()
     }
 
     /// Load Cast configuration from a directory, checking Cargo.toml first, then Cast.toml
@@ -334,6 +380,7 @@ deploys = ["deploy1", "deploy2"]
             framework: None,
             deploys: None,
             project_type: None,
+            ..Default::default()
         };
 
         config.save(&config_path).unwrap();
@@ -357,6 +404,7 @@ deploys = ["deploy1", "deploy2"]
             framework: None,
             deploys: None,
             project_type: None,
+            ..Default::default()
         };
 
         config.save(&config_path).unwrap();
@@ -433,6 +481,7 @@ deploys = ["deploy1", "deploy2"]
             framework: None,
             deploys: None,
             project_type: None,
+            ..Default::default()
         };
 
         config.save(&config_path).unwrap();
@@ -456,6 +505,7 @@ deploys = ["deploy1", "deploy2"]
             framework: None,
             deploys: None,
             project_type: None,
+            ..Default::default()
         };
 
         config.save(&config_path).unwrap();
@@ -497,6 +547,7 @@ deploys = ["deploy1", "deploy2"]
             framework: Some("dioxus".to_string()),
             deploys: None,
             project_type: None,
+            ..Default::default()
         };
 
         config.save(&config_path).unwrap();
@@ -524,6 +575,7 @@ deploys = ["deploy1", "deploy2"]
                 framework: Some(framework.to_string()),
                 deploys: None,
                 project_type: None,
+                ..Default::default()
             };
 
             config.save(&config_path).unwrap();
@@ -599,6 +651,7 @@ deploys = ["deploy1", "deploy2"]
             framework: None,
             deploys: Some(vec!["pane-cloudflare".to_string()]),
             project_type: None,
+            ..Default::default()
         };
 
         config.save(&config_path).unwrap();
@@ -662,6 +715,7 @@ deploys = ["deploy1", "deploy2"]
             framework: None,
             deploys: None,
             project_type: Some("static_website".to_string()),
+            ..Default::default()
         };
 
         config.save(&config_path).unwrap();
@@ -707,4 +761,97 @@ project_type = "library"
         let config = CastConfig::load_from_cargo_toml(&cargo_path).unwrap();
         assert_eq!(config.project_type, Some("library".to_string()));
     }
+
+    #[test]
+    fn test_parse_config_with_coverage() {
+        let config: CastConfig = toml::from_str("[coverage]\nmin_line_coverage = 80.5").unwrap();
+        assert_eq!(
+            config.coverage,
+            Some(CoverageConfig {
+                min_line_coverage: Some(80.5)
+            })
+        );
+        assert!(config.has_cast_metadata());
+    }
+
+    #[test]
+    fn test_parse_config_with_empty_coverage_section() {
+        let config: CastConfig = toml::from_str("[coverage]").unwrap();
+        assert_eq!(config.coverage, Some(CoverageConfig::default()));
+    }
+
+    #[test]
+    fn test_load_from_cargo_toml_with_coverage() {
+        let tmp_dir = TempDir::new("test_cargo_coverage").unwrap();
+        let cargo_path = tmp_dir.path().join("Cargo.toml");
+
+        let cargo_content = r#"
+[package]
+name = "test"
+version = "0.1.0"
+
+[package.metadata.cast.coverage]
+min_line_coverage = 70.0
+"#;
+        fs::write(&cargo_path, cargo_content).unwrap();
+
+        let config = CastConfig::load_from_cargo_toml(&cargo_path).unwrap();
+        assert_eq!(
+            config.coverage.and_then(|c| c.min_line_coverage),
+            Some(70.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_config_with_targets_and_artifacts_dir() {
+        let config: CastConfig = toml::from_str(
+            "targets = [\"x86_64-unknown-linux-gnu\", \"aarch64-apple-darwin\"]\nartifacts_dir = \"dist\"",
+        )
+        .unwrap();
+        assert_eq!(
+            config.targets,
+            Some(vec![
+                "x86_64-unknown-linux-gnu".to_string(),
+                "aarch64-apple-darwin".to_string()
+            ])
+        );
+        assert_eq!(config.artifacts_dir, Some("dist".to_string()));
+    }
+
+    #[test]
+    fn test_parse_config_with_audit() {
+        let config: CastConfig = toml::from_str(
+            "[audit]\nallowed_licenses = [\"MIT\", \"Apache-2.0\"]\nadvisory_db = \"advisories.toml\"",
+        )
+        .unwrap();
+        assert_eq!(
+            config.audit,
+            Some(AuditConfig {
+                allowed_licenses: Some(vec!["MIT".to_string(), "Apache-2.0".to_string()]),
+                advisory_db: Some("advisories.toml".to_string()),
+            })
+        );
+        assert!(config.has_cast_metadata());
+    }
+
+    #[test]
+    fn test_parse_config_with_workflow_substitutes() {
+        let config: CastConfig = toml::from_str(
+            "[workflows.substitutes]\n\"actions/setup-node\" = \"node --version\"\n\"actions/cache\" = \"\"",
+        )
+        .unwrap();
+        let substitutes = config
+            .workflows
+            .as_ref()
+            .unwrap()
+            .substitutes
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            substitutes.get("actions/setup-node"),
+            Some(&"node --version".to_string())
+        );
+        assert_eq!(substitutes.get("actions/cache"), Some(&String::new()));
+        assert!(config.has_cast_metadata());
+    }
 }
@@ -0,0 +1,355 @@
+use crate::config::CoverageConfig;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use thiserror::Error;
+
+/// Directory (relative to the project) where coverage artifacts are written
+const COVERAGE_DIRECTORY: &str = "target/coverage";
+
+#[derive(Error, Debug)]
+pub enum CoverageError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("UTF-8 error: {0}")]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+    #[error("llvm-tools not found - install them with: rustup component add llvm-tools")]
+    LlvmToolsNotInstalled,
+    #[error("Instrumented cargo test failed")]
+    TestFailed,
+    #[error("No profile data was generated by the test run")]
+    NoProfileData,
+    #[error("llvm-profdata merge failed")]
+    MergeFailed,
+    #[error("llvm-cov {0} failed")]
+    ReportFailed(String),
+}
+
+/// Line coverage totals for a project
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CoverageSummary {
+    pub lines_found: u64,
+    pub lines_hit: u64,
+}
+
+impl CoverageSummary {
+    /// Percentage of instrumented lines that were executed (100% when nothing was instrumented)
+    pub fn line_percent(&self) -> f64 {
+        if self.lines_found == 0 {
+            return 100.0;
+        }
+        self.lines_hit as f64 / self.lines_found as f64 * 100.0
+    }
+
+    /// Check whether the line coverage meets the configured minimum (if any)
+    pub fn meets_threshold(&self, config: &CoverageConfig) -> bool {
+        match config.min_line_coverage {
+            Some(minimum) => self.line_percent() >= minimum,
+            None => true,
+        }
+    }
+}
+
+/// Run tests with LLVM source-based coverage instrumentation
+///
+/// This:
+/// 1. Runs `cargo test` with `-C instrument-coverage` in a dedicated target directory
+/// 2. Merges the raw profiles with `llvm-profdata`
+/// 3. Writes `lcov.info` and `summary.txt` to `target/coverage` with `llvm-cov`
+///
+/// Requires the rustup `llvm-tools` component, no network access is needed.
+pub fn run(working_directory: impl AsRef<Path>) -> Result<CoverageSummary, CoverageError> {
+    let working_directory = working_directory.as_ref();
+    let llvm_tools = find_llvm_tools_dir()?;
+
+    let coverage_directory = working_directory.join(COVERAGE_DIRECTORY);
+    let profraw_directory = coverage_directory.join("profraw");
+    // Start from a clean slate so stale profiles don't leak into the report
+    if profraw_directory.exists() {
+        fs::remove_dir_all(&profraw_directory)?;
+    }
+    fs::create_dir_all(&profraw_directory)?;
+
+    let objects = build_instrumented_tests(working_directory, &coverage_directory)?;
+    run_instrumented_tests(working_directory, &coverage_directory, &profraw_directory)?;
+
+    let profdata = coverage_directory.join("coverage.profdata");
+    merge_profiles(&llvm_tools, &profraw_directory, &profdata)?;
+
+    let lcov = run_llvm_cov(
+        &llvm_tools,
+        "export",
+        &["-format=lcov"],
+        &profdata,
+        &objects,
+    )?;
+    fs::write(coverage_directory.join("lcov.info"), &lcov)?;
+
+    let summary = run_llvm_cov(&llvm_tools, "report", &[], &profdata, &objects)?;
+    fs::write(coverage_directory.join("summary.txt"), &summary)?;
+    eprintln!("{}", summary);
+
+    Ok(parse_lcov_summary(&lcov))
+}
+
+/// Locate the directory containing llvm-profdata and llvm-cov from the rustup llvm-tools component
+fn find_llvm_tools_dir() -> Result<PathBuf, CoverageError> {
+    let sysroot = Command::new("rustc")
+        .arg("--print")
+        .arg("sysroot")
+        .output()?;
+    let version = Command::new("rustc").arg("-vV").output()?;
+    if !sysroot.status.success() || !version.status.success() {
+        return Err(CoverageError::LlvmToolsNotInstalled);
+    }
+
+    let sysroot = String::from_utf8(sysroot.stdout)?;
+    let version = String::from_utf8(version.stdout)?;
+    let host = version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .ok_or(CoverageError::LlvmToolsNotInstalled)?;
+
+    let tools_dir = Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(host.trim())
+        .join("bin");
+
+    if !tools_dir.join(tool_file_name("llvm-profdata")).exists()
+        || !tools_dir.join(tool_file_name("llvm-cov")).exists()
+    {
+        return Err(CoverageError::LlvmToolsNotInstalled);
+    }
+
+    Ok(tools_dir)
+}
+
+fn tool_file_name(tool: &str) -> String {
+    format!("{}{}", tool, std::env::consts::EXE_SUFFIX)
+}
+
+/// Environment shared by the instrumented build and test run so cargo doesn't rebuild between them
+fn instrumented_cargo_test(working_directory: &Path, coverage_directory: &Path) -> Command {
+    let mut cmd = Command::new("cargo");
+    cmd.arg("test")
+        .current_dir(working_directory)
+        .env("RUSTFLAGS", "-C instrument-coverage")
+        .env("CARGO_TARGET_DIR", coverage_directory.join("target"));
+    cmd
+}
+
+/// Build the instrumented test binaries and return their paths (used as llvm-cov objects)
+fn build_instrumented_tests(
+    working_directory: &Path,
+    coverage_directory: &Path,
+) -> Result<Vec<PathBuf>, CoverageError> {
+    let output = instrumented_cargo_test(working_directory, coverage_directory)
+        .arg("--no-run")
+        .arg("--message-format=json")
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(CoverageError::TestFailed);
+    }
+
+    Ok(parse_test_executables(&String::from_utf8(output.stdout)?))
+}
+
+fn run_instrumented_tests(
+    working_directory: &Path,
+    coverage_directory: &Path,
+    profraw_directory: &Path,
+) -> Result<(), CoverageError> {
+    let status = instrumented_cargo_test(working_directory, coverage_directory)
+        .env(
+            "LLVM_PROFILE_FILE",
+            profraw_directory.join("cast-%p-%m.profraw"),
+        )
+        .stdout(io::stderr())
+        .status()?;
+
+    if !status.success() {
+        return Err(CoverageError::TestFailed);
+    }
+
+    Ok(())
+}
+
+/// Extract test executables from `cargo test --no-run --message-format=json` output
+fn parse_test_executables(cargo_messages: &str) -> Vec<PathBuf> {
+    cargo_messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .filter(|message| message["profile"]["test"] == true)
+        .filter_map(|message| message["executable"].as_str().map(PathBuf::from))
+        .collect()
+}
+
+fn merge_profiles(
+    llvm_tools: &Path,
+    profraw_directory: &Path,
+    profdata: &Path,
+) -> Result<(), CoverageError> {
+    let mut profraw_files: Vec<PathBuf> = fs::read_dir(profraw_directory)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("profraw"))
+        .collect();
+    profraw_files.sort();
+
+    if profraw_files.is_empty() {
+        return Err(CoverageError::NoProfileData);
+    }
+
+    let status = Command::new(llvm_tools.join(tool_file_name("llvm-profdata")))
+        .arg("merge")
+        .arg("-sparse")
+        .args(&profraw_files)
+        .arg("-o")
+        .arg(profdata)
+        .stdout(io::stderr())
+        .status()?;
+
+    if !status.success() {
+        return Err(CoverageError::MergeFailed);
+    }
+
+    Ok(())
+}
+
+/// Directories whose sources are left out of coverage: dependencies, std and generated code
+const IGNORED_SOURCE_DIRECTORIES: &[&[&str]] = &[&[".cargo", "registry"], &["rustc"], &["target"]];
+
+/// Regex matching file paths inside any of the ignored directories, with each component escaped
+fn ignore_filename_regex() -> String {
+    IGNORED_SOURCE_DIRECTORIES
+        .iter()
+        .map(|components| {
+            let escaped: Vec<String> = components.iter().map(|c| regex::escape(c)).collect();
+            format!("/{}/", escaped.join("/"))
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Run an llvm-cov subcommand over all test objects, ignoring dependency and std sources
+fn run_llvm_cov(
+    llvm_tools: &Path,
+    subcommand: &str,
+    extra_args: &[&str],
+    profdata: &Path,
+    objects: &[PathBuf],
+) -> Result<String, CoverageError> {
+    let mut cmd = Command::new(llvm_tools.join(tool_file_name("llvm-cov")));
+    cmd.arg(subcommand)
+        .args(extra_args)
+        .arg(format!("-instr-profile={}", profdata.display()))
+        .arg(format!(
+            "-ignore-filename-regex={}",
+            ignore_filename_regex()
+        ));
+
+    for (index, object) in objects.iter().enumerate() {
+        if index > 0 {
+            cmd.arg("-object");
+        }
+        cmd.arg(object);
+    }
+
+    let output = cmd.stderr(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(CoverageError::ReportFailed(subcommand.to_string()));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Total the `LF` (lines found) and `LH` (lines hit) records of an lcov report
+fn parse_lcov_summary(lcov: &str) -> CoverageSummary {
+    let mut summary = CoverageSummary {
+        lines_found: 0,
+        lines_hit: 0,
+    };
+
+    for line in lcov.lines() {
+        if let Some(count) = line.strip_prefix("LF:") {
+            summary.lines_found += count.trim().parse::<u64>().unwrap_or(0);
+        } else if let Some(count) = line.strip_prefix("LH:") {
+            summary.lines_hit += count.trim().parse::<u64>().unwrap_or(0);
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_filename_regex_escapes_path_components() {
+        let pattern = ignore_filename_regex();
+        assert_eq!(pattern, r"/\.cargo/registry/|/rustc/|/target/");
+
+        let regex = regex::Regex::new(&pattern).unwrap();
+        assert!(regex.is_match("/home/me/.cargo/registry/src/serde/lib.rs"));
+        assert!(regex.is_match("/home/me/app/target/debug/build/out.rs"));
+        assert!(!regex.is_match("/home/me/xcargo/registry/src/lib.rs"));
+        assert!(!regex.is_match("/home/me/app/src/main.rs"));
+    }
+
+    #[test]
+    fn test_parse_lcov_summary_totals_all_files() {
+        let lcov = "SF:src/lib.rs\nDA:1,1\nLF:10\nLH:8\nend_of_record\nSF:src/main.rs\nLF:10\nLH:2\nend_of_record\n";
+        let summary = parse_lcov_summary(lcov);
+        assert_eq!(summary.lines_found, 20);
+        assert_eq!(summary.lines_hit, 10);
+        assert_eq!(summary.line_percent(), 50.0);
+    }
+
+    #[test]
+    fn test_line_percent_is_full_when_nothing_instrumented() {
+        let summary = parse_lcov_summary("");
+        assert_eq!(summary.line_percent(), 100.0);
+    }
+
+    #[test]
+    fn test_meets_threshold() {
+        let summary = CoverageSummary {
+            lines_found: 100,
+            lines_hit: 75,
+        };
+
+        let no_minimum = CoverageConfig::default();
+        assert!(summary.meets_threshold(&no_minimum));
+
+        let below = CoverageConfig {
+            min_line_coverage: Some(80.0),
+        };
+        assert!(!summary.meets_threshold(&below));
+
+        let exact = CoverageConfig {
+            min_line_coverage: Some(75.0),
+        };
+        assert!(summary.meets_threshold(&exact));
+    }
+
+    #[test]
+    fn test_parse_test_executables_only_returns_test_artifacts() {
+        let messages = concat!(
+            r#"{"reason":"compiler-artifact","profile":{"test":false},"executable":null}"#,
+            "\n",
+            r#"{"reason":"compiler-artifact","profile":{"test":true},"executable":"/tmp/target/debug/deps/test-abc"}"#,
+            "\n",
+            r#"{"reason":"build-finished","success":true}"#,
+            "\n",
+        );
+        assert_eq!(
+            parse_test_executables(messages),
+            vec![PathBuf::from("/tmp/target/debug/deps/test-abc")]
+        );
+    }
+}
@@ -0,0 +1,531 @@
+use crate::executor::find_cast_toml;
+use crate::issues::{self, IssueFilter};
+use crate::{plugins, sessions};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use std::{env, fs};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// How often the sessions directory is checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Requests the daemon answers, reported by `initialize`
+const METHODS: &[&str] = &[
+    "initialize",
+    "shutdown",
+    "session/state",
+    "projects/list",
+    "issues/list",
+    "issues/next",
+    "ci/status",
+    "ci/run",
+];
+
+/// Notifications the daemon pushes, reported by `initialize`
+const NOTIFICATIONS: &[&str] = &["session/didChange", "ci/didChange"];
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+#[derive(Error, Debug)]
+pub enum DaemonError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Protocol error: {0}")]
+    ProtocolError(String),
+}
+
+/// Error returned to the client in a JSON-RPC response
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn internal(error: impl ToString) -> Self {
+        RpcError {
+            code: INTERNAL_ERROR,
+            message: error.to_string(),
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        RpcError {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+}
+
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+struct Daemon {
+    working_directory: PathBuf,
+    /// Where sessions are read from: the nearest directory with a Cast.toml, like `cast session`
+    session_directory: PathBuf,
+    writer: SharedWriter,
+    /// Latest `ci/run` result per requested project (`.` for the working directory's project)
+    ci_status: Arc<Mutex<BTreeMap<String, Value>>>,
+}
+
+/// Serve JSON-RPC 2.0 on stdin/stdout until the client sends `exit` or closes stdin
+///
+/// Messages are framed like the Language Server Protocol, with a `Content-Length` header. While
+/// running, the daemon pushes `session/didChange` whenever the session logs change and
+/// `ci/didChange` when a `ci/run` finishes.
+pub fn run(working_directory: impl AsRef<Path>) -> Result<(), DaemonError> {
+    serve(
+        working_directory.as_ref(),
+        io::stdin().lock(),
+        Box::new(io::stdout()),
+    )
+}
+
+fn serve(
+    working_directory: &Path,
+    mut reader: impl BufRead,
+    writer: Box<dyn Write + Send>,
+) -> Result<(), DaemonError> {
+    let daemon = Daemon {
+        working_directory: working_directory.to_path_buf(),
+        session_directory: find_cast_toml(working_directory)
+            .unwrap_or(working_directory)
+            .to_path_buf(),
+        writer: Arc::new(Mutex::new(writer)),
+        ci_status: Arc::new(Mutex::new(BTreeMap::new())),
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let watcher = daemon.watch_sessions(Arc::clone(&stop));
+
+    let result = daemon.serve_messages(&mut reader);
+
+    stop.store(true, Ordering::Relaxed);
+    if watcher.join().is_err() {
+        eprintln!("cast daemon: session watcher panicked");
+    }
+    result
+}
+
+impl Daemon {
+    fn serve_messages(&self, reader: &mut impl BufRead) -> Result<(), DaemonError> {
+        while let Some(text) = read_message(reader)? {
+            let message: Value = match serde_json::from_str(&text) {
+                Ok(message) => message,
+                Err(e) => {
+                    self.send(&error_response(
+                        Value::Null,
+                        RpcError {
+                            code: PARSE_ERROR,
+                            message: e.to_string(),
+                        },
+                    ))?;
+                    continue;
+                }
+            };
+
+            let id = message.get("id").cloned();
+            let Some(method) = message.get("method").and_then(Value::as_str) else {
+                if let Some(id) = id {
+                    self.send(&error_response(
+                        id,
+                        RpcError {
+                            code: INVALID_REQUEST,
+                            message: "missing method".into(),
+                        },
+                    ))?;
+                }
+                continue;
+            };
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            match (method, id) {
+                ("exit", _) => break,
+                // CI can take minutes, so it answers from its own thread
+                ("ci/run", Some(id)) => self.run_ci(id, &params),
+                (method, Some(id)) => {
+                    let response = match self.dispatch(method, &params) {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err(error) => error_response(id, error),
+                    };
+                    self.send(&response)?;
+                }
+                // Notifications other than `exit` need no answer
+                (_, None) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn dispatch(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({
+                "cast_version": env!("CARGO_PKG_VERSION"),
+                "working_directory": self.working_directory.display().to_string(),
+                "session_directory": self.session_directory.display().to_string(),
+                "methods": METHODS,
+                "notifications": NOTIFICATIONS,
+            })),
+            "shutdown" => Ok(Value::Null),
+            "session/state" => self.session_state(),
+            "projects/list" => {
+                let context =
+                    plugins::context(&self.working_directory).map_err(RpcError::internal)?;
+                Ok(json!({
+                    "project": context.project,
+                    "projects": context.projects,
+                }))
+            }
+            "issues/list" => {
+                let filter = issue_filter(params)?;
+                let index = issues::run(&self.working_directory).map_err(RpcError::internal)?;
+                serde_json::to_value(index.filter(&filter)).map_err(RpcError::internal)
+            }
+            "issues/next" => {
+                let index = issues::run(&self.working_directory).map_err(RpcError::internal)?;
+                serde_json::to_value(index.next()).map_err(RpcError::internal)
+            }
+            "ci/status" => {
+                let status = self.ci_status.lock().map_err(RpcError::internal)?;
+                Ok(json!(*status))
+            }
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("unknown method '{}'", method),
+            }),
+        }
+    }
+
+    fn session_state(&self) -> Result<Value, RpcError> {
+        let state = sessions::current(&self.session_directory).map_err(RpcError::internal)?;
+        serde_json::to_value(state).map_err(RpcError::internal)
+    }
+
+    /// Run `cast ci --output json` in the background, answering `id` and pushing `ci/didChange`
+    /// when it finishes
+    fn run_ci(&self, id: Value, params: &Value) {
+        let project = match params.get("project") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(project)) => Some(project.clone()),
+            Some(_) => {
+                let error = RpcError::invalid_params("project must be a string");
+                send(&self.writer, &error_response(id, error));
+                return;
+            }
+        };
+        let working_directory = self.working_directory.clone();
+        let writer = Arc::clone(&self.writer);
+        let ci_status = Arc::clone(&self.ci_status);
+
+        thread::spawn(move || {
+            let response = match run_ci_command(&working_directory, project.as_deref()) {
+                Ok(result) => {
+                    let key = project.unwrap_or_else(|| ".".to_string());
+                    let status = json!({
+                        "project": key,
+                        "finished_at": Utc::now().to_rfc3339(),
+                        "result": result,
+                    });
+                    if let Ok(mut ci_status) = ci_status.lock() {
+                        ci_status.insert(key, status.clone());
+                    }
+                    send(&writer, &notification("ci/didChange", status));
+                    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+                }
+                Err(e) => error_response(id, RpcError::internal(e)),
+            };
+            send(&writer, &response);
+        });
+    }
+
+    /// Push `session/didChange` whenever a session log is added, removed or written to
+    fn watch_sessions(&self, stop: Arc<AtomicBool>) -> thread::JoinHandle<()> {
+        let session_directory = self.session_directory.clone();
+        let writer = Arc::clone(&self.writer);
+
+        thread::spawn(move || {
+            let mut previous = sessions_fingerprint(&session_directory);
+            while !stop.load(Ordering::Relaxed) {
+                thread::sleep(WATCH_INTERVAL);
+                let current = sessions_fingerprint(&session_directory);
+                if current == previous {
+                    continue;
+                }
+                previous = current;
+
+                match sessions::current(&session_directory) {
+                    Ok(state) => send(&writer, &notification("session/didChange", json!(state))),
+                    Err(e) => eprintln!("cast daemon: {}", e),
+                }
+            }
+        })
+    }
+
+    fn send(&self, message: &Value) -> Result<(), DaemonError> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|e| DaemonError::ProtocolError(e.to_string()))?;
+        write_message(&mut *writer, message)
+    }
+}
+
+/// Send from a background thread, where there is no caller to return the error to
+fn send(writer: &SharedWriter, message: &Value) {
+    let result = match writer.lock() {
+        Ok(mut writer) => write_message(&mut *writer, message),
+        Err(e) => Err(DaemonError::ProtocolError(e.to_string())),
+    };
+    if let Err(e) = result {
+        eprintln!("cast daemon: {}", e);
+    }
+}
+
+/// Build the issue filter for `issues/list` from `{ project, completed, agent_generated }`
+///
+/// `completed` defaults to `false` (open issues); pass `null` for every issue.
+fn issue_filter(params: &Value) -> Result<IssueFilter, RpcError> {
+    let completed = match params.get("completed") {
+        None => Some(false),
+        Some(Value::Null) => None,
+        Some(Value::Bool(completed)) => Some(*completed),
+        Some(_) => return Err(RpcError::invalid_params("completed must be a boolean")),
+    };
+    let project = match params.get("project") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(project)) => Some(PathBuf::from(project)),
+        Some(_) => return Err(RpcError::invalid_params("project must be a string")),
+    };
+
+    Ok(IssueFilter {
+        project,
+        agent_generated: params
+            .get("agent_generated")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        completed,
+    })
+}
+
+/// Run CI in a child cast process, so cargo's output can't corrupt the JSON-RPC stream
+///
+/// The child writes its result to a file of its own, and its stdout is passed on to stderr with
+/// the rest of the tool output.
+fn run_ci_command(working_directory: &Path, project: Option<&str>) -> Result<Value, DaemonError> {
+    let result_file = env::temp_dir().join(format!("cast-ci-{}.json", Uuid::now_v7()));
+    let cast = env::current_exe()?;
+    let mut command = Command::new(cast);
+    command.arg("ci");
+    if let Some(project) = project {
+        command.args(["--project", project]);
+    }
+    let output = command
+        .args(["--output", "json", "--result-file"])
+        .arg(&result_file)
+        .current_dir(working_directory)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()?;
+    io::stderr().write_all(&output.stdout)?;
+
+    let result = fs::read_to_string(&result_file);
+    // The result was already read, so a leftover temp file is harmless
+    let _ = fs::remove_file(&result_file);
+    match result {
+        Ok(result) => Ok(serde_json::from_str(&result)?),
+        Err(_) => Err(DaemonError::ProtocolError(format!(
+            "cast ci exited with {} without a JSON result",
+            output.status
+        ))),
+    }
+}
+
+/// Names, sizes and modification times of the session logs
+fn sessions_fingerprint(session_directory: &Path) -> Vec<(OsString, u64, Option<SystemTime>)> {
+    let sessions_directory = session_directory.join(sessions::SESSIONS_DIRECTORY);
+    let mut fingerprint: Vec<_> = fs::read_dir(sessions_directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.file_name(), metadata.len(), metadata.modified().ok()))
+        })
+        .collect();
+    fingerprint.sort();
+    fingerprint
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// Read one `Content-Length` framed message, or `None` once the input is closed
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<String>, DaemonError> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return match content_length {
+                None => Ok(None),
+                Some(_) => Err(DaemonError::ProtocolError(
+                    "input ended inside message headers".into(),
+                )),
+            };
+        }
+
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            // Tolerate blank lines between messages
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(|_| {
+                    DaemonError::ProtocolError(format!("invalid Content-Length '{}'", value))
+                })?);
+            }
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap_or_default()];
+    reader.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|e| DaemonError::ProtocolError(e.to_string()))
+}
+
+/// Write one message with a `Content-Length` header
+pub fn write_message(
+    writer: &mut (impl Write + ?Sized),
+    message: &Value,
+) -> Result<(), DaemonError> {
+    let content = serde_json::to_string(message)?;
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tempdir::TempDir;
+
+    /// Writer whose output can be read after the daemon finishes
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn frame(messages: &[Value]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, message).unwrap();
+        }
+        input
+    }
+
+    fn responses(buffer: &SharedBuffer) -> Vec<Value> {
+        let output = buffer.0.lock().unwrap().clone();
+        let mut reader = Cursor::new(output);
+        let mut messages = Vec::new();
+        while let Some(text) = read_message(&mut reader).unwrap() {
+            messages.push(serde_json::from_str(&text).unwrap());
+        }
+        messages
+    }
+
+    #[test]
+    fn test_message_framing_round_trips() {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": "session/state" });
+        let mut input = frame(std::slice::from_ref(&message));
+        input.extend_from_slice(b"\r\n");
+        let mut reader = Cursor::new(input);
+
+        let text = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), message);
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_serve_answers_requests_until_exit() {
+        let tmp_dir = TempDir::new("test_daemon").unwrap();
+        fs::write(tmp_dir.path().join("Cast.toml"), "").unwrap();
+        sessions::start(tmp_dir.path(), None).unwrap();
+
+        let input = frame(&[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "session/state" }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "issues/list", "params": { "completed": "no" } }),
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "unknown" }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+            json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }),
+        ]);
+        let buffer = SharedBuffer::default();
+        serve(tmp_dir.path(), Cursor::new(input), Box::new(buffer.clone())).unwrap();
+
+        let responses = responses(&buffer);
+        assert_eq!(responses.len(), 4, "nothing is answered after exit");
+        assert_eq!(responses[0]["result"]["methods"][2], "session/state");
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["result"]["status"], "active");
+        assert_eq!(responses[2]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[3]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_sessions_fingerprint_changes_when_logs_change() {
+        let tmp_dir = TempDir::new("test_daemon_watch").unwrap();
+        let empty = sessions_fingerprint(tmp_dir.path());
+        assert!(empty.is_empty());
+
+        sessions::start(tmp_dir.path(), None).unwrap();
+        let started = sessions_fingerprint(tmp_dir.path());
+        assert_eq!(started.len(), 1);
+
+        sessions::pause(tmp_dir.path()).unwrap();
+        assert_ne!(sessions_fingerprint(tmp_dir.path()), started);
+    }
+}
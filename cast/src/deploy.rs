@@ -1,5 +1,6 @@
 use crate::config::CastConfig;
 use std::collections::HashMap;
+use std::io;
 use std::path::Path;
 use std::process::{Command, Stdio};
 use thiserror::Error;
@@ -68,13 +69,13 @@ fn deploy_cloudflare_pages(working_directory: &Path) -> Result<(), DeployError>
     // Load environment variables from .env if it exists
     let env_vars = load_env_file(working_directory)?;
 
-    // Run wrangler pages deploy with inherited stdio and environment variables
+    // Run wrangler pages deploy with the environment variables, its output going to stderr
     let mut cmd = Command::new("wrangler");
     cmd.arg("pages")
         .arg("deploy")
         .current_dir(working_directory)
         .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
+        .stdout(io::stderr())
         .stderr(Stdio::inherit());
 
     // Add environment variables from .env file to the command
@@ -0,0 +1,362 @@
+use crate::targets::{self, display_project, TargetArgs, TargetError, Targets};
+use crate::{
+    audit, build, cd, ci, daemon, deploy, hooks, issues, plugins, projects, release, run, serve,
+    sessions, test, workflow_runner, workflows,
+};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+use thiserror::Error;
+
+/// Exit code of a command that failed
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit code of a command that needs a Cast.toml when none was found (clap uses 2 for usage errors)
+pub const EXIT_CAST_TOML_NOT_FOUND: u8 = 3;
+
+/// A `cast` subcommand
+pub trait CastCommand {
+    /// Whether the command runs in the nearest directory containing a Cast.toml
+    ///
+    /// Commands that return `false` run in the directory cast was started from.
+    fn requires_cast_toml(&self) -> bool {
+        true
+    }
+
+    /// Projects requested with `--project` / `--all`, for commands that run in a project
+    fn targets(&self) -> Option<&TargetArgs> {
+        None
+    }
+
+    /// Whether one invocation can run the command in several projects
+    fn runs_in_many_projects(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, working_directory: &Path) -> Result<CommandOutput, ExecuteError>;
+}
+
+/// Result of a successful command
+#[derive(Debug, Default, PartialEq)]
+pub struct CommandOutput {
+    /// Human readable summary, printed with `--output text`
+    pub message: String,
+    /// Structured result, printed with `--output json`
+    pub data: Value,
+}
+
+impl CommandOutput {
+    pub fn new(message: impl Into<String>) -> Self {
+        CommandOutput {
+            message: message.into(),
+            data: Value::Null,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = data;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Error, Debug)]
+pub enum ExecuteError {
+    #[error("cast toml not found")]
+    CastTomlNotFound,
+    #[error("{0}")]
+    TargetError(#[from] TargetError),
+    #[error("{failed} of {total} projects failed:\n{report}")]
+    ProjectsFailed {
+        failed: usize,
+        total: usize,
+        report: String,
+        results: Value,
+    },
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("session error: {0}")]
+    StartSessionError(#[from] sessions::StartSessionError),
+    #[error("session error: {0}")]
+    PauseSessionError(#[from] sessions::PauseSessionError),
+    #[error("session error: {0}")]
+    StopSessionError(#[from] sessions::StopSessionError),
+    #[error("session log error: {0}")]
+    SessionLogError(#[from] sessions::SessionLogError),
+    #[error("new project error: {0}")]
+    NewProjectError(#[from] projects::NewProjectError),
+    #[error("with-changes error: {0}")]
+    WithChangesError(#[from] projects::WithChangesError),
+    #[error("ci error: {0}")]
+    CiError(#[from] ci::CiError),
+    #[error("cd error: {0}")]
+    CdError(#[from] cd::CdError),
+    #[error("build error: {0}")]
+    BuildError(#[from] build::BuildError),
+    #[error("test error: {0}")]
+    TestError(#[from] test::TestError),
+    #[error("run error: {0}")]
+    RunError(#[from] run::RunError),
+    #[error("serve error: {0}")]
+    ServeError(#[from] serve::ServeError),
+    #[error("daemon error: {0}")]
+    DaemonError(#[from] daemon::DaemonError),
+    #[error("deploy error: {0}")]
+    DeployError(#[from] deploy::DeployError),
+    #[error("release error: {0}")]
+    ReleaseError(#[from] release::ReleaseError),
+    #[error("audit error: {0}")]
+    AuditError(#[from] audit::AuditError),
+    #[error("issues error: {0}")]
+    IssuesError(#[from] issues::IssuesError),
+    #[error("workflows error: {0}")]
+    WorkflowsError(#[from] workflows::WorkflowsError),
+    #[error("workflow run error: {0}")]
+    WorkflowRunError(#[from] workflow_runner::WorkflowRunError),
+    #[error("hooks error: {0}")]
+    HooksError(#[from] hooks::HooksError),
+    #[error("plugin error: {0}")]
+    PluginsError(#[from] plugins::PluginsError),
+}
+
+impl ExecuteError {
+    /// Process exit code for this error
+    ///
+    /// Failing plugins pass their own exit code through.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ExecuteError::CastTomlNotFound => EXIT_CAST_TOML_NOT_FOUND,
+            ExecuteError::PluginsError(plugins::PluginsError::PluginFailed {
+                code: Some(code),
+                ..
+            }) => u8::try_from(*code)
+                .ok()
+                .filter(|code| *code != 0)
+                .unwrap_or(EXIT_FAILURE),
+            _ => EXIT_FAILURE,
+        }
+    }
+
+    /// Structured details of the failure, printed with `--output json`
+    pub fn data(&self) -> Value {
+        match self {
+            ExecuteError::ProjectsFailed { results, .. } => results.clone(),
+            ExecuteError::AuditError(audit::AuditError::Failed(report)) => {
+                serde_json::to_value(report).unwrap_or_default()
+            }
+            ExecuteError::WorkflowRunError(
+                workflow_runner::WorkflowRunError::Failed(report)
+                | workflow_runner::WorkflowRunError::Incomplete(report),
+            ) => serde_json::to_value(report).unwrap_or_default(),
+            ExecuteError::SessionLogError(sessions::SessionLogError::MigrationFailed(
+                migration,
+            )) => serde_json::to_value(migration).unwrap_or_default(),
+            _ => Value::Null,
+        }
+    }
+}
+
+/// Run a command in the directory it needs, starting from `entry_directory`
+pub fn execute(
+    command: &dyn CastCommand,
+    entry_directory: &Path,
+) -> Result<CommandOutput, ExecuteError> {
+    if !command.requires_cast_toml() {
+        return command.execute(entry_directory);
+    }
+
+    if let Some(target_args) = command.targets().filter(|args| args.is_set()) {
+        let targets = targets::resolve(entry_directory, target_args)?;
+        if targets.projects.len() > 1 && !command.runs_in_many_projects() {
+            return Err(TargetError::SingleProjectOnly(targets.projects).into());
+        }
+        return execute_in_projects(command, &targets);
+    }
+
+    let working_directory =
+        find_cast_toml(entry_directory).ok_or(ExecuteError::CastTomlNotFound)?;
+    command.execute(working_directory)
+}
+
+/// Run a command in each target project, carrying on past failures and reporting every result
+fn execute_in_projects(
+    command: &dyn CastCommand,
+    targets: &Targets,
+) -> Result<CommandOutput, ExecuteError> {
+    if targets.projects.is_empty() {
+        return Ok(CommandOutput::new("No projects found").with_data(json!([])));
+    }
+
+    let mut lines = Vec::new();
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for project in &targets.projects {
+        let name = display_project(project);
+        match command.execute(&targets.monorepo_root.join(project)) {
+            Ok(output) => {
+                lines.push(format!(
+                    "✓ {}: {}",
+                    name,
+                    output.message.replace('\n', "\n    ")
+                ));
+                results.push(json!({
+                    "project": name,
+                    "ok": true,
+                    "message": output.message,
+                    "data": output.data,
+                }));
+            }
+            Err(e) => {
+                failed += 1;
+                lines.push(format!(
+                    "✗ {}: {}",
+                    name,
+                    e.to_string().replace('\n', "\n    ")
+                ));
+                results.push(json!({
+                    "project": name,
+                    "ok": false,
+                    "error": e.to_string(),
+                    "exit_code": e.exit_code(),
+                }));
+            }
+        }
+    }
+
+    let report = lines.join("\n");
+    if failed > 0 {
+        return Err(ExecuteError::ProjectsFailed {
+            failed,
+            total: targets.projects.len(),
+            report,
+            results: Value::Array(results),
+        });
+    }
+    Ok(CommandOutput::new(report).with_data(Value::Array(results)))
+}
+
+/// The JSON object `--output json` prints for the result of a command
+pub fn result_json(result: &Result<CommandOutput, ExecuteError>) -> Value {
+    match result {
+        Ok(output) => json!({
+            "ok": true,
+            "message": output.message,
+            "data": output.data,
+        }),
+        Err(e) => json!({
+            "ok": false,
+            "error": e.to_string(),
+            "exit_code": e.exit_code(),
+            "data": e.data(),
+        }),
+    }
+}
+
+/// Print the result of a command in the requested format and return the process exit code
+///
+/// JSON output is written to stdout as a single object, so callers can parse successes and
+/// failures the same way. Commands send their progress and the output of the tools they run to
+/// stderr, which keeps stdout to that object.
+pub fn report(result: Result<CommandOutput, ExecuteError>, format: OutputFormat) -> ExitCode {
+    if format == OutputFormat::Json {
+        println!("{:#}", result_json(&result));
+    }
+    match result {
+        Ok(output) => {
+            // Plugins print their own output
+            if format == OutputFormat::Text && !output.message.is_empty() {
+                println!("{}", output.message);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            if format == OutputFormat::Text {
+                eprintln!("Error: {}", e);
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+/// Find the nearest directory at or above `working_directory` containing a Cast.toml
+pub(crate) fn find_cast_toml(working_directory: &Path) -> Option<&Path> {
+    let mut current_directory = Some(working_directory);
+    while let Some(current_path) = current_directory {
+        if let Ok(entries) = fs::read_dir(current_path) {
+            for entry in entries.flatten() {
+                if entry.file_name() == "Cast.toml" {
+                    return current_directory;
+                }
+            }
+        }
+        current_directory = current_path.parent();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    struct FailingCommand;
+
+    impl CastCommand for FailingCommand {
+        fn requires_cast_toml(&self) -> bool {
+            false
+        }
+
+        fn execute(&self, _working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+            Err(plugins::PluginsError::PluginFailed {
+                name: "lint".into(),
+                code: Some(7),
+            }
+            .into())
+        }
+    }
+
+    #[test]
+    fn it_only_looks_for_cast_toml_when_required() {
+        let tmp_dir = TempDir::new("test_executor").unwrap();
+
+        let result = execute(&FailingCommand, tmp_dir.path());
+        assert_eq!(result.unwrap_err().exit_code(), 7);
+
+        struct NeedsCastToml;
+        impl CastCommand for NeedsCastToml {
+            fn execute(&self, _working_directory: &Path) -> Result<CommandOutput, ExecuteError> {
+                Ok(CommandOutput::new("ran"))
+            }
+        }
+        let error = execute(&NeedsCastToml, tmp_dir.path()).unwrap_err();
+        assert!(matches!(error, ExecuteError::CastTomlNotFound));
+        assert_eq!(error.exit_code(), EXIT_CAST_TOML_NOT_FOUND);
+    }
+
+    #[test]
+    fn failed_reports_are_returned_as_data() {
+        let error: ExecuteError = audit::AuditError::Failed(Box::new(audit::AuditReport {
+            packages: 3,
+            ..Default::default()
+        }))
+        .into();
+        assert_eq!(error.data()["packages"], 3);
+
+        let error: ExecuteError =
+            workflow_runner::WorkflowRunError::Incomplete(Box::new(workflow_runner::RunReport {
+                workflow: "ci.yml".into(),
+                event: "push".into(),
+                jobs: Vec::new(),
+                skipped_actions: vec!["someone/action@v1".into()],
+            }))
+            .into();
+        assert_eq!(error.data()["skipped_actions"][0], "someone/action@v1");
+    }
+}
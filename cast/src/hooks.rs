@@ -0,0 +1,321 @@
+use crate::cd::find_monorepo_root;
+use crate::ci;
+use crate::config::CastConfig;
+use crate::projects::find_project_dir;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs, io};
+use thiserror::Error;
+
+/// Set this environment variable (e.g. `CAST_SKIP_HOOKS=1 git commit`) to skip the hook checks
+pub const BYPASS_ENV_VAR: &str = "CAST_SKIP_HOOKS";
+
+/// Marks hooks written by `cast hooks install`, so other hooks are never overwritten or removed
+const HOOK_MARKER: &str = "# Installed by `cast hooks install`";
+
+#[derive(Error, Debug)]
+pub enum HooksError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("CD error: {0}")]
+    CdError(#[from] crate::cd::CdError),
+    #[error("Git error: {0}")]
+    GitError(String),
+    #[error("{0} already exists and was not installed by cast, use --force to replace it")]
+    ExistingHook(PathBuf),
+    #[error("{0} was not installed by cast")]
+    NotCastHook(PathBuf),
+    #[error("Pre-commit checks failed (set {BYPASS_ENV_VAR}=1 to bypass):\n{}", .0.join("\n"))]
+    ChecksFailed(Vec<String>),
+}
+
+/// Install the pre-commit hook, returning its path
+///
+/// The hook calls `cast hooks run` with the currently running cast binary, falling back to
+/// `cast` on the PATH if that binary has moved. Existing hooks that cast didn't install are
+/// only replaced when `force` is set.
+pub fn install(working_directory: impl AsRef<Path>, force: bool) -> Result<PathBuf, HooksError> {
+    let hook_path = pre_commit_hook_path(working_directory.as_ref())?;
+
+    if let Ok(existing) = fs::read_to_string(&hook_path) {
+        if !existing.contains(HOOK_MARKER) && !force {
+            return Err(HooksError::ExistingHook(hook_path));
+        }
+    }
+
+    let cast_bin = env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "cast".to_string());
+
+    if let Some(parent) = hook_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&hook_path, hook_script(&cast_bin))?;
+    make_executable(&hook_path)?;
+
+    Ok(hook_path)
+}
+
+/// Remove the pre-commit hook installed by `cast hooks install`
+///
+/// Returns the removed hook's path, or `None` when no hook is installed.
+pub fn uninstall(working_directory: impl AsRef<Path>) -> Result<Option<PathBuf>, HooksError> {
+    let hook_path = pre_commit_hook_path(working_directory.as_ref())?;
+
+    match fs::read_to_string(&hook_path) {
+        Ok(existing) if existing.contains(HOOK_MARKER) => {
+            fs::remove_file(&hook_path)?;
+            Ok(Some(hook_path))
+        }
+        Ok(_) => Err(HooksError::NotCastHook(hook_path)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Run the pre-commit checks for the projects touched by the staged changes
+///
+/// For every project containing a staged file this:
+/// 1. Validates the Cast configuration (Cast.toml or Cargo.toml metadata)
+/// 2. Runs `cargo fmt --check` and `cargo clippy -- -D warnings` for Rust projects
+///
+/// Returns the checked projects. Does nothing when `CAST_SKIP_HOOKS` is set.
+pub fn run(working_directory: impl AsRef<Path>) -> Result<Vec<PathBuf>, HooksError> {
+    if is_bypassed() {
+        return Ok(Vec::new());
+    }
+
+    let repo_root = find_monorepo_root(working_directory.as_ref())?;
+    let projects = staged_projects(&repo_root)?;
+    let failures: Vec<String> = projects
+        .iter()
+        .flat_map(|project| check_project(&repo_root, project))
+        .collect();
+
+    if failures.is_empty() {
+        Ok(projects)
+    } else {
+        Err(HooksError::ChecksFailed(failures))
+    }
+}
+
+/// Whether the bypass variable is set to anything other than empty or `0`
+pub fn is_bypassed() -> bool {
+    env::var(BYPASS_ENV_VAR).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+fn hook_script(cast_bin: &str) -> String {
+    format!(
+        r#"#!/bin/sh
+{HOOK_MARKER}, remove it with `cast hooks uninstall`.
+# Runs fmt, clippy and Cast.toml validation for the projects touched by the staged changes.
+# Set {BYPASS_ENV_VAR}=1 to skip the checks for one commit.
+if [ -n "${BYPASS_ENV_VAR}" ] && [ "${BYPASS_ENV_VAR}" != "0" ]; then
+  exit 0
+fi
+
+CAST="{cast_bin}"
+if [ ! -x "$CAST" ]; then
+  CAST=cast
+fi
+exec "$CAST" hooks run
+"#
+    )
+}
+
+/// Path of the pre-commit hook, respecting `core.hooksPath` and worktrees
+fn pre_commit_hook_path(working_directory: &Path) -> Result<PathBuf, HooksError> {
+    let repo_root = find_monorepo_root(working_directory)?;
+    let hooks_dir = git(&repo_root, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(repo_root.join(hooks_dir.trim()).join("pre-commit"))
+}
+
+/// Projects (relative to the repo root) containing a staged added, copied, modified, renamed
+/// or deleted file
+fn staged_projects(repo_root: &Path) -> Result<Vec<PathBuf>, HooksError> {
+    let staged = git(
+        repo_root,
+        &["diff", "--cached", "--name-only", "--diff-filter=ACMRD"],
+    )?;
+
+    let projects: BTreeSet<PathBuf> = staged
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|file| find_project_dir(&repo_root.join(file), repo_root))
+        .collect();
+
+    Ok(projects.into_iter().collect())
+}
+
+/// Run the fast CI subset for one project, returning a message per failed check
+fn check_project(repo_root: &Path, project: &Path) -> Vec<String> {
+    let dir = repo_root.join(project);
+    let mut failures = Vec::new();
+
+    // A deleted project has nothing left to check
+    if !dir.exists() {
+        return failures;
+    }
+
+    let cast_toml = dir.join("Cast.toml");
+    if cast_toml.exists() {
+        if let Err(e) = CastConfig::load(&cast_toml) {
+            failures.push(format!("{}: invalid Cast.toml: {}", project.display(), e));
+        }
+    }
+
+    let cargo_toml = dir.join("Cargo.toml");
+    if cargo_toml.exists() {
+        if let Err(e) = CastConfig::load_from_cargo_toml(&cargo_toml) {
+            failures.push(format!("{}: invalid Cargo.toml: {}", project.display(), e));
+        }
+        if let Err(e) = ci::run_fmt_check(&dir) {
+            failures.push(format!("{}: {}", project.display(), e));
+        }
+        if let Err(e) = ci::run_clippy(&dir) {
+            failures.push(format!("{}: {}", project.display(), e));
+        }
+    }
+
+    failures
+}
+
+fn git(repo_root: &Path, args: &[&str]) -> Result<String, HooksError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo_root)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(HooksError::GitError(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn init_repo(directory: &Path) {
+        let status = Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(directory)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn stage(directory: &Path, file: &str, contents: &str) {
+        let path = directory.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        git(directory, &["add", file]).unwrap();
+    }
+
+    #[test]
+    fn test_install_and_uninstall_pre_commit_hook() {
+        let tmp_dir = TempDir::new("test_hooks_install").unwrap();
+        init_repo(tmp_dir.path());
+
+        let hook_path = install(tmp_dir.path(), false).unwrap();
+        assert_eq!(hook_path, tmp_dir.path().join(".git/hooks/pre-commit"));
+
+        let script = fs::read_to_string(&hook_path).unwrap();
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(HOOK_MARKER));
+        assert!(script.contains("\"$CAST_SKIP_HOOKS\""));
+        assert!(script.contains("hooks run"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&hook_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        // Reinstalling replaces cast's own hook
+        install(tmp_dir.path(), false).unwrap();
+
+        assert_eq!(uninstall(tmp_dir.path()).unwrap(), Some(hook_path.clone()));
+        assert!(!hook_path.exists());
+        assert_eq!(uninstall(tmp_dir.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_install_keeps_existing_hooks_unless_forced() {
+        let tmp_dir = TempDir::new("test_hooks_existing").unwrap();
+        init_repo(tmp_dir.path());
+        let hook_path = tmp_dir.path().join(".git/hooks/pre-commit");
+        fs::create_dir_all(hook_path.parent().unwrap()).unwrap();
+        fs::write(&hook_path, "#!/bin/sh\nnpx lint-staged\n").unwrap();
+
+        assert!(matches!(
+            install(tmp_dir.path(), false),
+            Err(HooksError::ExistingHook(_))
+        ));
+        assert!(matches!(
+            uninstall(tmp_dir.path()),
+            Err(HooksError::NotCastHook(_))
+        ));
+        assert_eq!(
+            fs::read_to_string(&hook_path).unwrap(),
+            "#!/bin/sh\nnpx lint-staged\n"
+        );
+
+        install(tmp_dir.path(), true).unwrap();
+        assert!(fs::read_to_string(&hook_path)
+            .unwrap()
+            .contains(HOOK_MARKER));
+    }
+
+    #[test]
+    fn test_staged_projects_only_includes_touched_projects() {
+        let tmp_dir = TempDir::new("test_hooks_staged").unwrap();
+        init_repo(tmp_dir.path());
+        fs::create_dir_all(tmp_dir.path().join("untouched")).unwrap();
+        fs::write(tmp_dir.path().join("untouched/Cast.toml"), "").unwrap();
+        stage(tmp_dir.path(), "web/Cast.toml", "framework = \"dioxus\"\n");
+        stage(tmp_dir.path(), "web/src/main.rs", "fn main() {}\n");
+        stage(tmp_dir.path(), "notes.md", "not in a project\n");
+
+        assert_eq!(
+            staged_projects(tmp_dir.path()).unwrap(),
+            vec![PathBuf::from("web")]
+        );
+    }
+
+    #[test]
+    fn test_check_project_reports_invalid_cast_toml() {
+        let tmp_dir = TempDir::new("test_hooks_invalid").unwrap();
+        init_repo(tmp_dir.path());
+        stage(
+            tmp_dir.path(),
+            "site/Cast.toml",
+            "deploys = \"not a list\"\n",
+        );
+
+        let projects = staged_projects(tmp_dir.path()).unwrap();
+        let failures = check_project(tmp_dir.path(), &projects[0]);
+
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("site: invalid Cast.toml"));
+    }
+}
@@ -0,0 +1,1081 @@
+use crate::cd::find_monorepo_root;
+use crate::projects::find_project_dir;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use thiserror::Error;
+
+const ISSUES_FILE_NAME: &str = "ISSUES.md";
+
+/// Source file extensions scanned for `// TODO` comments
+const SOURCE_EXTENSIONS: &[&str] = &["rs", "ts", "tsx", "js", "jsx"];
+
+/// Directories that never contain hand-written issues
+const SKIPPED_DIRECTORIES: &[&str] = &["target", "node_modules", "dist", "out", "artifacts"];
+
+#[derive(Error, Debug)]
+pub enum IssuesError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("CD error: {0}")]
+    CdError(#[from] crate::cd::CdError),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Issue not found: {0}")]
+    IssueNotFound(String),
+    #[error("Only ISSUES.md items can be marked done, remove the comment instead: {0}")]
+    NotAnIssuesMdItem(String),
+    #[error("Issue is already completed: {0}")]
+    AlreadyCompleted(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSource {
+    /// A list item or heading in an ISSUES.md file
+    IssuesMd,
+    /// A `// TODO` comment in a source file
+    Comment,
+}
+
+/// A single TODO item found in an ISSUES.md file or a source comment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Issue {
+    /// Declared with `[id: name]`, otherwise `<file>:<line>`
+    pub id: String,
+    pub text: String,
+    /// Cast project the item belongs to (None for items outside any project)
+    pub project: Option<PathBuf>,
+    /// File containing the item, relative to the monorepo root
+    pub file: PathBuf,
+    pub line: usize,
+    pub source: IssueSource,
+    pub completed: bool,
+    pub agent_generated: bool,
+    /// Marked `(agent-ignore)`, so agents should not pick it up
+    pub agent_ignore: bool,
+    /// Listed under the `# Priority Issues` section of its ISSUES.md
+    pub priority: bool,
+    /// Markdown headings the item is nested under (e.g., epic and phase)
+    pub headings: Vec<String>,
+    /// Ids of items that must be completed first (declared with `[depends-on: a, b]` or inferred from phases)
+    pub depends_on: Vec<String>,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)?;
+        if let Some(project) = &self.project {
+            write!(f, " [{}]", project.display())?;
+        }
+        write!(
+            f,
+            " {}",
+            if self.completed {
+                "✓ COMPLETED"
+            } else {
+                "TODO"
+            }
+        )?;
+        if self.agent_generated {
+            write!(f, " (agent-generated)")?;
+        }
+        if self.agent_ignore {
+            write!(f, " (agent-ignore)")?;
+        }
+        write!(f, ": {}", self.text)
+    }
+}
+
+/// Every issue in the monorepo plus the priority project list from the root ISSUES.md
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct IssueIndex {
+    pub issues: Vec<Issue>,
+    pub priority_projects: Vec<PathBuf>,
+}
+
+/// Output format of the issue dependency graph, Graphviz DOT or Mermaid
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+/// Which issues to include when listing
+#[derive(Debug, Default, Clone)]
+pub struct IssueFilter {
+    /// Only include issues attributed to this project
+    pub project: Option<PathBuf>,
+    /// Only include agent-generated issues
+    pub agent_generated: bool,
+    /// Only include completed (`Some(true)`) or open (`Some(false)`) issues
+    pub completed: Option<bool>,
+}
+
+impl IssueFilter {
+    pub fn matches(&self, issue: &Issue) -> bool {
+        self.project
+            .as_ref()
+            .is_none_or(|project| issue.project.as_ref() == Some(project))
+            && (!self.agent_generated || issue.agent_generated)
+            && self
+                .completed
+                .is_none_or(|completed| issue.completed == completed)
+    }
+}
+
+impl IssueIndex {
+    /// Issues matching the filter, in file order
+    pub fn filter(&self, filter: &IssueFilter) -> Vec<&Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| filter.matches(issue))
+            .collect()
+    }
+
+    /// Pick the next open issue to work on
+    ///
+    /// Issues are ranked the way agents are asked to look for work:
+    /// 1. `# Priority Issues` in the root ISSUES.md
+    /// 2. ISSUES.md of each `# Priority Projects` entry, in list order
+    /// 3. Any other ISSUES.md
+    /// 4. TODO comments in source files
+    ///
+    /// Completed and `(agent-ignore)` items are skipped.
+    pub fn next(&self) -> Option<&Issue> {
+        self.unblocked()
+            .into_iter()
+            .min_by_key(|issue| self.rank(issue))
+    }
+
+    /// Find an issue by its id or `<file>:<line>` location
+    pub fn find(&self, id: &str) -> Option<&Issue> {
+        self.issues.iter().find(|issue| issue.id == id).or_else(|| {
+            self.issues
+                .iter()
+                .find(|issue| format!("{}:{}", issue.file.display(), issue.line) == id)
+        })
+    }
+
+    /// Whether any dependency is still open (unknown ids also block, so typos don't release work early)
+    pub fn is_blocked(&self, issue: &Issue) -> bool {
+        issue
+            .depends_on
+            .iter()
+            .any(|id| !self.find(id).is_some_and(|dependency| dependency.completed))
+    }
+
+    /// Open, non-ignored issues whose dependencies are all completed
+    pub fn unblocked(&self) -> Vec<&Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| !issue.completed && !issue.agent_ignore && !self.is_blocked(issue))
+            .collect()
+    }
+
+    /// Dependency ids that don't match any issue
+    pub fn unknown_dependencies(&self) -> Vec<(&Issue, &str)> {
+        self.issues
+            .iter()
+            .flat_map(|issue| {
+                issue
+                    .depends_on
+                    .iter()
+                    .filter(|id| self.find(id).is_none())
+                    .map(move |id| (issue, id.as_str()))
+            })
+            .collect()
+    }
+
+    /// Render ISSUES.md items matching the filter as a dependency graph
+    ///
+    /// Unblocked items are listed in a comment at the top so the output stays valid DOT/Mermaid.
+    pub fn graph(&self, filter: &IssueFilter, format: GraphFormat) -> String {
+        let nodes: Vec<&Issue> = self
+            .filter(filter)
+            .into_iter()
+            .filter(|issue| issue.source == IssueSource::IssuesMd)
+            .collect();
+        let node_index = |id: &str| {
+            self.find(id)
+                .and_then(|dependency| nodes.iter().position(|node| *node == dependency))
+        };
+        let edges: Vec<(usize, usize)> = nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(to, issue)| {
+                issue
+                    .depends_on
+                    .iter()
+                    .filter_map(move |id| node_index(id).map(|from| (from, to)))
+            })
+            .collect();
+        let unblocked: Vec<&Issue> = self
+            .unblocked()
+            .into_iter()
+            .filter(|issue| nodes.contains(issue))
+            .collect();
+
+        let mut lines = Vec::new();
+        let comment = match format {
+            GraphFormat::Dot => "//",
+            GraphFormat::Mermaid => "%%",
+        };
+        lines.push(format!("{} Unblocked ({}):", comment, unblocked.len()));
+        lines.extend(
+            unblocked
+                .iter()
+                .map(|issue| format!("{}   {}", comment, issue)),
+        );
+
+        match format {
+            GraphFormat::Dot => {
+                lines.push("digraph issues {".to_string());
+                lines.push("  rankdir=LR;".to_string());
+                lines.push("  node [shape=box];".to_string());
+                for (index, issue) in nodes.iter().enumerate() {
+                    lines.push(format!(
+                        "  n{} [label=\"{}\"{}];",
+                        index,
+                        graph_label(issue)
+                            .replace('\\', "\\\\")
+                            .replace('"', "\\\""),
+                        if issue.completed {
+                            ", style=filled, fillcolor=palegreen"
+                        } else if unblocked.contains(issue) {
+                            ", style=bold"
+                        } else {
+                            ""
+                        }
+                    ));
+                }
+                for (from, to) in edges {
+                    lines.push(format!("  n{} -> n{};", from, to));
+                }
+                lines.push("}".to_string());
+            }
+            GraphFormat::Mermaid => {
+                lines.push("graph TD".to_string());
+                lines.push("  classDef done fill:#cfc;".to_string());
+                lines.push("  classDef unblocked stroke-width:3px;".to_string());
+                for (index, issue) in nodes.iter().enumerate() {
+                    lines.push(format!(
+                        "  n{}[\"{}\"]{}",
+                        index,
+                        graph_label(issue).replace('"', "#quot;"),
+                        if issue.completed {
+                            ":::done"
+                        } else if unblocked.contains(issue) {
+                            ":::unblocked"
+                        } else {
+                            ""
+                        }
+                    ));
+                }
+                for (from, to) in edges {
+                    lines.push(format!("  n{} --> n{}", from, to));
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    fn rank(&self, issue: &Issue) -> usize {
+        let others = 1 + self.priority_projects.len() * 2;
+        match issue.source {
+            IssueSource::Comment => others + 2,
+            IssueSource::IssuesMd => {
+                let directory = issue.file.parent().unwrap_or(Path::new(""));
+                if directory.as_os_str().is_empty() && issue.priority {
+                    return 0;
+                }
+                match self
+                    .priority_projects
+                    .iter()
+                    .position(|project| project == directory)
+                {
+                    Some(index) => 1 + index * 2 + usize::from(!issue.priority),
+                    None => others + usize::from(!issue.priority),
+                }
+            }
+        }
+    }
+}
+
+/// Short node label for graphs, truncated so long items don't stretch the layout
+fn graph_label(issue: &Issue) -> String {
+    const MAX_LABEL_CHARS: usize = 60;
+    if issue.text.chars().count() <= MAX_LABEL_CHARS {
+        return issue.text.clone();
+    }
+    let truncated: String = issue.text.chars().take(MAX_LABEL_CHARS - 3).collect();
+    format!("{}...", truncated)
+}
+
+/// Index every ISSUES.md and `// TODO` comment in the monorepo
+///
+/// This:
+/// 1. Finds the monorepo root from the working directory
+/// 2. Parses ISSUES.md files into items, keeping their section and headings
+/// 3. Scans source files for `// TODO` comments
+/// 4. Attributes each item to the Cast project containing it
+/// 5. Makes every item in an epic phase depend on the items of the previous phase
+pub fn run(working_directory: impl AsRef<Path>) -> Result<IssueIndex, IssuesError> {
+    let monorepo_root = find_monorepo_root(working_directory.as_ref())?;
+
+    let mut files = Vec::new();
+    find_issue_files_recursive(&monorepo_root, &mut files)?;
+    files.sort();
+
+    let mut index = IssueIndex::default();
+    for path in files {
+        // Skip files that aren't text (e.g., bundled assets with a .js extension)
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let relative = path
+            .strip_prefix(&monorepo_root)
+            .unwrap_or(&path)
+            .to_path_buf();
+        let project = find_project_dir(&path, &monorepo_root);
+
+        if path.ends_with(ISSUES_FILE_NAME) {
+            let parsed = parse_issues_md(&contents, &relative, project.as_deref());
+            if relative == Path::new(ISSUES_FILE_NAME) {
+                index.priority_projects = parsed.priority_projects;
+            }
+            index.issues.extend(parsed.issues);
+        } else {
+            index.issues.extend(parse_source_comments(
+                &contents,
+                &relative,
+                project.as_deref(),
+            ));
+        }
+    }
+
+    Ok(index)
+}
+
+/// Mark an ISSUES.md item as done by rewriting its `TODO` marker to `✓ COMPLETED` in place
+pub fn mark_done(working_directory: impl AsRef<Path>, id: &str) -> Result<Issue, IssuesError> {
+    let working_directory = working_directory.as_ref();
+    let index = run(working_directory)?;
+    let issue = index
+        .find(id)
+        .ok_or_else(|| IssuesError::IssueNotFound(id.to_string()))?;
+    if issue.source != IssueSource::IssuesMd {
+        return Err(IssuesError::NotAnIssuesMdItem(id.to_string()));
+    }
+    if issue.completed {
+        return Err(IssuesError::AlreadyCompleted(id.to_string()));
+    }
+
+    let path = find_monorepo_root(working_directory)?.join(&issue.file);
+    let contents = fs::read_to_string(&path)?;
+    let mut updated = String::with_capacity(contents.len());
+    for (index, line) in contents.split_inclusive('\n').enumerate() {
+        if index + 1 == issue.line {
+            updated.push_str(&complete_line(line).ok_or_else(|| {
+                IssuesError::IssueNotFound(format!("{} (file changed while updating)", id))
+            })?);
+        } else {
+            updated.push_str(line);
+        }
+    }
+    fs::write(&path, updated)?;
+
+    Ok(Issue {
+        completed: true,
+        ..issue.clone()
+    })
+}
+
+/// Replace the `TODO (...):` marker of a line with `✓ COMPLETED:`, keeping the prefix and text
+fn complete_line(line: &str) -> Option<String> {
+    let content = line.trim_end_matches(['\r', '\n']);
+    let start = content.find("TODO")?;
+    let (marker, rest) = strip_item_marker(&content[start..])?;
+    if marker.completed {
+        return None;
+    }
+    Some(format!(
+        "{}✓ COMPLETED: {}{}",
+        &content[..start],
+        rest,
+        &line[content.len()..]
+    ))
+}
+
+fn find_issue_files_recursive(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if entry.file_type()?.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_ref()) {
+                find_issue_files_recursive(&path, files)?;
+            }
+        } else if name == ISSUES_FILE_NAME
+            || path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parsed item text and the markers found around it
+#[derive(Debug, PartialEq)]
+struct ItemMarker {
+    text: String,
+    completed: bool,
+    agent_generated: bool,
+    agent_ignore: bool,
+    id: Option<String>,
+    depends_on: Vec<String>,
+}
+
+/// Parse `TODO: text`, `TODO (agent-generated): text`, `TODO: (agent-ignore) text` or `✓ COMPLETED: text`
+///
+/// The text may contain `[id: name]` and `[depends-on: a, b]` annotations, which are removed from it.
+fn parse_item_marker(text: &str) -> Option<ItemMarker> {
+    let (mut marker, rest) = strip_item_marker(text)?;
+
+    let mut text = rest.to_string();
+    while let Some((start, end, key, value)) = find_annotation(&text) {
+        match key {
+            "id" => marker.id = Some(value.to_string()),
+            _ => marker.depends_on.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(String::from),
+            ),
+        }
+        text.replace_range(start..end, "");
+    }
+    marker.text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    Some(marker)
+}
+
+/// Locate the first `[id: ...]` or `[depends-on: ...]` annotation, returning its byte range, key and value
+fn find_annotation(text: &str) -> Option<(usize, usize, &str, &str)> {
+    ["[id:", "[depends-on:"]
+        .iter()
+        .filter_map(|prefix| {
+            let start = text.find(prefix)?;
+            let end = start + text[start..].find(']')? + 1;
+            let key = &prefix[1..prefix.len() - 1];
+            Some((start, end, key, text[start + prefix.len()..end - 1].trim()))
+        })
+        .min_by_key(|(start, ..)| *start)
+}
+
+/// Split the marker (status and tags) from the rest of the item text
+fn strip_item_marker(text: &str) -> Option<(ItemMarker, &str)> {
+    let (completed, rest) = if let Some(rest) = text.strip_prefix("✓ COMPLETED") {
+        (true, rest)
+    } else if let Some(rest) = text.strip_prefix("TODO") {
+        // Require a word boundary so identifiers like TODOS don't match
+        if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        (false, rest)
+    } else {
+        return None;
+    };
+
+    let mut agent_generated = false;
+    let mut agent_ignore = false;
+    let mut rest = rest.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix(':') {
+            rest = after.trim_start();
+        } else if let Some(after) = rest.strip_prefix("(agent-generated)") {
+            agent_generated = true;
+            rest = after.trim_start();
+        } else if let Some(after) = rest.strip_prefix("(agent-ignore)") {
+            agent_ignore = true;
+            rest = after.trim_start();
+        } else {
+            break;
+        }
+    }
+
+    Some((
+        ItemMarker {
+            text: String::new(),
+            completed,
+            agent_generated,
+            agent_ignore,
+            id: None,
+            depends_on: Vec::new(),
+        },
+        rest.trim_end(),
+    ))
+}
+
+#[derive(Debug, Default)]
+struct ParsedIssuesMd {
+    issues: Vec<Issue>,
+    priority_projects: Vec<PathBuf>,
+}
+
+fn parse_issues_md(contents: &str, file: &Path, project: Option<&Path>) -> ParsedIssuesMd {
+    let mut parsed = ParsedIssuesMd::default();
+    let mut section = String::new();
+    // Headings below the top-level section, indexed by depth (## is 0, ### is 1, ...)
+    let mut headings: Vec<String> = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let trimmed = line.trim();
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        let heading = (level > 0)
+            .then(|| trimmed[level..].trim())
+            .filter(|_| trimmed[level..].starts_with(' '));
+
+        let item = heading
+            .unwrap_or_else(|| trimmed.trim_start_matches(['-', '*']).trim_start())
+            .trim_start_matches("[ ]")
+            .trim_start();
+
+        if let Some(marker) = parse_item_marker(item) {
+            // A heading item ends any deeper heading that came before it
+            if heading.is_some() && level > 1 {
+                headings.truncate(level - 2);
+            }
+            parsed.issues.push(Issue {
+                id: marker
+                    .id
+                    .unwrap_or_else(|| format!("{}:{}", file.display(), index + 1)),
+                text: marker.text,
+                project: project.map(Path::to_path_buf),
+                file: file.to_path_buf(),
+                line: index + 1,
+                source: IssueSource::IssuesMd,
+                completed: marker.completed,
+                agent_generated: marker.agent_generated,
+                agent_ignore: marker.agent_ignore,
+                priority: section == "Priority Issues",
+                headings: headings.clone(),
+                depends_on: marker.depends_on,
+            });
+            continue;
+        }
+
+        match heading {
+            Some(title) if level == 1 => {
+                section = title.to_string();
+                headings.clear();
+            }
+            Some(title) => {
+                headings.truncate(level - 2);
+                headings.push(title.to_string());
+            }
+            None if section == "Priority Projects" => {
+                if let Some(name) = trimmed.strip_prefix("- ") {
+                    parsed.priority_projects.push(PathBuf::from(name.trim()));
+                }
+            }
+            None => {}
+        }
+    }
+
+    infer_phase_dependencies(&mut parsed.issues);
+    parsed
+}
+
+/// Make each item in a `### Phase ...` heading depend on every item of the epic's previous phase
+fn infer_phase_dependencies(issues: &mut [Issue]) {
+    // Per epic: (current phase, ids in the previous phase, ids in the current phase)
+    let mut epics: Vec<(String, String, Vec<String>, Vec<String>)> = Vec::new();
+
+    for issue in issues.iter_mut() {
+        let [epic, phase, ..] = issue.headings.as_slice() else {
+            continue;
+        };
+        if !phase.starts_with("Phase ") {
+            continue;
+        }
+
+        let position = match epics.iter().position(|(name, ..)| name == epic) {
+            Some(position) => position,
+            None => {
+                epics.push((epic.clone(), phase.clone(), Vec::new(), Vec::new()));
+                epics.len() - 1
+            }
+        };
+        let (_, current_phase, previous_ids, current_ids) = &mut epics[position];
+        if current_phase != phase {
+            *current_phase = phase.clone();
+            *previous_ids = std::mem::take(current_ids);
+        }
+
+        for id in previous_ids.iter() {
+            if !issue.depends_on.contains(id) {
+                issue.depends_on.push(id.clone());
+            }
+        }
+        current_ids.push(issue.id.clone());
+    }
+}
+
+/// Text after the `//` that starts a line comment, ignoring `//` inside string literals and URLs
+///
+/// A comment starts at the beginning of the line or after whitespace outside double quotes.
+fn line_comment(line: &str) -> Option<&str> {
+    if let Some(comment) = line.trim_start().strip_prefix("//") {
+        return Some(comment);
+    }
+
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut previous = None;
+    for (index, c) in line.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if line[index..].starts_with("//") && previous.is_some_and(char::is_whitespace) {
+            return Some(&line[index + 2..]);
+        }
+        previous = Some(c);
+    }
+    None
+}
+
+fn parse_source_comments(contents: &str, file: &Path, project: Option<&Path>) -> Vec<Issue> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let comment = line_comment(line)?;
+            let comment = comment.trim_start_matches(['/', '!']).trim_start();
+            let marker = parse_item_marker(comment)?;
+            Some(Issue {
+                id: marker
+                    .id
+                    .unwrap_or_else(|| format!("{}:{}", file.display(), index + 1)),
+                text: marker.text,
+                project: project.map(Path::to_path_buf),
+                file: file.to_path_buf(),
+                line: index + 1,
+                source: IssueSource::Comment,
+                completed: marker.completed,
+                agent_generated: marker.agent_generated,
+                agent_ignore: marker.agent_ignore,
+                priority: false,
+                headings: Vec::new(),
+                depends_on: marker.depends_on,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    const CAST_ISSUES: &str = "# Priority Issues
+
+## Restructuring (Epic)
+
+### Phase 1: Preparation (agent-generated)
+- ✓ COMPLETED: Document dependencies
+- TODO (agent-generated): Create workspace root
+
+### TODO (agent-generated): Create CLI skeleton
+Create a new binary crate
+- Add `[[bin]]` section
+
+# Backlog
+TODO: Refactor commands
+- TODO (agent-ignore): Leave this alone
+";
+
+    #[test]
+    fn test_parse_item_marker_variants() {
+        assert_eq!(
+            parse_item_marker("TODO: (agent-generated) Handle errors"),
+            Some(ItemMarker {
+                text: "Handle errors".to_string(),
+                completed: false,
+                agent_generated: true,
+                agent_ignore: false,
+                id: None,
+                depends_on: Vec::new(),
+            })
+        );
+        assert_eq!(
+            parse_item_marker("✓ COMPLETED: Done").map(|m| (m.completed, m.text)),
+            Some((true, "Done".to_string()))
+        );
+        assert!(
+            parse_item_marker("TODO (agent-ignore): Skip")
+                .unwrap()
+                .agent_ignore
+        );
+        assert_eq!(parse_item_marker("TODOS are fun"), None);
+        assert_eq!(parse_item_marker("Not an item"), None);
+    }
+
+    #[test]
+    fn test_parse_issues_md_tracks_sections_and_headings() {
+        let parsed = parse_issues_md(
+            CAST_ISSUES,
+            Path::new("cast/ISSUES.md"),
+            Some(Path::new("cast")),
+        );
+        let issues = parsed.issues;
+        assert_eq!(issues.len(), 5);
+
+        assert!(issues[0].completed);
+        assert!(issues[0].priority);
+        assert_eq!(
+            issues[1].headings,
+            vec![
+                "Restructuring (Epic)".to_string(),
+                "Phase 1: Preparation (agent-generated)".to_string()
+            ]
+        );
+        assert_eq!(issues[1].line, 7);
+        assert_eq!(issues[1].project, Some(PathBuf::from("cast")));
+
+        // Heading items are nested under the enclosing epic, not the previous phase
+        assert_eq!(issues[2].text, "Create CLI skeleton");
+        assert_eq!(issues[2].headings, vec!["Restructuring (Epic)".to_string()]);
+
+        assert_eq!(issues[3].text, "Refactor commands");
+        assert!(!issues[3].priority);
+        assert!(issues[3].headings.is_empty());
+        assert!(issues[4].agent_ignore);
+    }
+
+    #[test]
+    fn test_parse_issues_md_reads_priority_projects() {
+        let parsed = parse_issues_md(
+            "# Priority Issues\n\n# Priority Projects\n- cast\n- cookbook/web\n",
+            Path::new("ISSUES.md"),
+            None,
+        );
+        assert!(parsed.issues.is_empty());
+        assert_eq!(
+            parsed.priority_projects,
+            vec![PathBuf::from("cast"), PathBuf::from("cookbook/web")]
+        );
+    }
+
+    #[test]
+    fn test_parse_source_comments() {
+        let source = "fn main() {\n    run(); // TODO (agent-generated): Handle errors\n    /// TODO: Document\n    let s = \"TODO\";\n}\n";
+        let issues = parse_source_comments(source, Path::new("app/src/main.rs"), None);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].line, 2);
+        assert_eq!(issues[0].text, "Handle errors");
+        assert!(issues[0].agent_generated);
+        assert_eq!(issues[0].source, IssueSource::Comment);
+        assert_eq!(issues[1].text, "Document");
+    }
+
+    #[test]
+    fn test_parse_source_comments_ignores_urls_and_strings() {
+        let source = "let url = \"https://example.com/todo\"; // TODO: Retry on timeout\nlet docs = \"see // TODO: not a comment\";\nlet escaped = \"\\\" // TODO: still a string\";\nlet link = https://example.com/TODO: nope\n";
+        let issues = parse_source_comments(source, Path::new("app/src/main.rs"), None);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 1);
+        assert_eq!(issues[0].text, "Retry on timeout");
+    }
+
+    #[test]
+    fn test_filter() {
+        let index = IssueIndex {
+            issues: parse_issues_md(
+                CAST_ISSUES,
+                Path::new("cast/ISSUES.md"),
+                Some(Path::new("cast")),
+            )
+            .issues,
+            priority_projects: Vec::new(),
+        };
+
+        let open = index.filter(&IssueFilter {
+            completed: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(open.len(), 4);
+
+        let agent_generated = index.filter(&IssueFilter {
+            agent_generated: true,
+            ..Default::default()
+        });
+        assert_eq!(agent_generated.len(), 2);
+
+        let other_project = index.filter(&IssueFilter {
+            project: Some(PathBuf::from("luggage")),
+            ..Default::default()
+        });
+        assert!(other_project.is_empty());
+    }
+
+    fn setup_monorepo() -> TempDir {
+        let tmp_dir = TempDir::new("test_issues").unwrap();
+        let root = tmp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(
+            root.join("ISSUES.md"),
+            "# Priority Issues\n\n# Backlog\n- TODO: Root backlog item\n\n# Priority Projects\n- web\n- cast\n",
+        )
+        .unwrap();
+
+        fs::create_dir_all(root.join("cast/src")).unwrap();
+        fs::write(root.join("cast/Cast.toml"), "").unwrap();
+        fs::write(root.join("cast/ISSUES.md"), CAST_ISSUES).unwrap();
+        fs::write(
+            root.join("cast/src/lib.rs"),
+            "// TODO: Comment in cast\npub fn f() {}\n",
+        )
+        .unwrap();
+
+        fs::create_dir_all(root.join("web/node_modules/dep")).unwrap();
+        fs::write(root.join("web/Cast.toml"), "").unwrap();
+        fs::write(
+            root.join("web/ISSUES.md"),
+            "# Priority Issues\n- ✓ COMPLETED: Already done\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("web/node_modules/dep/index.js"),
+            "// TODO: Not ours\n",
+        )
+        .unwrap();
+
+        tmp_dir
+    }
+
+    #[test]
+    fn test_run_indexes_issues_and_attributes_projects() {
+        let tmp_dir = setup_monorepo();
+        let index = run(tmp_dir.path().join("cast")).unwrap();
+
+        assert_eq!(
+            index.priority_projects,
+            vec![PathBuf::from("web"), PathBuf::from("cast")]
+        );
+        assert_eq!(index.issues.len(), 8);
+        assert_eq!(index.issues[0].file, PathBuf::from("ISSUES.md"));
+        assert_eq!(index.issues[0].project, None);
+
+        let comment = index
+            .issues
+            .iter()
+            .find(|issue| issue.source == IssueSource::Comment)
+            .unwrap();
+        assert_eq!(comment.file, PathBuf::from("cast/src/lib.rs"));
+        assert_eq!(comment.project, Some(PathBuf::from("cast")));
+        assert!(!index
+            .issues
+            .iter()
+            .any(|issue| issue.file.starts_with("web/node_modules")));
+    }
+
+    #[test]
+    fn test_next_follows_priority_order() {
+        let tmp_dir = setup_monorepo();
+        let mut index = run(tmp_dir.path()).unwrap();
+
+        // web is the first priority project, but its only item is completed
+        let next = index.next().unwrap();
+        assert_eq!(next.file, PathBuf::from("cast/ISSUES.md"));
+        assert_eq!(next.text, "Create workspace root");
+
+        // Root priority issues come before priority projects
+        index.issues.push(Issue {
+            id: "root-priority".to_string(),
+            text: "Root priority".to_string(),
+            project: None,
+            file: PathBuf::from("ISSUES.md"),
+            line: 2,
+            source: IssueSource::IssuesMd,
+            completed: false,
+            agent_generated: false,
+            agent_ignore: false,
+            priority: true,
+            headings: Vec::new(),
+            depends_on: Vec::new(),
+        });
+        assert_eq!(index.next().unwrap().text, "Root priority");
+    }
+
+    #[test]
+    fn test_next_falls_back_to_comments() {
+        let index = IssueIndex {
+            issues: parse_source_comments("// TODO: Only item", Path::new("a.rs"), None),
+            priority_projects: Vec::new(),
+        };
+        assert_eq!(index.next().unwrap().text, "Only item");
+        assert_eq!(index.next().unwrap().to_string(), "a.rs:1 TODO: Only item");
+        assert_eq!(IssueIndex::default().next(), None);
+    }
+
+    const EPIC_ISSUES: &str = "# Priority Issues
+
+## Restructuring (Epic)
+
+### Phase 1: Prepare
+- ✓ COMPLETED: Document dependencies
+- TODO: Write plan [id: plan]
+
+### Phase 2: Move
+- TODO: Move files
+- TODO: Update workflows [depends-on: ci-tests]
+
+## Unrelated
+- TODO: Add CI tests [id: ci-tests]
+";
+
+    fn epic_index() -> IssueIndex {
+        IssueIndex {
+            issues: parse_issues_md(EPIC_ISSUES, Path::new("cast/ISSUES.md"), None).issues,
+            priority_projects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_item_marker_annotations() {
+        let marker =
+            parse_item_marker("TODO: Move files [id: move] after [depends-on: a, b ] it").unwrap();
+        assert_eq!(marker.text, "Move files after it");
+        assert_eq!(marker.id, Some("move".to_string()));
+        assert_eq!(marker.depends_on, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_phases_depend_on_previous_phase() {
+        let index = epic_index();
+        let issues = &index.issues;
+        assert_eq!(issues[1].id, "plan");
+        assert_eq!(issues[0].id, "cast/ISSUES.md:6");
+        assert!(issues[1].depends_on.is_empty());
+        assert_eq!(
+            issues[2].depends_on,
+            vec!["cast/ISSUES.md:6".to_string(), "plan".to_string()]
+        );
+        assert_eq!(
+            issues[3].depends_on,
+            vec![
+                "ci-tests".to_string(),
+                "cast/ISSUES.md:6".to_string(),
+                "plan".to_string()
+            ]
+        );
+        assert!(issues[4].depends_on.is_empty());
+    }
+
+    #[test]
+    fn test_unblocked_and_next_skip_blocked_items() {
+        let mut index = epic_index();
+        let unblocked: Vec<&str> = index.unblocked().iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(unblocked, vec!["Write plan", "Add CI tests"]);
+
+        index.issues[1].completed = true;
+        let unblocked: Vec<&str> = index.unblocked().iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(unblocked, vec!["Move files", "Add CI tests"]);
+        assert_eq!(index.next().unwrap().text, "Move files");
+
+        // Unknown dependencies block the item and are reported
+        index.issues[4].depends_on.push("missing".to_string());
+        assert!(index.is_blocked(&index.issues[4]));
+        assert_eq!(index.unknown_dependencies().len(), 1);
+    }
+
+    #[test]
+    fn test_graph_renders_dot_and_mermaid() {
+        let index = epic_index();
+
+        let dot = index.graph(&IssueFilter::default(), GraphFormat::Dot);
+        assert!(dot.starts_with("// Unblocked (2):\n//   cast/ISSUES.md:7 TODO: Write plan"));
+        assert!(dot.contains("digraph issues {"));
+        assert!(dot.contains(
+            "  n0 [label=\"Document dependencies\", style=filled, fillcolor=palegreen];"
+        ));
+        assert!(dot.contains("  n1 [label=\"Write plan\", style=bold];"));
+        assert!(dot.contains("  n1 -> n2;"));
+        assert!(dot.contains("  n4 -> n3;"));
+        assert!(dot.ends_with("}"));
+
+        let mermaid = index.graph(
+            &IssueFilter {
+                completed: Some(false),
+                ..Default::default()
+            },
+            GraphFormat::Mermaid,
+        );
+        assert!(mermaid.contains("graph TD"));
+        assert!(mermaid.contains("  n0[\"Write plan\"]:::unblocked"));
+        // Edges to completed items are dropped with the filtered out node
+        assert!(mermaid.contains("  n0 --> n1"));
+        assert!(!mermaid.contains("Document dependencies"));
+    }
+
+    #[test]
+    fn test_complete_line_keeps_prefix_text_and_line_ending() {
+        assert_eq!(
+            complete_line("- TODO (agent-generated): Write plan [id: plan]\r\n"),
+            Some("- ✓ COMPLETED: Write plan [id: plan]\r\n".to_string())
+        );
+        assert_eq!(
+            complete_line("### TODO: Heading item"),
+            Some("### ✓ COMPLETED: Heading item".to_string())
+        );
+        assert_eq!(complete_line("- ✓ COMPLETED: Done\n"), None);
+    }
+
+    #[test]
+    fn test_mark_done_updates_issues_md_in_place() {
+        let tmp_dir = setup_monorepo();
+        let issues_path = tmp_dir.path().join("cast/ISSUES.md");
+
+        let issue = mark_done(tmp_dir.path(), "cast/ISSUES.md:7").unwrap();
+        assert!(issue.completed);
+        assert_eq!(issue.text, "Create workspace root");
+
+        let contents = fs::read_to_string(&issues_path).unwrap();
+        assert!(contents.contains("\n- ✓ COMPLETED: Create workspace root\n"));
+        assert_eq!(
+            contents.lines().count(),
+            CAST_ISSUES.lines().count(),
+            "Only the marked line should change"
+        );
+
+        assert!(matches!(
+            mark_done(tmp_dir.path(), "cast/ISSUES.md:7"),
+            Err(IssuesError::AlreadyCompleted(_))
+        ));
+        assert!(matches!(
+            mark_done(tmp_dir.path(), "cast/src/lib.rs:1"),
+            Err(IssuesError::NotAnIssuesMdItem(_))
+        ));
+        assert!(matches!(
+            mark_done(tmp_dir.path(), "nope"),
+            Err(IssuesError::IssueNotFound(_))
+        ));
+    }
+}
@@ -1,11 +1,22 @@
 pub mod args;
+pub mod audit;
 pub mod build;
 pub mod cd;
 pub mod ci;
 pub mod config;
+pub mod coverage;
+pub mod daemon;
 pub mod deploy;
+pub mod executor;
+pub mod hooks;
+pub mod issues;
+pub mod plugins;
 pub mod projects;
+pub mod release;
 pub mod run;
 pub mod serve;
 pub mod sessions;
+pub mod targets;
 pub mod test;
+pub mod workflow_runner;
+pub mod workflows;
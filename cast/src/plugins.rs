@@ -0,0 +1,374 @@
+use crate::cd::find_monorepo_root;
+use crate::config::CastConfig;
+use crate::projects::{find_project_dir, find_projects};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{env, fs, io};
+use thiserror::Error;
+
+/// Plugins are executables named `cast-<name>`
+pub const PLUGIN_PREFIX: &str = "cast-";
+
+/// Directory (relative to the monorepo root) searched for plugins before the PATH
+pub const REPO_PLUGINS_DIRECTORY: &str = ".cast/plugins";
+
+#[derive(Error, Debug)]
+pub enum PluginsError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] crate::config::ConfigError),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Unknown command `{0}`: no {PLUGIN_PREFIX}{0} plugin in {REPO_PLUGINS_DIRECTORY} or on PATH")]
+    NotFound(String),
+    #[error("Plugin `{name}` failed with {}", describe_exit(*.code))]
+    PluginFailed { name: String, code: Option<i32> },
+}
+
+fn describe_exit(code: Option<i32>) -> String {
+    code.map_or("a signal".to_string(), |code| format!("exit code {}", code))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginSource {
+    /// Found in the monorepo's `.cast/plugins` directory
+    Repo,
+    /// Found on the PATH
+    Path,
+}
+
+impl fmt::Display for PluginSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginSource::Repo => write!(f, "repo"),
+            PluginSource::Path => write!(f, "PATH"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    /// Subcommand name, e.g. `lint` for `cast-lint`
+    pub name: String,
+    pub path: PathBuf,
+    pub source: PluginSource,
+}
+
+/// Context passed to plugins as JSON on stdin
+#[derive(Debug, Serialize)]
+pub struct PluginContext {
+    pub cast_version: String,
+    pub working_directory: PathBuf,
+    /// Root of the monorepo, if cast runs inside one
+    pub monorepo_root: Option<PathBuf>,
+    /// The project containing the working directory
+    pub project: Option<PluginProject>,
+    /// Every Cast project in the monorepo; `deploys` in each config are the graph's edges
+    pub projects: Vec<PluginProject>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginProject {
+    /// Path relative to the monorepo root
+    pub path: PathBuf,
+    pub config: CastConfig,
+}
+
+/// Find every plugin, with repo plugins shadowing PATH plugins of the same name
+pub fn find_plugins(working_directory: impl AsRef<Path>) -> Vec<Plugin> {
+    find_plugins_in(&plugin_directories(working_directory.as_ref()))
+}
+
+/// Find the plugin implementing `cast <name>`
+pub fn find_plugin(working_directory: impl AsRef<Path>, name: &str) -> Option<Plugin> {
+    find_plugins(working_directory)
+        .into_iter()
+        .find(|plugin| plugin.name == name)
+}
+
+/// Build the context for plugins run from `working_directory`
+pub fn context(working_directory: impl AsRef<Path>) -> Result<PluginContext, PluginsError> {
+    let working_directory = working_directory.as_ref();
+    let monorepo_root = find_monorepo_root(working_directory).ok();
+
+    let mut projects = Vec::new();
+    let mut project = None;
+    if let Some(repo_root) = &monorepo_root {
+        for path in find_projects(repo_root)? {
+            projects.push(PluginProject {
+                config: CastConfig::load_from_dir(repo_root.join(&path))?,
+                path,
+            });
+        }
+
+        if let Some(path) = find_project_dir(working_directory, repo_root) {
+            let path = if path == Path::new(".") {
+                PathBuf::new()
+            } else {
+                path
+            };
+            project = Some(PluginProject {
+                config: CastConfig::load_from_dir(repo_root.join(&path))?,
+                path,
+            });
+        }
+    }
+
+    Ok(PluginContext {
+        cast_version: env!("CARGO_PKG_VERSION").to_string(),
+        working_directory: working_directory.to_path_buf(),
+        monorepo_root,
+        project,
+        projects,
+    })
+}
+
+/// Run `cast <name> <args>` through the `cast-<name>` plugin
+///
+/// The plugin runs in `working_directory` with the [`PluginContext`] as JSON on stdin, and
+/// `CAST_MONOREPO_ROOT` / `CAST_PROJECT_DIR` set when they are known. Its stdout goes to
+/// stderr, like the output of every tool cast runs, and its stderr is passed through.
+pub fn run(
+    working_directory: impl AsRef<Path>,
+    name: &str,
+    args: &[OsString],
+) -> Result<(), PluginsError> {
+    let working_directory = working_directory.as_ref();
+    let plugin = find_plugin(working_directory, name)
+        .ok_or_else(|| PluginsError::NotFound(name.to_string()))?;
+    let context = context(working_directory)?;
+
+    let mut command = Command::new(&plugin.path);
+    command
+        .args(args)
+        .current_dir(working_directory)
+        .stdin(Stdio::piped())
+        .stdout(io::stderr());
+    if let Some(repo_root) = &context.monorepo_root {
+        command.env("CAST_MONOREPO_ROOT", repo_root);
+        if let Some(project) = &context.project {
+            let project_dir = if project.path.as_os_str().is_empty() {
+                repo_root.clone()
+            } else {
+                repo_root.join(&project.path)
+            };
+            command.env("CAST_PROJECT_DIR", project_dir);
+        }
+    }
+
+    let mut child = command.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Plugins that don't read the context may exit before it is written
+        match stdin.write_all(serde_json::to_string(&context)?.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(PluginsError::PluginFailed {
+            name: name.to_string(),
+            code: status.code(),
+        });
+    }
+    Ok(())
+}
+
+/// Directories searched for plugins, in priority order
+fn plugin_directories(working_directory: &Path) -> Vec<(PathBuf, PluginSource)> {
+    let mut directories = Vec::new();
+    if let Ok(repo_root) = find_monorepo_root(working_directory) {
+        directories.push((repo_root.join(REPO_PLUGINS_DIRECTORY), PluginSource::Repo));
+    }
+    if let Some(path) = env::var_os("PATH") {
+        directories.extend(env::split_paths(&path).map(|dir| (dir, PluginSource::Path)));
+    }
+    directories
+}
+
+fn find_plugins_in(directories: &[(PathBuf, PluginSource)]) -> Vec<Plugin> {
+    let mut plugins: BTreeMap<String, Plugin> = BTreeMap::new();
+
+    for (directory, source) in directories {
+        let Ok(entries) = fs::read_dir(directory) else {
+            continue;
+        };
+        let mut found: Vec<(String, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| is_executable(path))
+            .filter_map(|path| Some((plugin_name(&path)?, path)))
+            .collect();
+        found.sort();
+
+        for (name, path) in found {
+            plugins.entry(name.clone()).or_insert(Plugin {
+                name,
+                path,
+                source: *source,
+            });
+        }
+    }
+
+    plugins.into_values().collect()
+}
+
+fn plugin_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let file_name = file_name
+        .strip_suffix(env::consts::EXE_SUFFIX)
+        .unwrap_or(file_name);
+    let name = file_name.strip_prefix(PLUGIN_PREFIX)?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn write_plugin(directory: &Path, file_name: &str, script: &str) -> PathBuf {
+        fs::create_dir_all(directory).unwrap();
+        let path = directory.join(file_name);
+        fs::write(&path, script).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_find_plugins_prefers_repo_plugins() {
+        let tmp_dir = TempDir::new("test_find_plugins").unwrap();
+        let repo = tmp_dir.path().join("repo/.cast/plugins");
+        let path = tmp_dir.path().join("bin");
+        let repo_lint = write_plugin(&repo, "cast-lint", "#!/bin/sh\n");
+        write_plugin(&path, "cast-lint", "#!/bin/sh\n");
+        let path_docs = write_plugin(&path, "cast-docs", "#!/bin/sh\n");
+        write_plugin(&path, "unrelated", "#!/bin/sh\n");
+        fs::write(path.join("cast-notes.txt"), "").unwrap();
+
+        let plugins = find_plugins_in(&[
+            (repo, PluginSource::Repo),
+            (tmp_dir.path().join("missing"), PluginSource::Path),
+            (path, PluginSource::Path),
+        ]);
+
+        #[cfg(unix)]
+        assert_eq!(
+            plugins,
+            vec![
+                Plugin {
+                    name: "docs".into(),
+                    path: path_docs,
+                    source: PluginSource::Path,
+                },
+                Plugin {
+                    name: "lint".into(),
+                    path: repo_lint,
+                    source: PluginSource::Repo,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_context_includes_project_and_graph() {
+        let tmp_dir = TempDir::new("test_plugin_context").unwrap();
+        fs::create_dir_all(tmp_dir.path().join(".git")).unwrap();
+        fs::create_dir_all(tmp_dir.path().join("web/src")).unwrap();
+        fs::create_dir_all(tmp_dir.path().join("web-cloudflare")).unwrap();
+        fs::write(
+            tmp_dir.path().join("web/Cast.toml"),
+            "framework = \"dioxus\"\ndeploys = [\"web-cloudflare\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp_dir.path().join("web-cloudflare/Cast.toml"),
+            "project_type = \"iac\"\n",
+        )
+        .unwrap();
+
+        let context = context(tmp_dir.path().join("web/src")).unwrap();
+
+        assert_eq!(context.monorepo_root, Some(tmp_dir.path().to_path_buf()));
+        let project = context.project.unwrap();
+        assert_eq!(project.path, PathBuf::from("web"));
+        assert_eq!(
+            project.config.deploys,
+            Some(vec!["web-cloudflare".to_string()])
+        );
+        assert_eq!(
+            context
+                .projects
+                .iter()
+                .map(|p| p.path.clone())
+                .collect::<Vec<_>>(),
+            vec![PathBuf::from("web"), PathBuf::from("web-cloudflare")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_passes_args_and_context_to_plugin() {
+        let tmp_dir = TempDir::new("test_run_plugin").unwrap();
+        fs::create_dir_all(tmp_dir.path().join(".git")).unwrap();
+        fs::write(tmp_dir.path().join("Cast.toml"), "exemplar = true\n").unwrap();
+        write_plugin(
+            &tmp_dir.path().join(REPO_PLUGINS_DIRECTORY),
+            "cast-echo",
+            "#!/bin/sh\necho \"$@\" > args.txt\ncat > context.json\necho \"$CAST_PROJECT_DIR\" > project.txt\n",
+        );
+        write_plugin(
+            &tmp_dir.path().join(REPO_PLUGINS_DIRECTORY),
+            "cast-fail",
+            "#!/bin/sh\nexit 4\n",
+        );
+
+        run(tmp_dir.path(), "echo", &["--flag".into(), "value".into()]).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(tmp_dir.path().join("args.txt")).unwrap(),
+            "--flag value\n"
+        );
+        let context: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(tmp_dir.path().join("context.json")).unwrap())
+                .unwrap();
+        assert_eq!(context["project"]["config"]["exemplar"], true);
+        assert_eq!(
+            fs::read_to_string(tmp_dir.path().join("project.txt")).unwrap(),
+            format!("{}\n", tmp_dir.path().display())
+        );
+
+        assert!(matches!(
+            run(tmp_dir.path(), "fail", &[]),
+            Err(PluginsError::PluginFailed { name, code: Some(4) }) if name == "fail"
+        ));
+        assert!(matches!(
+            run(tmp_dir.path(), "missing-plugin-for-test", &[]),
+            Err(PluginsError::NotFound(_))
+        ));
+    }
+}
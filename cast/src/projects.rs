@@ -242,8 +242,56 @@ fn is_valid_git_ref(git_ref: &str) -> bool {
     })
 }
 
+/// Find every Cast project in the monorepo, returned relative to the root and sorted
+///
+/// A directory is a Cast project if it has a Cast.toml or a Cargo.toml with Cast metadata.
+pub(crate) fn find_projects(repo_root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut projects = Vec::new();
+    find_projects_recursive(repo_root, repo_root, &mut projects)?;
+    projects.sort();
+    Ok(projects)
+}
+
+fn find_projects_recursive(
+    dir: &Path,
+    repo_root: &Path,
+    projects: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    // Skip directories that shouldn't be searched
+    if let Some(dir_name) = dir.file_name() {
+        let dir_name = dir_name.to_string_lossy();
+        if dir_name == "target"
+            || dir_name == "node_modules"
+            || dir_name == ".git"
+            || dir_name == "dist"
+            || dir_name == "build"
+        {
+            return Ok(());
+        }
+    }
+
+    let is_project = dir.join("Cast.toml").exists()
+        || CastConfig::load_from_cargo_toml(dir.join("Cargo.toml"))
+            .map(|config| config.has_cast_metadata())
+            .unwrap_or(false);
+    if is_project {
+        if let Ok(relative) = dir.strip_prefix(repo_root) {
+            projects.push(relative.to_path_buf());
+        }
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            find_projects_recursive(&entry.path(), repo_root, projects)?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Find the project directory containing a Cast.toml or Cargo.toml for a given file path
-fn find_project_dir(file_path: &Path, repo_root: &Path) -> Option<PathBuf> {
+pub(crate) fn find_project_dir(file_path: &Path, repo_root: &Path) -> Option<PathBuf> {
     let mut current = file_path;
 
     // If the file path doesn't exist (might be deleted), try its parent
@@ -883,4 +931,37 @@ mod tests {
         let exemplars = result.unwrap();
         assert_eq!(exemplars.len(), 3);
     }
+
+    #[test]
+    fn test_find_projects_returns_sorted_relative_paths() {
+        let tmp_dir = TempDir::new("test_find_projects").unwrap();
+        let root = tmp_dir.path();
+
+        fs::create_dir_all(root.join("web/app")).unwrap();
+        fs::write(root.join("web/Cast.toml"), "").unwrap();
+        fs::write(
+            root.join("web/app/Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[package.metadata.cast]\nframework = \"dioxus\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("api/node_modules/dep")).unwrap();
+        fs::write(root.join("api/Cast.toml"), "").unwrap();
+        fs::write(root.join("api/node_modules/dep/Cast.toml"), "").unwrap();
+        fs::create_dir_all(root.join("plain")).unwrap();
+        fs::write(
+            root.join("plain/Cargo.toml"),
+            "[package]\nname = \"plain\"\n",
+        )
+        .unwrap();
+
+        let projects = find_projects(root).unwrap();
+        assert_eq!(
+            projects,
+            vec![
+                PathBuf::from("api"),
+                PathBuf::from("web"),
+                PathBuf::from("web/app")
+            ]
+        );
+    }
 }
@@ -0,0 +1,824 @@
+use crate::cd::find_monorepo_root;
+use chrono::prelude::*;
+use semver::{Version, VersionReq};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{fs, io};
+use thiserror::Error;
+
+const CHANGELOG_FILE_NAME: &str = "CHANGELOG.md";
+
+const CHANGELOG_HEADER: &str =
+    "# Change Log\n\nAll notable changes to this project will be documented in this file.\n";
+
+#[derive(Error, Debug)]
+pub enum ReleaseError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("CD error: {0}")]
+    CdError(#[from] crate::cd::CdError),
+    #[error("No Cargo.toml or package.json found in {0}")]
+    ManifestNotFound(PathBuf),
+    #[error("No version found in {0}")]
+    VersionNotFound(PathBuf),
+    #[error("Invalid version: {0}")]
+    InvalidVersion(#[from] semver::Error),
+    #[error("Failed to parse manifest: {0}")]
+    ManifestParseError(String),
+    #[error("Tag already exists: {0}")]
+    TagExists(String),
+    #[error("Dependents require a different version: {}", .0.join(", "))]
+    IncompatibleDependents(Vec<String>),
+    #[error("Git error: {0}")]
+    GitError(String),
+}
+
+/// Which part of the version to increment
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+}
+
+pub struct ReleaseOptions {
+    pub bump: Bump,
+    /// Compute the release without writing files, committing or tagging
+    pub dry_run: bool,
+}
+
+/// The outcome of a release
+#[derive(Debug, PartialEq)]
+pub struct ReleaseSummary {
+    pub previous_version: Version,
+    pub version: Version,
+    pub tag: String,
+    pub changes: Vec<ConventionalCommit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ManifestKind {
+    Cargo,
+    Npm,
+}
+
+impl ManifestKind {
+    fn file_name(&self) -> &'static str {
+        match self {
+            ManifestKind::Cargo => "Cargo.toml",
+            ManifestKind::Npm => "package.json",
+        }
+    }
+}
+
+/// A commit message following the conventional commits format (`type(scope)!: description`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConventionalCommit {
+    pub hash: String,
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl fmt::Display for ConventionalCommit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let short_hash: String = self.hash.chars().take(7).collect();
+        match &self.scope {
+            Some(scope) => write!(f, "**{}:** {} ({})", scope, self.description, short_hash),
+            None => write!(f, "{} ({})", self.description, short_hash),
+        }
+    }
+}
+
+/// Release a project
+///
+/// This:
+/// 1. Computes the next version from the project's Cargo.toml or package.json
+/// 2. Checks that every dependent's version requirement still matches the new version
+/// 3. Updates the manifest and prepends a section to CHANGELOG.md built from the
+///    conventional commits touching the project since its previous tag
+/// 4. Commits the changes and creates a `<project>-v<version>` tag
+pub fn run(
+    project_directory: impl AsRef<Path>,
+    options: ReleaseOptions,
+) -> Result<ReleaseSummary, ReleaseError> {
+    let project_directory = project_directory.as_ref();
+    let monorepo_root = find_monorepo_root(project_directory)?;
+    let kind = detect_manifest(project_directory)?;
+    let manifest_path = project_directory.join(kind.file_name());
+    let manifest = fs::read_to_string(&manifest_path)?;
+
+    let (package_name, previous_version) = read_name_and_version(kind, &manifest)
+        .ok_or_else(|| ReleaseError::VersionNotFound(manifest_path.clone()))?;
+    let previous_version = Version::parse(&previous_version)?;
+    let version = bump_version(&previous_version, options.bump);
+
+    let project_name = project_name(project_directory, &package_name);
+    let tag = format!("{}-v{}", project_name, version);
+    if tag_exists(&monorepo_root, &tag)? {
+        return Err(ReleaseError::TagExists(tag));
+    }
+
+    let incompatible = find_incompatible_dependents(&monorepo_root, &package_name, &version)?;
+    if !incompatible.is_empty() {
+        return Err(ReleaseError::IncompatibleDependents(incompatible));
+    }
+
+    let previous_tag = format!("{}-v{}", project_name, previous_version);
+    let previous_tag = tag_exists(&monorepo_root, &previous_tag)?.then_some(previous_tag);
+    let changes = conventional_commits_since(project_directory, previous_tag.as_deref())?;
+
+    if !options.dry_run {
+        let updated_manifest = set_version(kind, &manifest, &previous_version, &version)?;
+        fs::write(&manifest_path, updated_manifest)?;
+
+        let changelog_path = project_directory.join(CHANGELOG_FILE_NAME);
+        let changelog = fs::read_to_string(&changelog_path).unwrap_or_default();
+        let section = render_changelog_section(&version, Utc::now().date_naive(), &changes);
+        fs::write(
+            &changelog_path,
+            insert_changelog_section(&changelog, &section),
+        )?;
+
+        commit_and_tag(project_directory, &[&manifest_path, &changelog_path], &tag)?;
+    }
+
+    Ok(ReleaseSummary {
+        previous_version,
+        version,
+        tag,
+        changes,
+    })
+}
+
+fn detect_manifest(project_directory: &Path) -> Result<ManifestKind, ReleaseError> {
+    [ManifestKind::Cargo, ManifestKind::Npm]
+        .into_iter()
+        .find(|kind| project_directory.join(kind.file_name()).exists())
+        .ok_or_else(|| ReleaseError::ManifestNotFound(project_directory.to_path_buf()))
+}
+
+/// The name used for tags, preferring the project directory name over the package name
+fn project_name(project_directory: &Path, package_name: &str) -> String {
+    fs::canonicalize(project_directory)
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| package_name.to_string())
+}
+
+/// Compute the next version, resetting lower components and dropping pre-release/build metadata
+pub fn bump_version(version: &Version, bump: Bump) -> Version {
+    match bump {
+        Bump::Major => Version::new(version.major + 1, 0, 0),
+        Bump::Minor => Version::new(version.major, version.minor + 1, 0),
+        Bump::Patch => Version::new(version.major, version.minor, version.patch + 1),
+    }
+}
+
+fn read_name_and_version(kind: ManifestKind, manifest: &str) -> Option<(String, String)> {
+    match kind {
+        ManifestKind::Cargo => {
+            let value: toml::Value = toml::from_str(manifest).ok()?;
+            let package = value.get("package")?;
+            Some((
+                package.get("name")?.as_str()?.to_string(),
+                package.get("version")?.as_str()?.to_string(),
+            ))
+        }
+        ManifestKind::Npm => {
+            let value: serde_json::Value = serde_json::from_str(manifest).ok()?;
+            Some((
+                value.get("name")?.as_str()?.to_string(),
+                value.get("version")?.as_str()?.to_string(),
+            ))
+        }
+    }
+}
+
+/// Rewrite the package version, leaving the rest of the manifest untouched
+fn set_version(
+    kind: ManifestKind,
+    manifest: &str,
+    previous: &Version,
+    version: &Version,
+) -> Result<String, ReleaseError> {
+    match kind {
+        ManifestKind::Cargo => set_cargo_version(manifest, previous, version),
+        ManifestKind::Npm => set_npm_version(manifest, version),
+    }
+}
+
+/// Replace the `version` line of the `[package]` table, keeping the manifest's formatting
+fn set_cargo_version(
+    manifest: &str,
+    previous: &Version,
+    version: &Version,
+) -> Result<String, ReleaseError> {
+    let mut in_package_section = false;
+    let mut replaced = false;
+    let mut lines = Vec::new();
+
+    for line in manifest.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            in_package_section = trimmed.starts_with("[package]");
+        }
+
+        let is_version_line = trimmed
+            .strip_prefix("version")
+            .is_some_and(|rest| rest.trim_start().starts_with('='));
+
+        if !replaced && in_package_section && is_version_line {
+            lines.push(line.replacen(&format!("\"{}\"", previous), &format!("\"{}\"", version), 1));
+            replaced = true;
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    if !replaced {
+        return Err(ReleaseError::ManifestParseError(
+            "package version not found".to_string(),
+        ));
+    }
+
+    Ok(lines.concat())
+}
+
+/// Set the top level `version` of a package.json, keeping its keys in order
+fn set_npm_version(manifest: &str, version: &Version) -> Result<String, ReleaseError> {
+    let mut package: serde_json::Value = serde_json::from_str(manifest)
+        .map_err(|e| ReleaseError::ManifestParseError(e.to_string()))?;
+    let package_version = package
+        .get_mut("version")
+        .ok_or_else(|| ReleaseError::ManifestParseError("package version not found".to_string()))?;
+    *package_version = serde_json::Value::String(version.to_string());
+
+    let mut updated = serde_json::to_string_pretty(&package)
+        .map_err(|e| ReleaseError::ManifestParseError(e.to_string()))?;
+    if manifest.ends_with('\n') {
+        updated.push('\n');
+    }
+    Ok(updated)
+}
+
+fn tag_exists(repo_directory: &Path, tag: &str) -> Result<bool, ReleaseError> {
+    let status = Command::new("git")
+        .arg("rev-parse")
+        .arg("--quiet")
+        .arg("--verify")
+        .arg(format!("refs/tags/{}", tag))
+        .current_dir(repo_directory)
+        .output()?
+        .status;
+    Ok(status.success())
+}
+
+/// Collect conventional commits touching the project, newest first
+fn conventional_commits_since(
+    project_directory: &Path,
+    previous_tag: Option<&str>,
+) -> Result<Vec<ConventionalCommit>, ReleaseError> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log").arg("--format=%H%x1f%s%x1f%b%x1e");
+    if let Some(previous_tag) = previous_tag {
+        cmd.arg(format!("{}..HEAD", previous_tag));
+    }
+    let output = cmd
+        .arg("--")
+        .arg(".")
+        .current_dir(project_directory)
+        .output()?;
+
+    // A repository without commits has no history to report
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let log = String::from_utf8_lossy(&output.stdout);
+    Ok(log
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+            let hash = fields.next()?;
+            let subject = fields.next()?;
+            let body = fields.next().unwrap_or_default();
+            parse_conventional_commit(hash, subject, body)
+        })
+        .collect())
+}
+
+/// Parse a commit subject like `feat(cli)!: add release command`
+pub fn parse_conventional_commit(
+    hash: &str,
+    subject: &str,
+    body: &str,
+) -> Option<ConventionalCommit> {
+    let (prefix, description) = subject.split_once(": ")?;
+    let (prefix, breaking) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?.to_string())),
+        None => (prefix, None),
+    };
+
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    Some(ConventionalCommit {
+        hash: hash.to_string(),
+        kind: kind.to_lowercase(),
+        scope,
+        breaking: breaking || body.contains("BREAKING CHANGE"),
+        description: description.trim().to_string(),
+    })
+}
+
+/// Changelog section titles in the order they are rendered
+const CHANGELOG_GROUPS: [&str; 5] = [
+    "Breaking Changes",
+    "Features",
+    "Bug Fixes",
+    "Performance",
+    "Other Changes",
+];
+
+fn changelog_group(commit: &ConventionalCommit) -> &'static str {
+    if commit.breaking {
+        return "Breaking Changes";
+    }
+    match commit.kind.as_str() {
+        "feat" => "Features",
+        "fix" => "Bug Fixes",
+        "perf" => "Performance",
+        _ => "Other Changes",
+    }
+}
+
+/// Render a Keep a Changelog style section for a version
+pub fn render_changelog_section(
+    version: &Version,
+    date: NaiveDate,
+    changes: &[ConventionalCommit],
+) -> String {
+    let mut section = format!("## [{}] - {}\n", version, date.format("%Y-%m-%d"));
+    if changes.is_empty() {
+        section.push_str("\n- No notable changes\n");
+    }
+
+    for title in CHANGELOG_GROUPS {
+        let entries: Vec<String> = changes
+            .iter()
+            .filter(|c| changelog_group(c) == title)
+            .map(|c| format!("- {}", c))
+            .collect();
+        if !entries.is_empty() {
+            section.push_str(&format!("\n### {}\n\n{}\n", title, entries.join("\n")));
+        }
+    }
+
+    section
+}
+
+/// Insert a version section after any `[Unreleased]` section and before earlier releases
+fn insert_changelog_section(changelog: &str, section: &str) -> String {
+    if changelog.trim().is_empty() {
+        return format!("{}\n{}", CHANGELOG_HEADER, section);
+    }
+
+    let mut offset = 0;
+    for line in changelog.split_inclusive('\n') {
+        if line.starts_with("## ") && !line.contains("[Unreleased]") {
+            return format!(
+                "{}{}\n{}",
+                &changelog[..offset],
+                section,
+                &changelog[offset..]
+            );
+        }
+        offset += line.len();
+    }
+
+    let separator = if changelog.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    format!("{}{}{}", changelog, separator, section)
+}
+
+/// Find dependents in the monorepo whose version requirement no longer matches the new version
+fn find_incompatible_dependents(
+    monorepo_root: &Path,
+    package_name: &str,
+    version: &Version,
+) -> Result<Vec<String>, ReleaseError> {
+    let mut manifests = Vec::new();
+    find_manifests_recursive(monorepo_root, &mut manifests)?;
+    manifests.sort();
+
+    let mut incompatible = Vec::new();
+    for manifest_path in manifests {
+        let Ok(contents) = fs::read_to_string(&manifest_path) else {
+            continue;
+        };
+        let requirements = if manifest_path.ends_with("Cargo.toml") {
+            cargo_requirements(&contents, package_name)
+        } else {
+            npm_requirements(&contents, package_name)
+        };
+
+        for requirement in requirements {
+            // Requirements that aren't semver ranges (paths, git, workspace links) can't conflict
+            if let Ok(req) = VersionReq::parse(&requirement) {
+                if !req.matches(version) {
+                    let relative = manifest_path
+                        .strip_prefix(monorepo_root)
+                        .unwrap_or(&manifest_path);
+                    incompatible.push(format!("{} ({})", relative.display(), requirement));
+                }
+            }
+        }
+    }
+
+    Ok(incompatible)
+}
+
+fn find_manifests_recursive(dir: &Path, manifests: &mut Vec<PathBuf>) -> io::Result<()> {
+    if let Some(dir_name) = dir.file_name() {
+        let dir_name = dir_name.to_string_lossy();
+        if dir_name == "target" || dir_name == "node_modules" || dir_name == ".git" {
+            return Ok(());
+        }
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_manifests_recursive(&path, manifests)?;
+        } else if path.ends_with("Cargo.toml") || path.ends_with("package.json") {
+            manifests.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Version requirements on a crate from every dependency table in a Cargo.toml
+fn cargo_requirements(manifest: &str, package_name: &str) -> Vec<String> {
+    let Ok(value) = toml::from_str::<toml::Value>(manifest) else {
+        return Vec::new();
+    };
+
+    let mut tables = Vec::new();
+    for key in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        tables.extend(value.get(key));
+        tables.extend(value.get("workspace").and_then(|w| w.get(key)));
+    }
+
+    tables
+        .into_iter()
+        .filter_map(|table| table.as_table())
+        .flat_map(|table| table.iter())
+        .filter_map(|(name, dependency)| match dependency {
+            toml::Value::String(requirement) if name == package_name => Some(requirement.clone()),
+            toml::Value::Table(details) => {
+                let actual_name = details
+                    .get("package")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or(name);
+                if actual_name != package_name {
+                    return None;
+                }
+                details
+                    .get("version")
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Version requirements on a package from the dependency maps of a package.json
+fn npm_requirements(manifest: &str, package_name: &str) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(manifest) else {
+        return Vec::new();
+    };
+
+    [
+        "dependencies",
+        "devDependencies",
+        "peerDependencies",
+        "optionalDependencies",
+    ]
+    .iter()
+    .filter_map(|key| value.get(key)?.get(package_name)?.as_str())
+    .map(String::from)
+    .collect()
+}
+
+fn commit_and_tag(
+    project_directory: &Path,
+    files: &[&Path],
+    tag: &str,
+) -> Result<(), ReleaseError> {
+    run_git(project_directory, |cmd| {
+        cmd.arg("add").arg("--").args(files);
+    })?;
+    run_git(project_directory, |cmd| {
+        cmd.arg("commit")
+            .arg("-m")
+            .arg(format!("chore(release): {}", tag))
+            .arg("--")
+            .args(files);
+    })?;
+    run_git(project_directory, |cmd| {
+        cmd.arg("tag")
+            .arg("-a")
+            .arg(tag)
+            .arg("-m")
+            .arg(format!("Release {}", tag));
+    })
+}
+
+fn run_git(directory: &Path, configure: impl FnOnce(&mut Command)) -> Result<(), ReleaseError> {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(directory);
+    configure(&mut cmd);
+    let output = cmd.output()?;
+
+    if !output.status.success() {
+        return Err(ReleaseError::GitError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn git(directory: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(directory)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn init_repo(directory: &Path) {
+        git(directory, &["init", "-q"]);
+        git(directory, &["config", "user.name", "Cast Test"]);
+        git(directory, &["config", "user.email", "cast@example.com"]);
+        git(directory, &["config", "commit.gpgsign", "false"]);
+        git(directory, &["config", "tag.gpgsign", "false"]);
+    }
+
+    #[test]
+    fn test_bump_version() {
+        let version = Version::parse("1.2.3-beta.1").unwrap();
+        assert_eq!(bump_version(&version, Bump::Major), Version::new(2, 0, 0));
+        assert_eq!(bump_version(&version, Bump::Minor), Version::new(1, 3, 0));
+        assert_eq!(bump_version(&version, Bump::Patch), Version::new(1, 2, 4));
+    }
+
+    #[test]
+    fn test_parse_conventional_commit() {
+        let commit = parse_conventional_commit("abc", "feat(cli)!: add release", "").unwrap();
+        assert_eq!(commit.kind, "feat");
+        assert_eq!(commit.scope, Some("cli".to_string()));
+        assert!(commit.breaking);
+        assert_eq!(commit.description, "add release");
+
+        let commit =
+            parse_conventional_commit("abc", "fix: handle tags", "BREAKING CHANGE: yes").unwrap();
+        assert_eq!(commit.scope, None);
+        assert!(commit.breaking);
+
+        assert_eq!(
+            parse_conventional_commit("abc", "[user-028] Add release", ""),
+            None
+        );
+        assert_eq!(parse_conventional_commit("abc", "Update README", ""), None);
+    }
+
+    #[test]
+    fn test_render_changelog_section_groups_changes() {
+        let changes = vec![
+            parse_conventional_commit("1111111aaa", "feat: add a", "").unwrap(),
+            parse_conventional_commit("2222222bbb", "fix(core): fix b", "").unwrap(),
+            parse_conventional_commit("3333333ccc", "docs: document c", "").unwrap(),
+        ];
+        let date = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        let section = render_changelog_section(&Version::new(0, 2, 0), date, &changes);
+        assert_eq!(
+            section,
+            "## [0.2.0] - 2025-01-02\n\n### Features\n\n- add a (1111111)\n\n### Bug Fixes\n\n- **core:** fix b (2222222)\n\n### Other Changes\n\n- document c (3333333)\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_changelog_section_keeps_unreleased_first() {
+        let changelog = "# Change Log\n\n## [Unreleased]\n\n- Initial release\n\n## [0.1.0] - 2025-01-01\n\n- Old\n";
+        let updated = insert_changelog_section(changelog, "## [0.2.0] - 2025-02-01\n");
+        assert_eq!(
+            updated,
+            "# Change Log\n\n## [Unreleased]\n\n- Initial release\n\n## [0.2.0] - 2025-02-01\n\n## [0.1.0] - 2025-01-01\n\n- Old\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_changelog_section_creates_header() {
+        let updated = insert_changelog_section("", "## [0.1.1] - 2025-02-01\n");
+        assert!(updated.starts_with("# Change Log"));
+        assert!(updated.ends_with("## [0.1.1] - 2025-02-01\n"));
+    }
+
+    #[test]
+    fn test_set_version_only_changes_package_version() {
+        let manifest = "[package]\nname = \"cast\"\nversion = \"0.1.0\"\n\n[dependencies]\nfoo = { version = \"0.1.0\" }\n";
+        let updated = set_version(
+            ManifestKind::Cargo,
+            manifest,
+            &Version::new(0, 1, 0),
+            &Version::new(0, 2, 0),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            "[package]\nname = \"cast\"\nversion = \"0.2.0\"\n\n[dependencies]\nfoo = { version = \"0.1.0\" }\n"
+        );
+
+        let manifest =
+            "{\n  \"name\": \"cast\",\n  \"version\": \"0.0.1\",\n  \"engines\": {}\n}\n";
+        let updated = set_version(
+            ManifestKind::Npm,
+            manifest,
+            &Version::new(0, 0, 1),
+            &Version::new(0, 0, 2),
+        )
+        .unwrap();
+        assert!(updated.contains("\"version\": \"0.0.2\""));
+    }
+
+    #[test]
+    fn test_set_npm_version_skips_nested_versions() {
+        let manifest = "{\n  \"name\": \"cast\",\n  \"scripts\": {\n    \"version\": \"npm run build\"\n  },\n  \"engines\": {\n    \"node\": \">=20\",\n    \"version\": \"0.0.1\"\n  },\n  \"version\": \"0.0.1\",\n  \"private\": true\n}\n";
+        let updated = set_version(
+            ManifestKind::Npm,
+            manifest,
+            &Version::new(0, 0, 1),
+            &Version::new(0, 0, 2),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            manifest.replace(
+                "\"version\": \"0.0.1\",\n  \"private\"",
+                "\"version\": \"0.0.2\",\n  \"private\""
+            )
+        );
+
+        assert!(matches!(
+            set_version(
+                ManifestKind::Npm,
+                "{ \"scripts\": { \"version\": \"0.0.1\" } }",
+                &Version::new(0, 0, 1),
+                &Version::new(0, 0, 2),
+            ),
+            Err(ReleaseError::ManifestParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_cargo_requirements_handles_renames_and_paths() {
+        let manifest = "[dependencies]\ncast = { path = \"../cast\", version = \"0.1\" }\nother = \"1\"\n\n[dev-dependencies]\nrenamed = { package = \"cast\", version = \"^0.1.2\" }\n";
+        assert_eq!(
+            cargo_requirements(manifest, "cast"),
+            vec!["0.1".to_string(), "^0.1.2".to_string()]
+        );
+        assert!(
+            cargo_requirements("[dependencies]\ncast = { path = \"../cast\" }", "cast").is_empty()
+        );
+    }
+
+    #[test]
+    fn test_release_fails_when_dependents_are_incompatible() {
+        let tmp_dir = TempDir::new("test_release_dependents").unwrap();
+        init_repo(tmp_dir.path());
+        let project = tmp_dir.path().join("lib");
+        let dependent = tmp_dir.path().join("app");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(&dependent).unwrap();
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"lib\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dependent.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nlib = { path = \"../lib\", version = \"0.1\" }\n",
+        )
+        .unwrap();
+
+        let result = run(
+            &project,
+            ReleaseOptions {
+                bump: Bump::Minor,
+                dry_run: false,
+            },
+        );
+        match result {
+            Err(ReleaseError::IncompatibleDependents(dependents)) => {
+                assert_eq!(dependents, vec!["app/Cargo.toml (0.1)".to_string()]);
+            }
+            other => panic!("Expected IncompatibleDependents, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_release_updates_manifest_changelog_and_tags() {
+        let tmp_dir = TempDir::new("test_release").unwrap();
+        init_repo(tmp_dir.path());
+        let project = tmp_dir.path().join("cast");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"cast\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        git(tmp_dir.path(), &["add", "."]);
+        git(
+            tmp_dir.path(),
+            &["commit", "-q", "-m", "feat: initial cast"],
+        );
+        fs::write(tmp_dir.path().join("README.md"), "outside project").unwrap();
+        git(tmp_dir.path(), &["add", "."]);
+        git(
+            tmp_dir.path(),
+            &["commit", "-q", "-m", "fix: outside the project"],
+        );
+
+        let summary = run(
+            &project,
+            ReleaseOptions {
+                bump: Bump::Minor,
+                dry_run: false,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(summary.version, Version::new(0, 2, 0));
+        assert_eq!(summary.tag, "cast-v0.2.0");
+        assert_eq!(summary.changes.len(), 1);
+        assert!(fs::read_to_string(project.join("Cargo.toml"))
+            .unwrap()
+            .contains("version = \"0.2.0\""));
+        let changelog = fs::read_to_string(project.join(CHANGELOG_FILE_NAME)).unwrap();
+        assert!(changelog.contains("## [0.2.0]"));
+        assert!(changelog.contains("- initial cast"));
+        assert!(!changelog.contains("outside the project"));
+        assert!(tag_exists(tmp_dir.path(), "cast-v0.2.0").unwrap());
+    }
+
+    #[test]
+    fn test_release_dry_run_does_not_write() {
+        let tmp_dir = TempDir::new("test_release_dry_run").unwrap();
+        init_repo(tmp_dir.path());
+        fs::write(
+            tmp_dir.path().join("package.json"),
+            "{\n  \"name\": \"ext\",\n  \"version\": \"0.0.1\"\n}\n",
+        )
+        .unwrap();
+
+        let summary = run(
+            tmp_dir.path(),
+            ReleaseOptions {
+                bump: Bump::Patch,
+                dry_run: true,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(summary.version, Version::new(0, 0, 2));
+        assert!(fs::read_to_string(tmp_dir.path().join("package.json"))
+            .unwrap()
+            .contains("0.0.1"));
+        assert!(!tmp_dir.path().join(CHANGELOG_FILE_NAME).exists());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::CastConfig;
+use std::io;
 use std::path::Path;
 use std::process::Command;
 use thiserror::Error;
@@ -31,6 +32,7 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), RunError> {
     let status = Command::new(command)
         .args(&args)
         .current_dir(working_directory)
+        .stdout(io::stderr())
         .status()?;
 
     if !status.success() {
@@ -22,9 +22,9 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), ServeError> {
     let server =
         Server::http("127.0.0.1:8000").map_err(|e| ServeError::BindError(e.to_string()))?;
 
-    println!("Serving static files from: {}", working_directory.display());
-    println!("Server running at http://127.0.0.1:8000");
-    println!("Press Ctrl+C to stop");
+    eprintln!("Serving static files from: {}", working_directory.display());
+    eprintln!("Server running at http://127.0.0.1:8000");
+    eprintln!("Press Ctrl+C to stop");
 
     // Canonicalize working directory once before the request loop
     let canonical_base = fs::canonicalize(working_directory).map_err(ServeError::IoError)?;
@@ -118,6 +118,7 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), ServeError> {
 }
 
 /// Create a Content-Type header safely
+#[allow(clippy::unwrap_used)] // The fallback header is a static, valid ASCII value
 fn create_content_type_header(content_type: &str) -> Header {
     // Since we control the content type strings, this should never fail
     // But we handle it gracefully just in case
@@ -1,4 +1,7 @@
 use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs::OpenOptions;
 use std::io::Write;
@@ -7,7 +10,17 @@ use std::{fs, io};
 use thiserror::Error;
 use uuid::Uuid;
 
-const SESSIONS_DIRECTORY: &str = ".cast/sessions";
+pub(crate) const SESSIONS_DIRECTORY: &str = ".cast/sessions";
+
+/// Version of the session records written by this cast
+///
+/// Bump it when a record changes incompatibly, and teach `parse_line` to read the old version.
+pub const SESSION_SCHEMA_VERSION: u64 = 1;
+
+/// Extension of JSON Lines session logs
+const SESSION_LOG_EXTENSION: &str = "jsonl";
+/// Extension of legacy `{timestamp},{kind},{name}` session logs
+const LEGACY_SESSION_LOG_EXTENSION: &str = "log";
 
 pub struct SessionStartOptions {
     pub(crate) name: Option<String>,
@@ -18,31 +31,60 @@ struct SessionEntry {
     timestamp: DateTime<Utc>,
     kind: SessionEntryKind,
     name: Option<String>,
+    metadata: BTreeMap<String, Value>,
+}
+
+/// One line of a JSON Lines session log
+#[derive(Serialize, Deserialize)]
+struct SessionRecord {
+    version: u64,
+    id: String,
+    kind: SessionEntryKind,
+    /// RFC 3339
+    timestamp: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    metadata: BTreeMap<String, Value>,
 }
 
 impl fmt::Display for SessionEntry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let postfix = if let Some(name) = &self.name {
-            format!(",{}", name)
-        } else {
-            String::new()
+        let record = SessionRecord {
+            version: SESSION_SCHEMA_VERSION,
+            id: self.session_id.to_string(),
+            kind: self.kind,
+            timestamp: self.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            name: self.name.clone(),
+            metadata: self.metadata.clone(),
         };
-        write!(f, "{},{}{}", self.timestamp, self.kind, postfix)
+        let line = serde_json::to_string(&record).map_err(|_| fmt::Error)?;
+        write!(f, "{}", line)
     }
 }
 
 impl SessionEntry {
+    fn new(session_id: Uuid, kind: SessionEntryKind, name: Option<String>) -> Self {
+        SessionEntry {
+            session_id,
+            timestamp: Utc::now(),
+            kind,
+            name,
+            metadata: BTreeMap::new(),
+        }
+    }
+
     fn file_name(&self) -> String {
         let postfix = if let Some(name) = &self.name {
             format!("-{}", name)
         } else {
             String::new()
         };
-        format!("{}{}.log", self.session_id, postfix)
+        format!("{}{}.{}", self.session_id, postfix, SESSION_LOG_EXTENSION)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 enum SessionEntryKind {
     Start,
     Pause,
@@ -72,12 +114,15 @@ pub fn start(
     let sessions_directory = working_directory.as_ref().join(SESSIONS_DIRECTORY);
     fs::create_dir_all(&sessions_directory)?;
 
-    let entry = SessionEntry {
-        session_id: Uuid::now_v7(),
-        timestamp: Utc::now(),
-        kind: SessionEntryKind::Start,
-        name: options.and_then(|v| v.name),
-    };
+    let mut entry = SessionEntry::new(
+        Uuid::now_v7(),
+        SessionEntryKind::Start,
+        options.and_then(|v| v.name),
+    );
+    entry.metadata.insert(
+        "cast_version".into(),
+        Value::String(env!("CARGO_PKG_VERSION").into()),
+    );
     let session_path = sessions_directory.join(entry.file_name());
 
     fs::write(session_path, format!("{}\n", entry))?;
@@ -90,21 +135,21 @@ pub enum PauseSessionError {
     Io(#[from] io::Error),
     #[error("no active session found")]
     NoActiveSession,
+    #[error("{0}")]
+    Log(#[from] SessionLogError),
 }
 
 pub fn pause(working_directory: impl AsRef<Path>) -> Result<(), PauseSessionError> {
     let sessions_directory = working_directory.as_ref().join(SESSIONS_DIRECTORY);
-    let latest_session =
-        find_latest_session(&sessions_directory).ok_or(PauseSessionError::NoActiveSession)?;
-
-    let entry = SessionEntry {
-        session_id: latest_session.session_id,
-        timestamp: Utc::now(),
-        kind: SessionEntryKind::Pause,
-        name: latest_session.name,
-    };
+    let (latest_session, session_path) =
+        find_latest_session(&sessions_directory)?.ok_or(PauseSessionError::NoActiveSession)?;
+
+    let entry = SessionEntry::new(
+        latest_session.session_id,
+        SessionEntryKind::Pause,
+        latest_session.name,
+    );
 
-    let session_path = sessions_directory.join(entry.file_name());
     let mut file = OpenOptions::new().append(true).open(session_path)?;
     writeln!(file, "{}", entry)?;
     Ok(())
@@ -116,88 +161,286 @@ pub enum StopSessionError {
     Io(#[from] io::Error),
     #[error("no active session found")]
     NoActiveSession,
+    #[error("{0}")]
+    Log(#[from] SessionLogError),
 }
 
 pub fn stop(working_directory: impl AsRef<Path>) -> Result<(), StopSessionError> {
     let sessions_directory = working_directory.as_ref().join(SESSIONS_DIRECTORY);
-    let latest_session =
-        find_latest_session(&sessions_directory).ok_or(StopSessionError::NoActiveSession)?;
-
-    let entry = SessionEntry {
-        session_id: latest_session.session_id,
-        timestamp: Utc::now(),
-        kind: SessionEntryKind::Stop,
-        name: latest_session.name,
-    };
+    let (latest_session, session_path) =
+        find_latest_session(&sessions_directory)?.ok_or(StopSessionError::NoActiveSession)?;
+
+    let entry = SessionEntry::new(
+        latest_session.session_id,
+        SessionEntryKind::Stop,
+        latest_session.name,
+    );
 
-    let session_path = sessions_directory.join(entry.file_name());
     let mut file = OpenOptions::new().append(true).open(session_path)?;
     writeln!(file, "{}", entry)?;
     Ok(())
 }
 
-/// Find the most recent session by UUID v7 timestamp
-fn find_latest_session(sessions_directory: &Path) -> Option<SessionEntry> {
-    if !sessions_directory.exists() {
-        return None;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    Active,
+    Paused,
+    Stopped,
+}
+
+/// State of the most recent session, read from its log
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SessionState {
+    pub id: String,
+    pub name: Option<String>,
+    pub status: SessionStatus,
+    /// RFC 3339 time of the Start entry
+    pub started_at: String,
+    /// RFC 3339 time of the latest Pause or Stop entry, when the session isn't active
+    pub ended_at: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum SessionLogError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid session log entry: {0}")]
+    InvalidEntry(String),
+    #[error("session log entry has schema version {0}, but this cast only reads up to version {SESSION_SCHEMA_VERSION}")]
+    UnsupportedVersion(u64),
+    #[error("session log {0} has no Start entry")]
+    MissingStart(PathBuf),
+    #[error("{0} already exists")]
+    AlreadyMigrated(PathBuf),
+    #[error("Some session logs could not be migrated\n{0}")]
+    MigrationFailed(Box<SessionMigration>),
+}
+
+/// Legacy session logs rewritten by [`migrate`], and those left in place
+#[derive(Debug, Default, Serialize)]
+pub struct SessionMigration {
+    /// The new JSON Lines logs
+    pub migrated: Vec<PathBuf>,
+    pub failed: Vec<FailedSessionMigration>,
+}
+
+/// A legacy session log that was left untouched, and why
+#[derive(Debug, Serialize)]
+pub struct FailedSessionMigration {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+impl fmt::Display for SessionMigration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.migrated.is_empty() && self.failed.is_empty() {
+            return writeln!(f, "No session logs to migrate");
+        }
+        if !self.migrated.is_empty() {
+            writeln!(f, "Migrated session logs:")?;
+            for path in &self.migrated {
+                writeln!(f, "  {}", path.display())?;
+            }
+        }
+        if !self.failed.is_empty() {
+            writeln!(f, "Session logs left as they were:")?;
+            for failed in &self.failed {
+                writeln!(f, "  ✗ {}: {}", failed.path.display(), failed.error)?;
+            }
+        }
+        Ok(())
     }
+}
 
-    let entries = fs::read_dir(sessions_directory).ok()?;
-    let mut latest: Option<(Uuid, PathBuf)> = None;
+/// Read the state of the most recent session, or `None` when no session was started
+pub fn current(working_directory: impl AsRef<Path>) -> Result<Option<SessionState>, SessionLogError> {
+    let sessions_directory = working_directory.as_ref().join(SESSIONS_DIRECTORY);
+    let Some((session_id, path)) = find_latest_session_log(&sessions_directory) else {
+        return Ok(None);
+    };
 
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) != Some("log") {
-            continue;
+    let entries = read_session_log(session_id, &path)?;
+    let start = entries
+        .iter()
+        .find(|entry| entry.kind == SessionEntryKind::Start)
+        .ok_or_else(|| SessionLogError::MissingStart(path.clone()))?;
+    let (status, ended_at) = match entries.last() {
+        Some(entry) if entry.kind == SessionEntryKind::Pause => {
+            (SessionStatus::Paused, Some(entry.timestamp))
+        }
+        Some(entry) if entry.kind == SessionEntryKind::Stop => {
+            (SessionStatus::Stopped, Some(entry.timestamp))
         }
+        _ => (SessionStatus::Active, None),
+    };
 
-        let filename = match path.file_stem().and_then(|s| s.to_str()) {
-            Some(f) => f,
-            None => continue,
-        };
+    Ok(Some(SessionState {
+        id: session_id.to_string(),
+        name: start.name.clone(),
+        status,
+        started_at: start.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        ended_at: ended_at.map(|timestamp| timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+    }))
+}
 
-        // Extract UUID and optional name from filename
-        // Format is either "uuid.log" or "uuid-name.log" where name doesn't contain dashes from the UUID
-        // UUID v7 format: xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx (8-4-4-4-12 hex digits)
-        // We need to extract the first 36 characters as the UUID
-        let uuid_str = if filename.len() >= 36 {
-            &filename[..36]
-        } else {
-            filename
-        };
+/// Rewrite every legacy `.log` session log as JSON Lines
+///
+/// Logs are only removed once their replacement is written. A log that fails to parse, or whose
+/// replacement already exists, is left untouched and reported in
+/// [`SessionMigration::failed`] while the other logs are still migrated.
+pub fn migrate(working_directory: impl AsRef<Path>) -> SessionMigration {
+    let sessions_directory = working_directory.as_ref().join(SESSIONS_DIRECTORY);
+    let mut migration = SessionMigration::default();
 
-        if let Ok(uuid) = Uuid::parse_str(uuid_str) {
-            if let Some((latest_uuid, _)) = latest {
-                // UUID v7 embeds timestamp, so we can compare directly
-                if uuid > latest_uuid {
-                    latest = Some((uuid, path.clone()));
-                }
-            } else {
-                latest = Some((uuid, path.clone()));
-            }
+    for (session_id, path) in session_logs(&sessions_directory) {
+        if path.extension().and_then(|s| s.to_str()) != Some(LEGACY_SESSION_LOG_EXTENSION) {
+            continue;
+        }
+        match migrate_log(session_id, &path) {
+            Ok(destination) => migration.migrated.push(destination),
+            Err(e) => migration.failed.push(FailedSessionMigration {
+                path,
+                error: e.to_string(),
+            }),
         }
     }
 
-    if let Some((uuid, path)) = latest {
-        // Extract the name from the filename if present
-        let filename = path.file_stem()?.to_str()?;
-        // Name starts after the UUID and the separator dash
-        let name = if filename.len() > 37 {
-            // 36 chars for UUID + 1 char for dash
-            Some(filename[37..].to_string())
-        } else {
-            None
-        };
+    migration.migrated.sort();
+    migration.failed.sort_by(|a, b| a.path.cmp(&b.path));
+    migration
+}
+
+/// Rewrite one legacy session log as JSON Lines, returning the new log
+fn migrate_log(session_id: Uuid, path: &Path) -> Result<PathBuf, SessionLogError> {
+    let entries = read_session_log(session_id, path)?;
+    let destination = path.with_extension(SESSION_LOG_EXTENSION);
+    if destination.exists() {
+        return Err(SessionLogError::AlreadyMigrated(destination));
+    }
+
+    let content: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+    fs::write(&destination, content)?;
+    fs::remove_file(path)?;
+    Ok(destination)
+}
 
-        Some(SessionEntry {
-            session_id: uuid,
-            timestamp: Utc::now(), // This will be overwritten when creating new entry
-            kind: SessionEntryKind::Start, // Placeholder, actual kind doesn't matter for finding session
+/// Read every entry of a session log, accepting JSON Lines records and legacy lines
+fn read_session_log(session_id: Uuid, path: &Path) -> Result<Vec<SessionEntry>, SessionLogError> {
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_line(session_id, line))
+        .collect()
+}
+
+fn parse_line(session_id: Uuid, line: &str) -> Result<SessionEntry, SessionLogError> {
+    let invalid = || SessionLogError::InvalidEntry(line.to_string());
+
+    if !line.trim_start().starts_with('{') {
+        let (timestamp, kind, name) = parse_legacy_line(line)?;
+        return Ok(SessionEntry {
+            session_id,
+            timestamp,
+            kind,
             name,
-        })
-    } else {
-        None
+            metadata: BTreeMap::new(),
+        });
     }
+
+    let value: Value = serde_json::from_str(line).map_err(|_| invalid())?;
+    match value.get("version").and_then(Value::as_u64) {
+        Some(version) if version > SESSION_SCHEMA_VERSION => {
+            return Err(SessionLogError::UnsupportedVersion(version))
+        }
+        Some(_) => {}
+        None => return Err(invalid()),
+    }
+
+    let record: SessionRecord = serde_json::from_value(value).map_err(|_| invalid())?;
+    Ok(SessionEntry {
+        session_id: Uuid::parse_str(&record.id).map_err(|_| invalid())?,
+        timestamp: DateTime::parse_from_rfc3339(&record.timestamp)
+            .map_err(|_| invalid())?
+            .with_timezone(&Utc),
+        kind: record.kind,
+        name: record.name,
+        metadata: record.metadata,
+    })
+}
+
+/// Parse a legacy `{timestamp},{kind}[,{name}]` log line
+fn parse_legacy_line(
+    line: &str,
+) -> Result<(DateTime<Utc>, SessionEntryKind, Option<String>), SessionLogError> {
+    let invalid = || SessionLogError::InvalidEntry(line.to_string());
+    let mut parts = line.splitn(3, ',');
+    let timestamp = parts.next().ok_or_else(invalid)?;
+    let kind = match parts.next() {
+        Some("Start") => SessionEntryKind::Start,
+        Some("Pause") => SessionEntryKind::Pause,
+        Some("Stop") => SessionEntryKind::Stop,
+        _ => return Err(invalid()),
+    };
+    let name = parts.next().map(str::to_string);
+
+    // Timestamps were written with `DateTime<Utc>`'s Display, e.g. `2025-01-01 12:00:00.5 UTC`
+    let timestamp = NaiveDateTime::parse_from_str(
+        timestamp.trim_end_matches(" UTC"),
+        "%Y-%m-%d %H:%M:%S%.f",
+    )
+    .map_err(|_| invalid())?
+    .and_utc();
+
+    Ok((timestamp, kind, name))
+}
+
+/// Every session log with the session id from its file name
+///
+/// Logs are named `{uuid}.jsonl` or `{uuid}-{name}.jsonl` (`.log` for legacy logs).
+fn session_logs(sessions_directory: &Path) -> Vec<(Uuid, PathBuf)> {
+    let Ok(entries) = fs::read_dir(sessions_directory) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|s| s.to_str()),
+                Some(SESSION_LOG_EXTENSION | LEGACY_SESSION_LOG_EXTENSION)
+            )
+        })
+        .filter_map(|path| {
+            // The session id is the first 36 characters (a hyphenated UUID)
+            let filename = path.file_stem()?.to_str()?;
+            let uuid = Uuid::parse_str(filename.get(..36)?).ok()?;
+            Some((uuid, path))
+        })
+        .collect()
+}
+
+/// Find the log of the most recent session by UUID v7 timestamp
+fn find_latest_session_log(sessions_directory: &Path) -> Option<(Uuid, PathBuf)> {
+    session_logs(sessions_directory)
+        .into_iter()
+        .max_by_key(|(uuid, _)| *uuid)
+}
+
+/// Find the Start entry and log of the most recent session
+fn find_latest_session(
+    sessions_directory: &Path,
+) -> Result<Option<(SessionEntry, PathBuf)>, SessionLogError> {
+    let Some((session_id, path)) = find_latest_session_log(sessions_directory) else {
+        return Ok(None);
+    };
+
+    let start = read_session_log(session_id, &path)?
+        .into_iter()
+        .find(|entry| entry.kind == SessionEntryKind::Start)
+        .ok_or_else(|| SessionLogError::MissingStart(path.clone()))?;
+    Ok(Some((start, path)))
 }
 
 #[cfg(test)]
@@ -213,8 +456,9 @@ mod tests {
             timestamp: Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap(),
             kind: SessionEntryKind::Start,
             name: None,
+            metadata: BTreeMap::new(),
         };
-        assert_eq!(entry.file_name(), format!("{}.log", TEST_UUID))
+        assert_eq!(entry.file_name(), format!("{}.jsonl", TEST_UUID))
     }
 
     #[test]
@@ -226,10 +470,11 @@ mod tests {
             timestamp: Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap(),
             kind: SessionEntryKind::Start,
             name: Some(TEST_NAME.into()),
+            metadata: BTreeMap::new(),
         };
         assert_eq!(
             entry.file_name(),
-            format!("{}-{}.log", TEST_UUID, TEST_NAME)
+            format!("{}-{}.jsonl", TEST_UUID, TEST_NAME)
         )
     }
 
@@ -242,10 +487,14 @@ mod tests {
             timestamp: test_timestamp,
             kind: SessionEntryKind::Start,
             name: None,
+            metadata: BTreeMap::new(),
         };
         assert_eq!(
             entry.to_string(),
-            "2025-01-01 12:00:00 UTC,Start".to_string()
+            format!(
+                r#"{{"version":1,"id":"{}","kind":"Start","timestamp":"2025-01-01T12:00:00Z","name":null,"metadata":{{}}}}"#,
+                TEST_UUID
+            )
         )
     }
 
@@ -259,10 +508,14 @@ mod tests {
             timestamp: test_timestamp,
             kind: SessionEntryKind::Start,
             name: Some(TEST_NAME.into()),
+            metadata: BTreeMap::from([("cast_version".into(), Value::from("0.1.0"))]),
         };
         assert_eq!(
             entry.to_string(),
-            format!("2025-01-01 12:00:00 UTC,Start,{}", TEST_NAME)
+            format!(
+                r#"{{"version":1,"id":"{}","kind":"Start","timestamp":"2025-01-01T12:00:00Z","name":"{}","metadata":{{"cast_version":"0.1.0"}}}}"#,
+                TEST_UUID, TEST_NAME
+            )
         )
     }
 
@@ -510,4 +763,118 @@ mod tests {
             "Second session should have Pause entry"
         );
     }
+
+    #[test]
+    fn current_reads_latest_session_state() {
+        use tempdir::TempDir;
+
+        let tmp_dir = TempDir::new("test").unwrap();
+        assert_eq!(current(tmp_dir.path()).unwrap(), None);
+
+        start(
+            tmp_dir.path(),
+            Some(SessionStartOptions {
+                name: Some("review".to_string()),
+            }),
+        )
+        .unwrap();
+        let state = current(tmp_dir.path()).unwrap().unwrap();
+        assert_eq!(state.name.as_deref(), Some("review"));
+        assert_eq!(state.status, SessionStatus::Active);
+        assert_eq!(state.ended_at, None);
+
+        stop(tmp_dir.path()).unwrap();
+        let stopped = current(tmp_dir.path()).unwrap().unwrap();
+        assert_eq!(stopped.status, SessionStatus::Stopped);
+        assert_eq!(stopped.started_at, state.started_at);
+        assert!(stopped.ended_at.is_some());
+    }
+
+    #[test]
+    fn parse_legacy_line_reads_written_entries() {
+        let (timestamp, kind, name) =
+            parse_legacy_line("2025-01-01 12:00:00.250 UTC,Pause,a, b").unwrap();
+        assert_eq!(timestamp.to_rfc3339(), "2025-01-01T12:00:00.250+00:00");
+        assert_eq!(kind, SessionEntryKind::Pause);
+        assert_eq!(name.as_deref(), Some("a, b"));
+
+        assert!(parse_legacy_line("2025-01-01 12:00:00 UTC,Resume").is_err());
+    }
+
+    #[test]
+    fn parse_line_rejects_newer_schema_versions() {
+        let line = r#"{"version":2,"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","kind":"Start","timestamp":"2025-01-01T12:00:00Z"}"#;
+        let result = parse_line(Uuid::nil(), line);
+        assert!(matches!(result, Err(SessionLogError::UnsupportedVersion(2))));
+    }
+
+    #[test]
+    fn migrate_converts_legacy_logs() {
+        use tempdir::TempDir;
+
+        const TEST_UUID: &str = "01890a5d-ac96-774b-bcce-b302099a8057";
+        let tmp_dir = TempDir::new("test").unwrap();
+        let sessions_dir = tmp_dir.path().join(SESSIONS_DIRECTORY);
+        fs::create_dir_all(&sessions_dir).unwrap();
+        let legacy_path = sessions_dir.join(format!("{}-review.log", TEST_UUID));
+        fs::write(
+            &legacy_path,
+            "2025-01-01 12:00:00 UTC,Start,review\n2025-01-01 12:30:00.5 UTC,Pause,review\n",
+        )
+        .unwrap();
+
+        // Legacy logs are readable before they're migrated
+        let state = current(tmp_dir.path()).unwrap().unwrap();
+        assert_eq!(state.status, SessionStatus::Paused);
+
+        let migrated = migrate(tmp_dir.path()).migrated;
+        assert_eq!(
+            migrated,
+            vec![sessions_dir.join(format!("{}-review.jsonl", TEST_UUID))]
+        );
+        assert!(!legacy_path.exists());
+
+        let entries = read_session_log(uuid!(TEST_UUID), &migrated[0]).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].kind, SessionEntryKind::Pause);
+        assert_eq!(entries[1].name.as_deref(), Some("review"));
+        assert_eq!(
+            current(tmp_dir.path()).unwrap().unwrap().started_at,
+            state.started_at
+        );
+
+        // State timestamps are formatted like the records
+        assert_eq!(state.started_at, "2025-01-01T12:00:00Z");
+        assert_eq!(state.ended_at.as_deref(), Some("2025-01-01T12:30:00.500Z"));
+
+        // Nothing is left to migrate, and new sessions keep working alongside
+        let migration = migrate(tmp_dir.path());
+        assert!(migration.migrated.is_empty() && migration.failed.is_empty());
+        start(tmp_dir.path(), None).unwrap();
+        stop(tmp_dir.path()).unwrap();
+    }
+
+    #[test]
+    fn migrate_continues_past_corrupt_logs() {
+        use tempdir::TempDir;
+
+        let tmp_dir = TempDir::new("test").unwrap();
+        let sessions_dir = tmp_dir.path().join(SESSIONS_DIRECTORY);
+        fs::create_dir_all(&sessions_dir).unwrap();
+        let corrupt_path = sessions_dir.join("01890a5d-ac96-774b-bcce-b302099a8057.log");
+        fs::write(&corrupt_path, "not a session log\n").unwrap();
+        let valid_path = sessions_dir.join("01890a5d-ac96-774b-bcce-b302099a8058.log");
+        fs::write(&valid_path, "2025-01-01 12:00:00 UTC,Start\n").unwrap();
+
+        let migration = migrate(tmp_dir.path());
+        assert_eq!(migration.migrated, vec![valid_path.with_extension("jsonl")]);
+        assert!(!valid_path.exists());
+        assert_eq!(migration.failed.len(), 1);
+        assert_eq!(migration.failed[0].path, corrupt_path);
+        assert_eq!(
+            fs::read_to_string(&corrupt_path).unwrap(),
+            "not a session log\n"
+        );
+        assert!(migration.to_string().contains("✗"));
+    }
 }
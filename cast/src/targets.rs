@@ -0,0 +1,277 @@
+use crate::cd::find_monorepo_root;
+use crate::projects::find_projects;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TargetError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("CD error: {0}")]
+    CdError(#[from] crate::cd::CdError),
+    #[error("no project matches '{0}'")]
+    NoMatch(String),
+    #[error("'{name}' matches several projects ({}), use its path instead", format_projects(.projects))]
+    Ambiguous {
+        name: String,
+        projects: Vec<PathBuf>,
+    },
+    #[error("this command runs in a single project, but {} matched", format_projects(.0))]
+    SingleProjectOnly(Vec<PathBuf>),
+}
+
+fn format_projects(projects: &[PathBuf]) -> String {
+    projects
+        .iter()
+        .map(|project| display_project(project))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Which projects a command runs in, instead of the one containing the current directory
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct TargetArgs {
+    /// Run in this project: a path, a project directory name or a glob like 'cahokia/*' relative
+    /// to the monorepo root (can be repeated)
+    #[arg(long = "project", value_name = "PROJECT")]
+    pub projects: Vec<String>,
+
+    /// Run in every project in the monorepo
+    #[arg(long, conflicts_with = "projects")]
+    pub all: bool,
+}
+
+impl TargetArgs {
+    /// Whether any projects were requested
+    pub fn is_set(&self) -> bool {
+        self.all || !self.projects.is_empty()
+    }
+}
+
+/// Projects resolved from `--project` / `--all`
+#[derive(Debug, PartialEq)]
+pub struct Targets {
+    pub monorepo_root: PathBuf,
+    /// Project paths relative to the monorepo root, sorted
+    pub projects: Vec<PathBuf>,
+}
+
+/// Resolve `--project` / `--all` against the projects in the monorepo containing
+/// `working_directory`
+///
+/// Each `--project` value is matched, in order, as:
+/// 1. A glob (`*` within a path segment, `**` across segments, `?`) against project paths
+/// 2. A path relative to `working_directory` or to the monorepo root
+/// 3. A project directory name, which must be unique
+pub fn resolve(working_directory: &Path, args: &TargetArgs) -> Result<Targets, TargetError> {
+    let monorepo_root = find_monorepo_root(working_directory)?;
+    let all_projects = find_projects(&monorepo_root)?;
+
+    let mut projects = if args.all {
+        all_projects
+    } else {
+        let mut matched = Vec::new();
+        for pattern in &args.projects {
+            matched.extend(match_projects(
+                pattern,
+                working_directory,
+                &monorepo_root,
+                &all_projects,
+            )?);
+        }
+        matched
+    };
+    projects.sort();
+    projects.dedup();
+
+    Ok(Targets {
+        monorepo_root,
+        projects,
+    })
+}
+
+/// Display a project path, using `.` for a project at the monorepo root
+pub fn display_project(project: &Path) -> String {
+    if project.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        project.display().to_string()
+    }
+}
+
+fn match_projects(
+    pattern: &str,
+    working_directory: &Path,
+    monorepo_root: &Path,
+    projects: &[PathBuf],
+) -> Result<Vec<PathBuf>, TargetError> {
+    let pattern = pattern.trim_end_matches('/');
+
+    if pattern.contains(['*', '?']) {
+        let matched: Vec<PathBuf> = projects
+            .iter()
+            .filter(|project| glob_match(pattern, &project_key(project)))
+            .cloned()
+            .collect();
+        if matched.is_empty() {
+            return Err(TargetError::NoMatch(pattern.to_string()));
+        }
+        return Ok(matched);
+    }
+
+    for base in [working_directory, monorepo_root] {
+        let candidate = normalize(&base.join(pattern));
+        if let Ok(relative) = candidate.strip_prefix(monorepo_root) {
+            if let Some(project) = projects.iter().find(|project| *project == relative) {
+                return Ok(vec![project.clone()]);
+            }
+        }
+    }
+
+    let named: Vec<PathBuf> = projects
+        .iter()
+        .filter(|project| project.file_name().is_some_and(|name| name == pattern))
+        .cloned()
+        .collect();
+    match named.len() {
+        0 => Err(TargetError::NoMatch(pattern.to_string())),
+        1 => Ok(named),
+        _ => Err(TargetError::Ambiguous {
+            name: pattern.to_string(),
+            projects: named,
+        }),
+    }
+}
+
+/// Project path with `/` separators, for glob matching
+fn project_key(project: &Path) -> String {
+    project
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Match `text` against a glob where `*` and `?` stay within a path segment and `**` spans
+/// segments
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[u8], text: &[u8]) -> bool {
+        match pattern {
+            [] => text.is_empty(),
+            [b'*', b'*', rest @ ..] => {
+                // `**/` also matches no directories at all
+                let rest_after_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+                (0..=text.len()).any(|i| {
+                    matches(rest, &text[i..])
+                        || (rest_after_slash.len() != rest.len()
+                            && matches(rest_after_slash, &text[i..]))
+                })
+            }
+            [b'*', rest @ ..] => (0..=text.len())
+                .take_while(|&i| i == 0 || text[i - 1] != b'/')
+                .any(|i| matches(rest, &text[i..])),
+            [b'?', rest @ ..] => {
+                matches!(text.first(), Some(c) if *c != b'/') && matches(rest, &text[1..])
+            }
+            [c, rest @ ..] => text.first() == Some(c) && matches(rest, &text[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempdir::TempDir;
+
+    fn create_monorepo(projects: &[&str]) -> TempDir {
+        let tmp_dir = TempDir::new("test_targets").unwrap();
+        fs::create_dir(tmp_dir.path().join(".git")).unwrap();
+        for project in projects {
+            let dir = tmp_dir.path().join(project);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("Cast.toml"), "").unwrap();
+        }
+        tmp_dir
+    }
+
+    fn targets(working_directory: &Path, projects: &[&str], all: bool) -> Vec<PathBuf> {
+        let args = TargetArgs {
+            projects: projects.iter().map(|project| project.to_string()).collect(),
+            all,
+        };
+        resolve(working_directory, &args).unwrap().projects
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("cahokia/*", "cahokia/web"));
+        assert!(!glob_match("cahokia/*", "cahokia"));
+        assert!(!glob_match("cahokia/*", "cahokia/web/api"));
+        assert!(glob_match("cahokia/**", "cahokia/web/api"));
+        assert!(glob_match("**/api", "api"));
+        assert!(glob_match("**/api", "cahokia/web/api"));
+        assert!(glob_match("web-?", "web-1"));
+        assert!(!glob_match("*-cloudflare", "pane/web-cloudflare"));
+    }
+
+    #[test]
+    fn test_resolve_by_path_name_glob_and_all() {
+        let tmp_dir = create_monorepo(&["cahokia/web", "cahokia/api", "pane", "tools/api"]);
+        let root = tmp_dir.path();
+
+        assert_eq!(targets(root, &["pane"], false), vec![PathBuf::from("pane")]);
+        assert_eq!(
+            targets(root, &["cahokia/web/"], false),
+            vec![PathBuf::from("cahokia/web")]
+        );
+        assert_eq!(
+            targets(&root.join("cahokia"), &["../pane", "web"], false),
+            vec![PathBuf::from("cahokia/web"), PathBuf::from("pane")]
+        );
+        assert_eq!(
+            targets(root, &["cahokia/*"], false),
+            vec![PathBuf::from("cahokia/api"), PathBuf::from("cahokia/web")]
+        );
+        assert_eq!(targets(root, &[], true).len(), 4);
+    }
+
+    #[test]
+    fn test_resolve_rejects_unknown_and_ambiguous_names() {
+        let tmp_dir = create_monorepo(&["cahokia/api", "tools/api"]);
+        let args = |project: &str| TargetArgs {
+            projects: vec![project.to_string()],
+            all: false,
+        };
+
+        assert!(matches!(
+            resolve(tmp_dir.path(), &args("api")),
+            Err(TargetError::Ambiguous { projects, .. }) if projects.len() == 2
+        ));
+        assert!(matches!(
+            resolve(tmp_dir.path(), &args("missing")),
+            Err(TargetError::NoMatch(_))
+        ));
+        assert!(matches!(
+            resolve(tmp_dir.path(), &args("web/*")),
+            Err(TargetError::NoMatch(_))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use std::io;
 use std::path::Path;
 use std::process::Command;
 use thiserror::Error;
@@ -17,6 +18,7 @@ pub fn run(working_directory: impl AsRef<Path>) -> Result<(), TestError> {
     let status = Command::new("cargo")
         .arg("test")
         .current_dir(working_directory)
+        .stdout(io::stderr())
         .status()?;
 
     if !status.success() {
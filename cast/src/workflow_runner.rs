@@ -0,0 +1,1915 @@
+use crate::cd::find_monorepo_root;
+use crate::config::CastConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs, io};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Actions skipped unless a substitute is configured, because the local working copy,
+/// toolchains and caches already stand in for them
+const DEFAULT_STUBBED_ACTIONS: &[&str] = &[
+    "actions/checkout",
+    "actions/cache",
+    "actions/setup-node",
+    "actions/upload-artifact",
+    "actions/download-artifact",
+    "actions-rust-lang/setup-rust-toolchain",
+];
+
+#[derive(Error, Debug)]
+pub enum WorkflowRunError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("CD error: {0}")]
+    CdError(#[from] crate::cd::CdError),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] crate::config::ConfigError),
+    #[error("Failed to parse {0}: {1}")]
+    ParseError(PathBuf, String),
+    #[error("Invalid expression `{0}`: {1}")]
+    ExpressionError(String, String),
+    #[error("{0} is not triggered by the `{1}` event")]
+    EventNotTriggered(PathBuf, String),
+    #[error("Job `{0}` not found")]
+    JobNotFound(String),
+    #[error("Job `{0}` needs unknown job `{1}`")]
+    UnknownNeed(String, String),
+    #[error("Jobs have a dependency cycle: {}", .0.join(", "))]
+    DependencyCycle(Vec<String>),
+    #[error("Workflow run failed:\n{0}")]
+    Failed(Box<RunReport>),
+    #[error("Workflow run skipped actions without a substitute:\n{0}")]
+    Incomplete(Box<RunReport>),
+}
+
+/// Options for a local workflow run
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Event that triggers the workflow (e.g. `pull_request`)
+    pub event: String,
+    /// JSON file with the `github.event` payload, built from the local git history when unset
+    pub event_path: Option<PathBuf>,
+    /// Only run these jobs and the jobs they need (every job when empty)
+    pub jobs: Vec<String>,
+}
+
+/// Result of a job or step, named like GitHub's `needs.<job>.result`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Success,
+    Failure,
+    Skipped,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Failure => "failure",
+            Outcome::Skipped => "skipped",
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Outcome::Success => "✓",
+            Outcome::Failure => "✗",
+            Outcome::Skipped => "-",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepReport {
+    pub name: String,
+    pub outcome: Outcome,
+    /// Why the step was stubbed, skipped or failed (e.g. `stubbed actions/checkout@v6`, `exit code 1`)
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobReport {
+    pub id: String,
+    /// Display name, including the matrix values for matrix jobs
+    pub name: String,
+    pub outcome: Outcome,
+    /// Why the job was skipped (e.g. `runs on macos-latest`)
+    pub note: Option<String>,
+    pub steps: Vec<StepReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub workflow: PathBuf,
+    pub event: String,
+    pub jobs: Vec<JobReport>,
+    /// `uses:` actions that were skipped because no substitute is configured for them
+    pub skipped_actions: Vec<String>,
+}
+
+impl RunReport {
+    /// Whether any job failed
+    pub fn has_failures(&self) -> bool {
+        self.jobs.iter().any(|job| job.outcome == Outcome::Failure)
+    }
+
+    /// Whether every step that should have run did, rather than being skipped for lack of a substitute
+    pub fn is_complete(&self) -> bool {
+        self.skipped_actions.is_empty()
+    }
+
+    /// Find the report of a job by id (the first instance for matrix jobs)
+    pub fn job(&self, id: &str) -> Option<&JobReport> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+}
+
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Ran {} for {}", self.workflow.display(), self.event)?;
+        for job in &self.jobs {
+            writeln!(
+                f,
+                "{} {}{}",
+                job.outcome.symbol(),
+                job.name,
+                format_note(job.note.as_deref())
+            )?;
+            for step in &job.steps {
+                writeln!(
+                    f,
+                    "    {} {}{}",
+                    step.outcome.symbol(),
+                    step.name,
+                    format_note(step.note.as_deref())
+                )?;
+            }
+        }
+        if !self.is_complete() {
+            writeln!(
+                f,
+                "Skipped actions without a substitute: {}",
+                self.skipped_actions.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn format_note(note: Option<&str>) -> String {
+    note.map(|note| format!(" ({})", note)).unwrap_or_default()
+}
+
+#[derive(Debug, Deserialize)]
+struct Workflow {
+    #[serde(rename = "on", default)]
+    triggers: Value,
+    #[serde(default)]
+    env: BTreeMap<String, Value>,
+    #[serde(default)]
+    defaults: Defaults,
+    /// Kept as a YAML mapping so jobs run in the order they are declared
+    #[serde(default)]
+    jobs: serde_yaml::Mapping,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Defaults {
+    #[serde(default)]
+    run: RunDefaults,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RunDefaults {
+    shell: Option<String>,
+    working_directory: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Job {
+    name: Option<String>,
+    #[serde(default)]
+    needs: Value,
+    #[serde(rename = "if")]
+    condition: Option<Value>,
+    runs_on: Option<Value>,
+    strategy: Option<Strategy>,
+    #[serde(default)]
+    env: BTreeMap<String, Value>,
+    #[serde(default)]
+    defaults: Defaults,
+    #[serde(default)]
+    outputs: BTreeMap<String, String>,
+    #[serde(default)]
+    steps: Vec<Step>,
+    /// Reusable workflow call, which the local runner does not follow
+    uses: Option<String>,
+}
+
+impl Job {
+    fn needs(&self) -> Vec<String> {
+        match &self.needs {
+            Value::String(need) => vec![need.clone()],
+            Value::Array(needs) => needs.iter().map(value_to_string).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Strategy {
+    matrix: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Step {
+    id: Option<String>,
+    name: Option<String>,
+    #[serde(rename = "if")]
+    condition: Option<Value>,
+    uses: Option<String>,
+    run: Option<String>,
+    shell: Option<String>,
+    working_directory: Option<String>,
+    #[serde(default)]
+    env: BTreeMap<String, Value>,
+    #[serde(default)]
+    with: BTreeMap<String, Value>,
+    #[serde(default)]
+    continue_on_error: bool,
+}
+
+/// Run a GitHub Actions workflow locally
+///
+/// This:
+/// 1. Parses the workflow and checks that it is triggered by `options.event`
+/// 2. Orders jobs by `needs`, evaluating `if:` conditions, matrices and `${{ }}` expressions
+/// 3. Runs each `run:` step with the configured shell, collecting `$GITHUB_OUTPUT` and `$GITHUB_ENV`
+/// 4. Replaces `uses:` steps with the `[workflows.substitutes]` scripts from the monorepo root
+///    Cast configuration, with `with:` inputs passed as `INPUT_<NAME>` environment variables.
+///    Actions without a substitute are skipped and listed in the report.
+///
+/// Secrets are read from environment variables of the same name.
+pub fn run(
+    working_directory: impl AsRef<Path>,
+    workflow_path: impl AsRef<Path>,
+    options: &RunOptions,
+) -> Result<RunReport, WorkflowRunError> {
+    let workspace = find_monorepo_root(working_directory.as_ref())?;
+    let workflow_path = workflow_path.as_ref();
+    let contents = fs::read_to_string(workflow_path)?;
+    let workflow: Workflow = serde_yaml::from_str(&contents)
+        .map_err(|e| WorkflowRunError::ParseError(workflow_path.to_path_buf(), e.to_string()))?;
+    let jobs = parse_jobs(workflow_path, &workflow.jobs)?;
+
+    let not_triggered =
+        || WorkflowRunError::EventNotTriggered(workflow_path.to_path_buf(), options.event.clone());
+    let types = event_types(&workflow.triggers, &options.event).ok_or_else(not_triggered)?;
+    let payload = match &options.event_path {
+        Some(path) => serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| WorkflowRunError::ParseError(path.clone(), e.to_string()))?,
+        None => default_event_payload(&workspace, &options.event, &types),
+    };
+    if let Some(action) = payload.get("action").and_then(Value::as_str) {
+        if !types.is_empty() && !types.iter().any(|t| t == action) {
+            return Err(not_triggered());
+        }
+    }
+
+    let order = job_order(&jobs, &options.jobs)?;
+    let substitutes = CastConfig::load_from_dir(&workspace)?
+        .workflows
+        .and_then(|workflows| workflows.substitutes)
+        .unwrap_or_default();
+
+    let temp_dir = env::temp_dir().join(format!("cast-workflow-{}", Uuid::now_v7()));
+    fs::create_dir_all(&temp_dir)?;
+    let event_path = temp_dir.join("event.json");
+    fs::write(&event_path, payload.to_string())?;
+
+    let mut runner = Runner::new(
+        workspace,
+        temp_dir.clone(),
+        &options.event,
+        payload,
+        substitutes,
+        workflow.defaults.run.clone(),
+    );
+    runner
+        .base_env
+        .insert("GITHUB_EVENT_PATH".into(), path_string(&event_path));
+
+    let result = runner.run_workflow(&workflow, &jobs, &order);
+    // The temp dir only holds step scripts and command files, so cleanup failures are harmless
+    let _ = fs::remove_dir_all(&temp_dir);
+
+    Ok(RunReport {
+        workflow: workflow_path.to_path_buf(),
+        event: options.event.clone(),
+        jobs: result?,
+        skipped_actions: runner.skipped_actions,
+    })
+}
+
+fn parse_jobs(
+    workflow_path: &Path,
+    jobs: &serde_yaml::Mapping,
+) -> Result<Vec<(String, Job)>, WorkflowRunError> {
+    let parse_error =
+        |message: String| WorkflowRunError::ParseError(workflow_path.to_path_buf(), message);
+
+    jobs.iter()
+        .map(|(id, job)| {
+            let id = id
+                .as_str()
+                .ok_or_else(|| parse_error("job ids must be strings".into()))?;
+            let job = serde_yaml::from_value(job.clone())
+                .map_err(|e| parse_error(format!("job `{}`: {}", id, e)))?;
+            Ok((id.to_string(), job))
+        })
+        .collect()
+}
+
+/// Activity types the workflow accepts for `event`, or `None` when the event doesn't trigger it
+fn event_types(triggers: &Value, event: &str) -> Option<Vec<String>> {
+    match triggers {
+        Value::String(trigger) => (trigger == event).then(Vec::new),
+        Value::Array(triggers) => triggers
+            .iter()
+            .any(|trigger| trigger.as_str() == Some(event))
+            .then(Vec::new),
+        Value::Object(triggers) => triggers.get(event).map(|config| {
+            config
+                .get("types")
+                .and_then(Value::as_array)
+                .map(|types| types.iter().map(value_to_string).collect())
+                .unwrap_or_default()
+        }),
+        _ => None,
+    }
+}
+
+/// Build a `github.event` payload from the local git history
+///
+/// Pull requests compare `HEAD~1` with `HEAD` and use the first activity type the workflow
+/// accepts, so a workflow that only runs on `closed` sees a merged pull request.
+fn default_event_payload(workspace: &Path, event: &str, types: &[String]) -> Value {
+    let head = git(workspace, &["rev-parse", "HEAD"]).unwrap_or_default();
+    let base = git(workspace, &["rev-parse", "HEAD~1"]).unwrap_or_else(|| head.clone());
+    let branch = git(workspace, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
+
+    match event {
+        "pull_request" | "pull_request_target" => {
+            let action = types.first().map(String::as_str).unwrap_or("opened");
+            json!({
+                "action": action,
+                "number": 0,
+                "pull_request": {
+                    "number": 0,
+                    "merged": action == "closed",
+                    "merge_commit_sha": head,
+                    "base": { "sha": base, "ref": "main" },
+                    "head": { "sha": head, "ref": branch },
+                },
+            })
+        }
+        "push" => json!({
+            "ref": format!("refs/heads/{}", branch),
+            "before": base,
+            "after": head,
+        }),
+        _ => json!({}),
+    }
+}
+
+fn git(workspace: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Order the selected jobs (and the jobs they need) so every job runs after its `needs`
+fn job_order(jobs: &[(String, Job)], selected: &[String]) -> Result<Vec<usize>, WorkflowRunError> {
+    let index_of = |id: &str| jobs.iter().position(|(job_id, _)| job_id == id);
+
+    for (id, job) in jobs {
+        if let Some(need) = job
+            .needs()
+            .into_iter()
+            .find(|need| index_of(need).is_none())
+        {
+            return Err(WorkflowRunError::UnknownNeed(id.clone(), need));
+        }
+    }
+
+    let mut wanted: HashSet<usize> = HashSet::new();
+    if selected.is_empty() {
+        wanted.extend(0..jobs.len());
+    } else {
+        let mut pending = Vec::new();
+        for id in selected {
+            pending.push(index_of(id).ok_or_else(|| WorkflowRunError::JobNotFound(id.clone()))?);
+        }
+        while let Some(index) = pending.pop() {
+            if wanted.insert(index) {
+                pending.extend(jobs[index].1.needs().iter().filter_map(|n| index_of(n)));
+            }
+        }
+    }
+
+    let mut order = Vec::new();
+    while order.len() < wanted.len() {
+        let next = (0..jobs.len()).find(|index| {
+            wanted.contains(index)
+                && !order.contains(index)
+                && jobs[*index]
+                    .1
+                    .needs()
+                    .iter()
+                    .all(|need| index_of(need).is_some_and(|n| order.contains(&n)))
+        });
+        match next {
+            Some(index) => order.push(index),
+            None => {
+                return Err(WorkflowRunError::DependencyCycle(
+                    (0..jobs.len())
+                        .filter(|index| wanted.contains(index) && !order.contains(index))
+                        .map(|index| jobs[index].0.clone())
+                        .collect(),
+                ))
+            }
+        }
+    }
+    Ok(order)
+}
+
+struct Runner {
+    workspace: PathBuf,
+    temp_dir: PathBuf,
+    substitutes: BTreeMap<String, String>,
+    defaults: RunDefaults,
+    /// `github`, `runner` and `secrets` contexts shared by every job
+    github: Value,
+    runner: Value,
+    secrets: Value,
+    /// Environment variables GitHub sets for every step
+    base_env: BTreeMap<String, String>,
+    workflow_env: Map<String, Value>,
+    /// `needs` context entries of the jobs that already ran
+    results: Map<String, Value>,
+    step_count: usize,
+    /// Actions without a substitute, in the order they were first skipped
+    skipped_actions: Vec<String>,
+}
+
+impl Runner {
+    fn new(
+        workspace: PathBuf,
+        temp_dir: PathBuf,
+        event: &str,
+        payload: Value,
+        substitutes: BTreeMap<String, String>,
+        defaults: RunDefaults,
+    ) -> Self {
+        let sha = git(&workspace, &["rev-parse", "HEAD"]).unwrap_or_default();
+        let branch = git(&workspace, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
+        let runner_os = match env::consts::OS {
+            "macos" => "macOS",
+            "windows" => "Windows",
+            _ => "Linux",
+        };
+
+        let base_env = BTreeMap::from([
+            ("CI".to_string(), "true".to_string()),
+            ("GITHUB_EVENT_NAME".to_string(), event.to_string()),
+            ("GITHUB_WORKSPACE".to_string(), path_string(&workspace)),
+            ("GITHUB_SHA".to_string(), sha.clone()),
+            ("GITHUB_REF".to_string(), format!("refs/heads/{}", branch)),
+            ("RUNNER_OS".to_string(), runner_os.to_string()),
+            ("RUNNER_TEMP".to_string(), path_string(&temp_dir)),
+        ]);
+
+        Self {
+            github: json!({
+                "event_name": event,
+                "event": payload,
+                "workspace": path_string(&workspace),
+                "sha": sha,
+                "ref": format!("refs/heads/{}", branch),
+                "ref_name": branch,
+                "run_id": "0",
+                "run_number": "1",
+            }),
+            runner: json!({ "os": runner_os, "temp": path_string(&temp_dir) }),
+            secrets: Value::Object(env::vars().map(|(k, v)| (k, Value::String(v))).collect()),
+            workspace,
+            temp_dir,
+            substitutes,
+            defaults,
+            base_env,
+            workflow_env: Map::new(),
+            results: Map::new(),
+            step_count: 0,
+            skipped_actions: Vec::new(),
+        }
+    }
+
+    fn context(
+        &self,
+        job_id: &str,
+        env: &Map<String, Value>,
+        needs: &Value,
+        matrix: &Value,
+        steps: &Map<String, Value>,
+    ) -> Value {
+        let mut github = self.github.clone();
+        github["job"] = Value::String(job_id.to_string());
+        json!({
+            "github": github,
+            "env": env,
+            "needs": needs,
+            "matrix": matrix,
+            "steps": steps,
+            "runner": self.runner,
+            "secrets": self.secrets,
+            "vars": {},
+            "inputs": {},
+        })
+    }
+
+    fn run_workflow(
+        &mut self,
+        workflow: &Workflow,
+        jobs: &[(String, Job)],
+        order: &[usize],
+    ) -> Result<Vec<JobReport>, WorkflowRunError> {
+        let context = self.context("", &Map::new(), &json!({}), &json!({}), &Map::new());
+        self.workflow_env = evaluate_env(&workflow.env, &context, Status::SUCCESS)?;
+
+        let mut reports = Vec::new();
+        for index in order {
+            let (id, job) = &jobs[*index];
+            reports.extend(self.run_job(id, job)?);
+        }
+        Ok(reports)
+    }
+
+    fn run_job(&mut self, id: &str, job: &Job) -> Result<Vec<JobReport>, WorkflowRunError> {
+        let needs: Map<String, Value> = job
+            .needs()
+            .into_iter()
+            .map(|need| {
+                let result = self.results.get(&need).cloned().unwrap_or(Value::Null);
+                (need, result)
+            })
+            .collect();
+        let results: Vec<&str> = needs
+            .values()
+            .map(|need| need["result"].as_str().unwrap_or("skipped"))
+            .collect();
+        let status = Status {
+            success: results.iter().all(|result| *result == "success"),
+            failure: results.contains(&"failure"),
+        };
+        let needs = Value::Object(needs);
+
+        let context = self.context(id, &self.workflow_env, &needs, &json!({}), &Map::new());
+        let should_run = match &job.condition {
+            Some(condition) => evaluate_condition(condition, &context, status)?,
+            None => status.success,
+        };
+
+        let skipped = |note: Option<&str>| JobReport {
+            id: id.to_string(),
+            name: job.name.clone().unwrap_or_else(|| id.to_string()),
+            outcome: Outcome::Skipped,
+            note: note.map(str::to_string),
+            steps: Vec::new(),
+        };
+
+        let matrix = match job.strategy.as_ref().and_then(|s| s.matrix.as_ref()) {
+            Some(matrix) => Some(evaluate_value(matrix, &context, Status::SUCCESS)?),
+            None => None,
+        };
+
+        let reports = if job.uses.is_some() {
+            vec![skipped(Some("reusable workflows are not run locally"))]
+        } else if !should_run {
+            vec![skipped(None)]
+        } else {
+            let mut reports = Vec::new();
+            let mut outputs = Map::new();
+            for combination in expand_matrix(matrix.as_ref()) {
+                let (report, job_outputs) = self.run_job_instance(id, job, combination, &needs)?;
+                reports.push(report);
+                outputs.extend(job_outputs);
+            }
+            if reports.is_empty() {
+                reports.push(skipped(Some("empty matrix")));
+            }
+            self.record_result(id, &reports, outputs);
+            return Ok(reports);
+        };
+
+        self.record_result(id, &reports, Map::new());
+        Ok(reports)
+    }
+
+    fn record_result(&mut self, id: &str, reports: &[JobReport], outputs: Map<String, Value>) {
+        let outcome = if reports.iter().any(|r| r.outcome == Outcome::Failure) {
+            Outcome::Failure
+        } else if reports.iter().any(|r| r.outcome == Outcome::Success) {
+            Outcome::Success
+        } else {
+            Outcome::Skipped
+        };
+        self.results.insert(
+            id.to_string(),
+            json!({ "result": outcome.as_str(), "outputs": outputs }),
+        );
+    }
+
+    fn run_job_instance(
+        &mut self,
+        id: &str,
+        job: &Job,
+        combination: Map<String, Value>,
+        needs: &Value,
+    ) -> Result<(JobReport, Map<String, Value>), WorkflowRunError> {
+        let matrix = Value::Object(combination);
+        let mut env = self.workflow_env.clone();
+        let context = self.context(id, &env, needs, &matrix, &Map::new());
+
+        let name = match &job.name {
+            Some(name) => interpolate(name, &context, Status::SUCCESS)?,
+            None => job_display_name(id, &matrix),
+        };
+
+        if let Some(runs_on) = &job.runs_on {
+            let labels = evaluate_value(runs_on, &context, Status::SUCCESS)?;
+            if let Some(label) = foreign_runner_label(&labels) {
+                let report = JobReport {
+                    id: id.to_string(),
+                    name,
+                    outcome: Outcome::Skipped,
+                    note: Some(format!("runs on {}", label)),
+                    steps: Vec::new(),
+                };
+                return Ok((report, Map::new()));
+            }
+        }
+
+        env.extend(evaluate_env(&job.env, &context, Status::SUCCESS)?);
+        let defaults = RunDefaults {
+            shell: job
+                .defaults
+                .run
+                .shell
+                .clone()
+                .or_else(|| self.defaults.shell.clone()),
+            working_directory: job
+                .defaults
+                .run
+                .working_directory
+                .clone()
+                .or_else(|| self.defaults.working_directory.clone()),
+        };
+
+        let mut steps = Map::new();
+        let mut reports = Vec::new();
+        let mut failed = false;
+
+        for step in &job.steps {
+            let status = Status {
+                success: !failed,
+                failure: failed,
+            };
+            let context = self.context(id, &env, needs, &matrix, &steps);
+            let step_name = match (&step.name, &step.run, &step.uses) {
+                (Some(name), _, _) => interpolate(name, &context, status)?,
+                (None, Some(run), _) => {
+                    format!("Run {}", run.lines().next().unwrap_or_default().trim())
+                }
+                (None, None, Some(uses)) => format!("Run {}", uses),
+                (None, None, None) => "Run".to_string(),
+            };
+
+            let should_run = match &step.condition {
+                Some(condition) => evaluate_condition(condition, &context, status)?,
+                None => !failed,
+            };
+            if !should_run {
+                if let Some(step_id) = &step.id {
+                    steps.insert(
+                        step_id.clone(),
+                        json!({ "outputs": {}, "outcome": "skipped", "conclusion": "skipped" }),
+                    );
+                }
+                reports.push(StepReport {
+                    name: step_name,
+                    outcome: Outcome::Skipped,
+                    note: None,
+                });
+                continue;
+            }
+
+            let mut step_env = env.clone();
+            step_env.extend(evaluate_env(&step.env, &context, status)?);
+            let context = self.context(id, &step_env, needs, &matrix, &steps);
+
+            self.step_count += 1;
+            let output_path = self
+                .temp_dir
+                .join(format!("step-{}.output", self.step_count));
+            let env_path = self.temp_dir.join(format!("step-{}.env", self.step_count));
+            fs::write(&output_path, "")?;
+            fs::write(&env_path, "")?;
+
+            let mut process_env = self.base_env.clone();
+            process_env.extend(
+                step_env
+                    .iter()
+                    .map(|(k, v)| (k.clone(), value_to_string(v))),
+            );
+            process_env.insert("GITHUB_OUTPUT".into(), path_string(&output_path));
+            process_env.insert("GITHUB_ENV".into(), path_string(&env_path));
+
+            eprintln!("==> {} / {}", name, step_name);
+            let (outcome, note) = if let Some(run) = &step.run {
+                let script = interpolate(run, &context, status)?;
+                let shell = step.shell.as_ref().or(defaults.shell.as_ref());
+                let directory = match step
+                    .working_directory
+                    .as_ref()
+                    .or(defaults.working_directory.as_ref())
+                {
+                    Some(directory) => self
+                        .workspace
+                        .join(interpolate(directory, &context, status)?),
+                    None => self.workspace.clone(),
+                };
+                self.execute(&script, shell.map(String::as_str), &directory, &process_env)?
+            } else if let Some(uses) = &step.uses {
+                for (input, value) in &step.with {
+                    let value = evaluate_value(value, &context, status)?;
+                    process_env.insert(
+                        format!("INPUT_{}", input.replace(' ', "_").to_uppercase()),
+                        value_to_string(&value),
+                    );
+                }
+                self.run_action(uses, &process_env)?
+            } else {
+                (Outcome::Success, None)
+            };
+
+            let conclusion = if outcome == Outcome::Failure && step.continue_on_error {
+                Outcome::Success
+            } else {
+                outcome
+            };
+            env.extend(parse_command_file(&env_path));
+            if let Some(step_id) = &step.id {
+                steps.insert(
+                    step_id.clone(),
+                    json!({
+                        "outputs": parse_command_file(&output_path),
+                        "outcome": outcome.as_str(),
+                        "conclusion": conclusion.as_str(),
+                    }),
+                );
+            }
+            failed |= conclusion == Outcome::Failure;
+            reports.push(StepReport {
+                name: step_name,
+                outcome,
+                note,
+            });
+        }
+
+        let context = self.context(id, &env, needs, &matrix, &steps);
+        let mut outputs = Map::new();
+        for (output, expression) in &job.outputs {
+            let value = interpolate(expression, &context, Status::SUCCESS)?;
+            outputs.insert(output.clone(), Value::String(value));
+        }
+
+        let report = JobReport {
+            id: id.to_string(),
+            name,
+            outcome: if failed {
+                Outcome::Failure
+            } else {
+                Outcome::Success
+            },
+            note: None,
+            steps: reports,
+        };
+        Ok((report, outputs))
+    }
+
+    /// Run the configured substitute for an action, stub out the actions the local working copy
+    /// stands in for, and skip the rest
+    fn run_action(
+        &mut self,
+        uses: &str,
+        process_env: &BTreeMap<String, String>,
+    ) -> Result<(Outcome, Option<String>), WorkflowRunError> {
+        let action = uses.split('@').next().unwrap_or(uses);
+        match self.substitutes.get(action) {
+            Some(script) if !script.trim().is_empty() => {
+                let script = script.clone();
+                let workspace = self.workspace.clone();
+                let (outcome, note) = self.execute(&script, None, &workspace, process_env)?;
+                let note = note.map_or_else(
+                    || format!("substituted {}", uses),
+                    |note| format!("substituted {}, {}", uses, note),
+                );
+                Ok((outcome, Some(note)))
+            }
+            Some(_) => Ok((Outcome::Success, Some(format!("stubbed {}", uses)))),
+            None if DEFAULT_STUBBED_ACTIONS.contains(&action) => {
+                Ok((Outcome::Success, Some(format!("stubbed {}", uses))))
+            }
+            None => {
+                if !self.skipped_actions.iter().any(|skipped| skipped == uses) {
+                    self.skipped_actions.push(uses.to_string());
+                }
+                Ok((
+                    Outcome::Skipped,
+                    Some(format!("skipped {}, no substitute configured", uses)),
+                ))
+            }
+        }
+    }
+
+    fn execute(
+        &mut self,
+        script: &str,
+        shell: Option<&str>,
+        directory: &Path,
+        process_env: &BTreeMap<String, String>,
+    ) -> Result<(Outcome, Option<String>), WorkflowRunError> {
+        let script_path = self.temp_dir.join(format!("step-{}.sh", self.step_count));
+        fs::write(&script_path, script)?;
+
+        let mut command = shell_command(shell.unwrap_or("bash"), &script_path);
+        let status = command
+            .current_dir(directory)
+            .envs(process_env)
+            .stdout(io::stderr())
+            .status();
+
+        Ok(match status {
+            Ok(status) if status.success() => (Outcome::Success, None),
+            Ok(status) => (
+                Outcome::Failure,
+                Some(match status.code() {
+                    Some(code) => format!("exit code {}", code),
+                    None => "terminated by signal".to_string(),
+                }),
+            ),
+            Err(e) => (Outcome::Failure, Some(e.to_string())),
+        })
+    }
+}
+
+/// Build the command GitHub uses for a `shell:` value
+fn shell_command(shell: &str, script_path: &Path) -> Command {
+    let script = path_string(script_path);
+    let template = match shell {
+        "bash" => "bash --noprofile --norc -eo pipefail {0}".to_string(),
+        "sh" => "sh -e {0}".to_string(),
+        "python" => "python {0}".to_string(),
+        custom if custom.contains("{0}") => custom.to_string(),
+        custom => format!("{} {{0}}", custom),
+    };
+
+    let mut parts = template
+        .split_whitespace()
+        .map(|part| part.replace("{0}", &script));
+    let mut command = Command::new(parts.next().unwrap_or_else(|| "bash".to_string()));
+    command.args(parts);
+    command
+}
+
+/// Parse a `$GITHUB_OUTPUT` or `$GITHUB_ENV` file (`name=value` and `name<<DELIMITER` blocks)
+fn parse_command_file(path: &Path) -> Map<String, Value> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let mut values = Map::new();
+    let mut lines = contents.lines();
+
+    while let Some(line) = lines.next() {
+        let heredoc = line
+            .split_once("<<")
+            .filter(|(name, _)| !name.contains('='));
+        if let Some((name, delimiter)) = heredoc {
+            let value: Vec<&str> = lines.by_ref().take_while(|l| *l != delimiter).collect();
+            values.insert(name.to_string(), Value::String(value.join("\n")));
+        } else if let Some((name, value)) = line.split_once('=') {
+            values.insert(name.to_string(), Value::String(value.to_string()));
+        }
+    }
+    values
+}
+
+/// Expand a `strategy.matrix` into its combinations, applying `exclude` and `include`
+fn expand_matrix(matrix: Option<&Value>) -> Vec<Map<String, Value>> {
+    let Some(Value::Object(matrix)) = matrix else {
+        return vec![Map::new()];
+    };
+
+    let base_keys: Vec<&String> = matrix
+        .keys()
+        .filter(|key| *key != "include" && *key != "exclude")
+        .collect();
+    let mut combinations: Vec<Map<String, Value>> = if base_keys.is_empty() {
+        Vec::new()
+    } else {
+        base_keys
+            .iter()
+            .fold(vec![Map::new()], |combinations, key| {
+                let values = match &matrix[key.as_str()] {
+                    Value::Array(values) => values.clone(),
+                    value => vec![value.clone()],
+                };
+                combinations
+                    .iter()
+                    .flat_map(|combination| {
+                        values.iter().map(move |value| {
+                            let mut combination = combination.clone();
+                            combination.insert(key.to_string(), value.clone());
+                            combination
+                        })
+                    })
+                    .collect()
+            })
+    };
+
+    let entries = |key: &str| -> Vec<Map<String, Value>> {
+        matrix
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.as_object().cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let excludes = entries("exclude");
+    combinations.retain(|combination| {
+        !excludes.iter().any(|exclude| {
+            exclude
+                .iter()
+                .all(|(key, value)| combination.get(key) == Some(value))
+        })
+    });
+
+    let original = combinations.len();
+    for include in entries("include") {
+        let mut matched = false;
+        for combination in combinations.iter_mut().take(original) {
+            let compatible = include.iter().all(|(key, value)| {
+                !base_keys.contains(&key) || combination.get(key) == Some(value)
+            });
+            if compatible {
+                combination.extend(include.clone());
+                matched = true;
+            }
+        }
+        if !matched {
+            combinations.push(include);
+        }
+    }
+
+    combinations
+}
+
+fn job_display_name(id: &str, matrix: &Value) -> String {
+    match matrix.as_object() {
+        Some(combination) if !combination.is_empty() => format!(
+            "{} ({})",
+            id,
+            combination
+                .values()
+                .map(value_to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => id.to_string(),
+    }
+}
+
+/// The first `runs-on` label for another operating system, since those jobs can't run here
+fn foreign_runner_label(labels: &Value) -> Option<String> {
+    let labels = match labels {
+        Value::Array(labels) => labels.iter().map(value_to_string).collect(),
+        label => vec![value_to_string(label)],
+    };
+    labels.into_iter().find(|label| {
+        let label = label.to_ascii_lowercase();
+        let os = if label.starts_with("macos") {
+            "macos"
+        } else if label.starts_with("windows") {
+            "windows"
+        } else if label.starts_with("ubuntu") {
+            "linux"
+        } else {
+            return false;
+        };
+        os != env::consts::OS
+    })
+}
+
+fn path_string(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn evaluate_env(
+    env: &BTreeMap<String, Value>,
+    context: &Value,
+    status: Status,
+) -> Result<Map<String, Value>, WorkflowRunError> {
+    env.iter()
+        .map(|(name, value)| {
+            let value = evaluate_value(value, context, status)?;
+            Ok((name.clone(), Value::String(value_to_string(&value))))
+        })
+        .collect()
+}
+
+/// Results the status check functions (`success()`, `failure()`) see
+#[derive(Debug, Clone, Copy)]
+struct Status {
+    success: bool,
+    failure: bool,
+}
+
+impl Status {
+    const SUCCESS: Status = Status {
+        success: true,
+        failure: false,
+    };
+}
+
+/// Evaluate an `if:` condition, which implicitly requires `success()` unless it calls a
+/// status check function itself
+fn evaluate_condition(
+    condition: &Value,
+    context: &Value,
+    status: Status,
+) -> Result<bool, WorkflowRunError> {
+    let Value::String(condition) = condition else {
+        return Ok(truthy(condition));
+    };
+    let expression = strip_expression(condition).unwrap_or(condition);
+    let lowercase = expression.to_lowercase();
+    let checks_status = ["success(", "failure(", "always(", "cancelled("]
+        .iter()
+        .any(|function| lowercase.contains(function));
+
+    let result = truthy(&evaluate(expression, context, status)?);
+    Ok(if checks_status {
+        result
+    } else {
+        status.success && result
+    })
+}
+
+/// The expression inside a value that is a single `${{ }}`
+fn strip_expression(value: &str) -> Option<&str> {
+    let inner = value.trim().strip_prefix("${{")?;
+    let end = expression_end(inner)?;
+    inner[end + 2..].trim().is_empty().then(|| &inner[..end])
+}
+
+/// Evaluate `${{ }}` expressions in a YAML value, keeping the type of values that are a
+/// single expression (so `matrix: ${{ fromJSON(...) }}` yields an object)
+fn evaluate_value(
+    value: &Value,
+    context: &Value,
+    status: Status,
+) -> Result<Value, WorkflowRunError> {
+    match value {
+        Value::String(text) => match strip_expression(text) {
+            Some(expression) => evaluate(expression, context, status),
+            None => Ok(Value::String(interpolate(text, context, status)?)),
+        },
+        Value::Array(values) => values
+            .iter()
+            .map(|value| evaluate_value(value, context, status))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Value::Object(values) => values
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), evaluate_value(value, context, status)?)))
+            .collect::<Result<_, _>>()
+            .map(Value::Object),
+        value => Ok(value.clone()),
+    }
+}
+
+/// Replace every `${{ }}` expression in `text` with its value
+fn interpolate(text: &str, context: &Value, status: Status) -> Result<String, WorkflowRunError> {
+    let mut output = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 3..];
+        let end = expression_end(after).ok_or_else(|| {
+            WorkflowRunError::ExpressionError(text.trim().to_string(), "missing `}}`".into())
+        })?;
+        output.push_str(&value_to_string(&evaluate(&after[..end], context, status)?));
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Byte offset of the `}}` closing an expression, ignoring braces in string literals
+fn expression_end(text: &str) -> Option<usize> {
+    let mut in_string = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            '\'' => in_string = !in_string,
+            '}' if !in_string && text[index..].starts_with("}}") => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn evaluate(expression: &str, context: &Value, status: Status) -> Result<Value, WorkflowRunError> {
+    let error =
+        |message: String| WorkflowRunError::ExpressionError(expression.trim().to_string(), message);
+    let tokens = tokenize(expression).map_err(error)?;
+    let mut parser = ExpressionParser {
+        tokens: &tokens,
+        position: 0,
+        context,
+        status,
+    };
+    let value = parser.or().map_err(error)?;
+    match tokens.get(parser.position) {
+        None => Ok(value),
+        Some(token) => Err(error(format!("unexpected {:?}", token))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    String(String),
+    Number(f64),
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Dot,
+    Comma,
+    Not,
+    And,
+    Or,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, length) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('(', _) => (Token::LeftParen, 1),
+            (')', _) => (Token::RightParen, 1),
+            ('[', _) => (Token::LeftBracket, 1),
+            (']', _) => (Token::RightBracket, 1),
+            ('.', _) => (Token::Dot, 1),
+            (',', _) => (Token::Comma, 1),
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Equal, 2),
+            ('!', Some('=')) => (Token::NotEqual, 2),
+            ('!', _) => (Token::Not, 1),
+            ('<', Some('=')) => (Token::LessEqual, 2),
+            ('<', _) => (Token::Less, 1),
+            ('>', Some('=')) => (Token::GreaterEqual, 2),
+            ('>', _) => (Token::Greater, 1),
+            ('\'', _) => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match (chars.get(j), chars.get(j + 1)) {
+                        (Some('\''), Some('\'')) => {
+                            value.push('\'');
+                            j += 2;
+                        }
+                        (Some('\''), _) => break,
+                        (Some(c), _) => {
+                            value.push(*c);
+                            j += 1;
+                        }
+                        (None, _) => return Err("unterminated string".into()),
+                    }
+                }
+                (Token::String(value), j + 1 - i)
+            }
+            (c, next)
+                if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let mut j = i + 1;
+                while j < chars.len() && (chars[j].is_ascii_digit() || chars[j] == '.') {
+                    j += 1;
+                }
+                let text: String = chars[i..j].iter().collect();
+                let number = text
+                    .parse()
+                    .map_err(|_| format!("invalid number `{}`", text))?;
+                (Token::Number(number), j - i)
+            }
+            (c, _) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut j = i + 1;
+                while j < chars.len()
+                    && (chars[j].is_ascii_alphanumeric() || chars[j] == '_' || chars[j] == '-')
+                {
+                    j += 1;
+                }
+                (Token::Identifier(chars[i..j].iter().collect()), j - i)
+            }
+            (c, _) => return Err(format!("unexpected character `{}`", c)),
+        };
+        tokens.push(token);
+        i += length;
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent evaluator for GitHub Actions expressions
+///
+/// Precedence from lowest to highest: `||`, `&&`, `==`/`!=`, `<`/`<=`/`>`/`>=`, `!`, then
+/// property access and function calls.
+struct ExpressionParser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    context: &'a Value,
+    status: Status,
+}
+
+impl ExpressionParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}", token))
+        }
+    }
+
+    fn or(&mut self) -> Result<Value, String> {
+        let mut left = self.and()?;
+        while self.eat(&Token::Or) {
+            let right = self.and()?;
+            if !truthy(&left) {
+                left = right;
+            }
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Value, String> {
+        let mut left = self.equality()?;
+        while self.eat(&Token::And) {
+            let right = self.equality()?;
+            if truthy(&left) {
+                left = right;
+            }
+        }
+        Ok(left)
+    }
+
+    fn equality(&mut self) -> Result<Value, String> {
+        let mut left = self.comparison()?;
+        loop {
+            let equal = if self.eat(&Token::Equal) {
+                true
+            } else if self.eat(&Token::NotEqual) {
+                false
+            } else {
+                return Ok(left);
+            };
+            let right = self.comparison()?;
+            left = Value::Bool(loose_equal(&left, &right) == equal);
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Value, String> {
+        let mut left = self.unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some(
+                    token @ (Token::Less | Token::LessEqual | Token::Greater | Token::GreaterEqual),
+                ) => token.clone(),
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            let right = self.unary()?;
+            let ordering = compare(&left, &right);
+            left = Value::Bool(ordering.is_some_and(|ordering| match operator {
+                Token::Less => ordering.is_lt(),
+                Token::LessEqual => ordering.is_le(),
+                Token::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Value, String> {
+        if self.eat(&Token::Not) {
+            return Ok(Value::Bool(!truthy(&self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Value, String> {
+        let mut value = self.primary()?;
+        loop {
+            if self.eat(&Token::Dot) {
+                match self.peek().cloned() {
+                    Some(Token::Identifier(name)) => {
+                        self.position += 1;
+                        value = property(&value, &name);
+                    }
+                    _ => return Err("expected a property name after `.`".into()),
+                }
+            } else if self.eat(&Token::LeftBracket) {
+                let index = self.or()?;
+                self.expect(&Token::RightBracket)?;
+                value = match (&value, &index) {
+                    (Value::Array(values), Value::Number(n)) => n
+                        .as_f64()
+                        .and_then(|n| values.get(n as usize).cloned())
+                        .unwrap_or(Value::Null),
+                    (_, index) => property(&value, &value_to_string(index)),
+                };
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Value, String> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| "unexpected end of expression".to_string())?;
+        self.position += 1;
+
+        match token {
+            Token::String(value) => Ok(Value::String(value)),
+            Token::Number(value) => Ok(number(value)),
+            Token::LeftParen => {
+                let value = self.or()?;
+                self.expect(&Token::RightParen)?;
+                Ok(value)
+            }
+            Token::Identifier(name) if self.eat(&Token::LeftParen) => {
+                let mut arguments = Vec::new();
+                if !self.eat(&Token::RightParen) {
+                    loop {
+                        arguments.push(self.or()?);
+                        if self.eat(&Token::RightParen) {
+                            break;
+                        }
+                        self.expect(&Token::Comma)?;
+                    }
+                }
+                self.call(&name, arguments)
+            }
+            Token::Identifier(name) => Ok(match name.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                _ => property(self.context, &name),
+            }),
+            token => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    fn call(&self, name: &str, arguments: Vec<Value>) -> Result<Value, String> {
+        let arity = |min: usize, max: usize| {
+            if arguments.len() < min || arguments.len() > max {
+                Err(format!("{}() takes {} to {} arguments", name, min, max))
+            } else {
+                Ok(())
+            }
+        };
+        let lowercase = |value: &Value| value_to_string(value).to_lowercase();
+
+        match name.to_lowercase().as_str() {
+            "success" => arity(0, 0).map(|_| Value::Bool(self.status.success)),
+            "failure" => arity(0, 0).map(|_| Value::Bool(self.status.failure)),
+            "always" => arity(0, 0).map(|_| Value::Bool(true)),
+            "cancelled" => arity(0, 0).map(|_| Value::Bool(false)),
+            "contains" => {
+                arity(2, 2)?;
+                Ok(Value::Bool(match &arguments[0] {
+                    Value::Array(values) => values.iter().any(|v| loose_equal(v, &arguments[1])),
+                    search => lowercase(search).contains(&lowercase(&arguments[1])),
+                }))
+            }
+            "startswith" => {
+                arity(2, 2)?;
+                Ok(Value::Bool(
+                    lowercase(&arguments[0]).starts_with(&lowercase(&arguments[1])),
+                ))
+            }
+            "endswith" => {
+                arity(2, 2)?;
+                Ok(Value::Bool(
+                    lowercase(&arguments[0]).ends_with(&lowercase(&arguments[1])),
+                ))
+            }
+            "format" => {
+                arity(1, usize::MAX)?;
+                let mut output = value_to_string(&arguments[0])
+                    .replace("{{", "\u{0}")
+                    .replace("}}", "\u{1}");
+                for (index, argument) in arguments.iter().enumerate().skip(1) {
+                    output =
+                        output.replace(&format!("{{{}}}", index - 1), &value_to_string(argument));
+                }
+                Ok(Value::String(
+                    output.replace('\u{0}', "{").replace('\u{1}', "}"),
+                ))
+            }
+            "join" => {
+                arity(1, 2)?;
+                let separator = arguments.get(1).map_or(",".to_string(), value_to_string);
+                Ok(Value::String(match &arguments[0] {
+                    Value::Array(values) => values
+                        .iter()
+                        .map(value_to_string)
+                        .collect::<Vec<_>>()
+                        .join(&separator),
+                    value => value_to_string(value),
+                }))
+            }
+            "tojson" => {
+                arity(1, 1)?;
+                serde_json::to_string_pretty(&arguments[0])
+                    .map(Value::String)
+                    .map_err(|e| e.to_string())
+            }
+            "fromjson" => {
+                arity(1, 1)?;
+                serde_json::from_str(&value_to_string(&arguments[0])).map_err(|e| e.to_string())
+            }
+            // Caches are stubbed locally, so hashing the patterns keeps keys stable and cheap
+            "hashfiles" => {
+                arity(1, usize::MAX)?;
+                let patterns: Vec<String> = arguments.iter().map(value_to_string).collect();
+                let digest = Sha256::digest(patterns.join("\n").as_bytes());
+                Ok(Value::String(
+                    digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                ))
+            }
+            _ => Err(format!("unknown function `{}`", name)),
+        }
+    }
+}
+
+/// Look up a property, falling back to a case-insensitive match like GitHub does
+fn property(value: &Value, name: &str) -> Value {
+    let Value::Object(object) = value else {
+        return Value::Null;
+    };
+    object
+        .get(name)
+        .or_else(|| {
+            object
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        })
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        Value::from(value as i64)
+    } else {
+        serde_json::Number::from_f64(value)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0 && !n.is_nan()),
+        Value::String(value) => !value.is_empty(),
+        Value::Array(_) | Value::Object(_) => true,
+    }
+}
+
+fn to_number(value: &Value) -> f64 {
+    match value {
+        Value::Null => 0.0,
+        Value::Bool(value) => f64::from(u8::from(*value)),
+        Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+        Value::String(value) if value.trim().is_empty() => 0.0,
+        Value::String(value) => value.trim().parse().unwrap_or(f64::NAN),
+        Value::Array(_) | Value::Object(_) => f64::NAN,
+    }
+}
+
+/// GitHub's loose equality: strings compare case-insensitively, mixed types compare as numbers
+fn loose_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::String(left), Value::String(right)) => left.eq_ignore_ascii_case(right),
+        (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => {
+            left == right
+        }
+        _ => to_number(left) == to_number(right),
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<std::cmp::Ordering> {
+    match (left, right) {
+        (Value::String(left), Value::String(right)) => {
+            Some(left.to_lowercase().cmp(&right.to_lowercase()))
+        }
+        _ => to_number(left).partial_cmp(&to_number(right)),
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn eval(expression: &str, context: &Value) -> Value {
+        evaluate(expression, context, Status::SUCCESS).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_expressions() {
+        let context = json!({
+            "github": { "event": { "action": "closed", "pull_request": { "merged": true } } },
+            "needs": { "changes": { "outputs": { "projects": "[\"cast\",\"pane\"]" } } },
+            "steps": { "changed-projects": { "outputs": { "count": "2" } } },
+        });
+
+        assert_eq!(
+            eval(
+                "github.event.pull_request.merged == true || github.event.action != 'closed'",
+                &context
+            ),
+            json!(true)
+        );
+        assert_eq!(
+            eval(
+                "contains(fromJSON(needs.changes.outputs.projects), 'pane')",
+                &context
+            ),
+            json!(true)
+        );
+        assert_eq!(
+            eval(
+                "contains(fromJSON(needs.changes.outputs.projects), 'web')",
+                &context
+            ),
+            json!(false)
+        );
+        assert_eq!(
+            eval("steps.changed-projects.outputs.count == 2", &context),
+            json!(true)
+        );
+        assert_eq!(
+            eval("github.event['action'] == 'CLOSED'", &context),
+            json!(true)
+        );
+        assert_eq!(eval("github.missing.property", &context), Value::Null);
+        assert_eq!(eval("null || 'fallback'", &context), json!("fallback"));
+        assert_eq!(
+            eval("!startsWith('refs/heads/main', 'refs/tags')", &context),
+            json!(true)
+        );
+        assert_eq!(eval("format('{0}-{1}', 'a', 1)", &context), json!("a-1"));
+        assert_eq!(
+            eval("join(fromJSON('[1, 2]'), '+')", &context),
+            json!("1+2")
+        );
+        assert_eq!(eval("1 < 2 && 'b' >= 'A'", &context), json!(true));
+        assert!(evaluate("contains('a')", &context, Status::SUCCESS).is_err());
+        assert!(evaluate("github.event ==", &context, Status::SUCCESS).is_err());
+    }
+
+    #[test]
+    fn test_interpolate_and_conditions() {
+        let context = json!({
+            "matrix": { "target": "x86_64-unknown-linux-gnu" },
+            "github": { "event": { "pull_request": { "merged": false, "head": { "sha": "abc" } } } },
+        });
+
+        assert_eq!(
+            interpolate(
+                "cargo build --target ${{ matrix.target }}",
+                &context,
+                Status::SUCCESS
+            )
+            .unwrap(),
+            "cargo build --target x86_64-unknown-linux-gnu"
+        );
+        assert_eq!(
+            interpolate(
+                "${{\n  github.event.pull_request.merged &&\n  'merge' ||\n  github.event.pull_request.head.sha\n}}",
+                &context,
+                Status::SUCCESS
+            )
+            .unwrap(),
+            "abc"
+        );
+        assert_eq!(
+            evaluate_value(
+                &json!("${{ fromJSON('{\"a\": 1}') }}"),
+                &context,
+                Status::SUCCESS
+            )
+            .unwrap(),
+            json!({ "a": 1 })
+        );
+
+        let failed = Status {
+            success: false,
+            failure: true,
+        };
+        // Conditions implicitly require success() unless they check the status themselves
+        assert!(evaluate_condition(&json!("true"), &context, Status::SUCCESS).unwrap());
+        assert!(!evaluate_condition(&json!("true"), &context, failed).unwrap());
+        assert!(evaluate_condition(&json!("${{ always() }}"), &context, failed).unwrap());
+        assert!(evaluate_condition(&json!("failure()"), &context, failed).unwrap());
+        assert!(!evaluate_condition(&json!(false), &context, Status::SUCCESS).unwrap());
+    }
+
+    #[test]
+    fn test_expand_matrix_with_include_and_exclude() {
+        let matrix = json!({
+            "os": ["linux", "macos"],
+            "channel": ["stable", "beta"],
+            "exclude": [{ "os": "macos", "channel": "beta" }],
+            "include": [
+                { "os": "linux", "runner": "ubuntu-latest" },
+                { "os": "windows", "channel": "stable" },
+            ],
+        });
+
+        let combinations = expand_matrix(Some(&matrix));
+        assert_eq!(combinations.len(), 4);
+        assert_eq!(
+            combinations
+                .iter()
+                .filter(|c| c.get("runner") == Some(&json!("ubuntu-latest")))
+                .count(),
+            2
+        );
+        assert_eq!(combinations[3]["os"], json!("windows"));
+
+        let include_only = json!({ "include": [{ "target": "a" }, { "target": "b" }] });
+        assert_eq!(expand_matrix(Some(&include_only)).len(), 2);
+        assert_eq!(expand_matrix(None), vec![Map::new()]);
+    }
+
+    #[test]
+    fn test_parse_command_file_supports_multiline_values() {
+        let tmp_dir = TempDir::new("test_parse_command_file").unwrap();
+        let path = tmp_dir.path().join("output");
+        fs::write(
+            &path,
+            "projects=[\"cast\"]\nnotes<<EOF\nfirst\nsecond\nEOF\nempty=\n",
+        )
+        .unwrap();
+
+        let values = parse_command_file(&path);
+        assert_eq!(values["projects"], json!("[\"cast\"]"));
+        assert_eq!(values["notes"], json!("first\nsecond"));
+        assert_eq!(values["empty"], json!(""));
+    }
+
+    fn write_workflow(dir: &Path, contents: &str) -> PathBuf {
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        let path = dir.join("workflow.yml");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn pull_request() -> RunOptions {
+        RunOptions {
+            event: "pull_request".into(),
+            ..RunOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_run_passes_outputs_between_jobs() {
+        let tmp_dir = TempDir::new("test_workflow_run_outputs").unwrap();
+        let workflow = write_workflow(
+            tmp_dir.path(),
+            r#"
+on:
+  pull_request:
+    types: [opened, closed]
+env:
+  GREETING: hello
+jobs:
+  changes:
+    if: github.event.action == 'opened'
+    runs-on: ubuntu-latest
+    outputs:
+      projects: ${{ steps.find.outputs.projects }}
+    steps:
+      - uses: actions/checkout@v6
+      - id: find
+        run: echo 'projects=["cast"]' >> "$GITHUB_OUTPUT"
+  build:
+    needs: changes
+    if: contains(fromJSON(needs.changes.outputs.projects), 'cast')
+    strategy:
+      matrix:
+        target: [a, b]
+    steps:
+      - name: Build ${{ matrix.target }}
+        env:
+          TARGET: ${{ matrix.target }}
+        run: echo "$GREETING $TARGET" >> built.txt
+  deploy:
+    needs: changes
+    if: contains(fromJSON(needs.changes.outputs.projects), 'pane')
+    steps:
+      - run: touch deployed.txt
+"#,
+        );
+
+        let report = run(tmp_dir.path(), &workflow, &pull_request()).unwrap();
+
+        assert!(!report.has_failures());
+        assert!(report.is_complete());
+        assert_eq!(report.job("changes").unwrap().outcome, Outcome::Success);
+        assert_eq!(
+            report.job("changes").unwrap().steps[0].note.as_deref(),
+            Some("stubbed actions/checkout@v6")
+        );
+        assert_eq!(
+            report.jobs.iter().filter(|j| j.id == "build").count(),
+            2,
+            "one job per matrix combination"
+        );
+        assert_eq!(report.job("build").unwrap().steps[0].name, "Build a");
+        assert_eq!(report.job("deploy").unwrap().outcome, Outcome::Skipped);
+        assert_eq!(
+            fs::read_to_string(tmp_dir.path().join("built.txt")).unwrap(),
+            "hello a\nhello b\n"
+        );
+        assert!(!tmp_dir.path().join("deployed.txt").exists());
+    }
+
+    #[test]
+    fn test_run_uses_substitutes_and_reports_failures() {
+        let tmp_dir = TempDir::new("test_workflow_run_substitutes").unwrap();
+        fs::write(
+            tmp_dir.path().join("Cast.toml"),
+            "[workflows.substitutes]\n\"actions/setup-node\" = \"printenv INPUT_NODE-VERSION > node.txt\"\n",
+        )
+        .unwrap();
+        let workflow = write_workflow(
+            tmp_dir.path(),
+            r#"
+on: [pull_request]
+jobs:
+  ci:
+    steps:
+      - uses: actions/setup-node@v4
+        with:
+          node-version: '20'
+      - uses: someone/unknown-action@v1
+      - run: exit 3
+      - run: touch after-failure.txt
+      - if: failure()
+        run: touch on-failure.txt
+"#,
+        );
+
+        let report = run(tmp_dir.path(), &workflow, &pull_request()).unwrap();
+
+        assert!(report.has_failures());
+        assert!(!report.is_complete());
+        assert_eq!(report.skipped_actions, vec!["someone/unknown-action@v1"]);
+        let steps = &report.job("ci").unwrap().steps;
+        assert_eq!(
+            steps[0].note.as_deref(),
+            Some("substituted actions/setup-node@v4")
+        );
+        assert_eq!(steps[1].outcome, Outcome::Skipped);
+        assert_eq!(
+            steps[1].note.as_deref(),
+            Some("skipped someone/unknown-action@v1, no substitute configured")
+        );
+        assert_eq!(steps[2].outcome, Outcome::Failure);
+        assert_eq!(steps[2].note.as_deref(), Some("exit code 3"));
+        assert_eq!(steps[3].outcome, Outcome::Skipped);
+        assert_eq!(steps[4].outcome, Outcome::Success);
+        assert_eq!(
+            fs::read_to_string(tmp_dir.path().join("node.txt")).unwrap(),
+            "20\n"
+        );
+        assert!(tmp_dir.path().join("on-failure.txt").exists());
+        assert!(!tmp_dir.path().join("after-failure.txt").exists());
+    }
+
+    #[test]
+    fn test_run_rejects_events_that_do_not_trigger_the_workflow() {
+        let tmp_dir = TempDir::new("test_workflow_run_event").unwrap();
+        let workflow = write_workflow(
+            tmp_dir.path(),
+            "on:\n  pull_request:\n    types: [closed]\njobs:\n  cd:\n    steps:\n      - run: 'true'\n",
+        );
+
+        let push = RunOptions {
+            event: "push".into(),
+            ..RunOptions::default()
+        };
+        assert!(matches!(
+            run(tmp_dir.path(), &workflow, &push),
+            Err(WorkflowRunError::EventNotTriggered(_, _))
+        ));
+
+        let event_path = tmp_dir.path().join("event.json");
+        fs::write(&event_path, r#"{"action": "opened"}"#).unwrap();
+        let opened = RunOptions {
+            event_path: Some(event_path),
+            ..pull_request()
+        };
+        assert!(matches!(
+            run(tmp_dir.path(), &workflow, &opened),
+            Err(WorkflowRunError::EventNotTriggered(_, _))
+        ));
+
+        // Without a payload, the first accepted activity type (a merged PR here) is used
+        let report = run(tmp_dir.path(), &workflow, &pull_request()).unwrap();
+        assert_eq!(report.job("cd").unwrap().outcome, Outcome::Success);
+    }
+}
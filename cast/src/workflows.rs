@@ -0,0 +1,789 @@
+use crate::cd::find_monorepo_root;
+use crate::config::CastConfig;
+use crate::projects::find_projects;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use thiserror::Error;
+
+/// Workflow generated for `cast ci`, relative to the monorepo root
+pub const CI_WORKFLOW_PATH: &str = ".github/workflows/cast-ci.yml";
+/// Workflow generated for `cast cd`, relative to the monorepo root
+pub const CD_WORKFLOW_PATH: &str = ".github/workflows/cast-cd.yml";
+
+const DIOXUS_CLI_VERSION: &str = "0.7.2";
+const NODE_VERSION: &str = "20";
+const CAST_BIN: &str = "$GITHUB_WORKSPACE/cast_cli/target/release/cast";
+
+const GENERATED_HEADER: &str = "\
+# This file is generated by `cast workflows generate` from the Cast project graph.
+# Do not edit it by hand: change the Cast configuration of a project instead and
+# regenerate. `cast workflows check` fails when this file is out of date.
+";
+
+/// Checkout ref used by CI: the merge commit for merged PRs, otherwise the PR head
+const CI_CHECKOUT_REF: &str = "\
+>-
+            ${{
+              github.event.pull_request.merged &&
+              github.event.pull_request.merge_commit_sha ||
+              github.event.pull_request.head.sha
+            }}";
+
+const CD_CHECKOUT_REF: &str = "${{ github.event.pull_request.merge_commit_sha }}";
+
+/// Script that lists changed Cast projects as the `projects` JSON output of the `changes` job
+const FIND_CHANGED_PROJECTS_SCRIPT: &str = r#"# Get the base and head commit SHAs
+BASE_SHA="${{ github.event.pull_request.base.sha }}"
+HEAD_SHA="${{ github.event.pull_request.head.sha }}"
+
+# Validate SHA format (40 hex characters)
+if ! [[ "$BASE_SHA" =~ ^[0-9a-f]{40}$ ]] || \
+   ! [[ "$HEAD_SHA" =~ ^[0-9a-f]{40}$ ]]; then
+  echo "Error: Invalid SHA format"
+  exit 1
+fi
+
+# Explicitly fetch both base and head commits to ensure
+# they're available
+echo "Fetching base commit: $BASE_SHA"
+git fetch origin "$BASE_SHA" || true
+echo "Fetching head commit: $HEAD_SHA"
+git fetch origin "$HEAD_SHA" || true
+
+# Verify commits are available
+echo "Verifying commits are available..."
+if ! git cat-file -e "$BASE_SHA" 2>/dev/null || \
+   ! git cat-file -e "$HEAD_SHA" 2>/dev/null; then
+  echo "Error: One or both commits not available after checkout"
+  git log --oneline --all -10
+  exit 1
+fi
+echo "Commits verified successfully"
+
+# Use cast CLI to find projects with changes
+CAST_BIN="$GITHUB_WORKSPACE/cast_cli/target/release/cast"
+CHANGED_PROJECTS=$("$CAST_BIN" project with-changes \
+  --base "$BASE_SHA" --head "$HEAD_SHA" 2>&1)
+
+# Check if the command failed
+if [ $? -ne 0 ]; then
+  echo "Error: cast project with-changes command failed"
+  echo "Git diff output: $CHANGED_PROJECTS"
+  exit 1
+fi
+
+if [ -z "$CHANGED_PROJECTS" ]; then
+  echo "No projects with Cast.toml found in changed files"
+  echo "projects=[]" >> $GITHUB_OUTPUT
+  echo "has_projects=false" >> $GITHUB_OUTPUT
+else
+  echo "Changed projects:"
+  echo "$CHANGED_PROJECTS"
+  # Convert to JSON array so project jobs can check membership
+  PROJECTS_JSON=$(echo "$CHANGED_PROJECTS" | \
+    jq -R -s -c 'split("\n") | map(select(length > 0))')
+  echo "projects=$PROJECTS_JSON" >> $GITHUB_OUTPUT
+  echo "has_projects=true" >> $GITHUB_OUTPUT
+fi"#;
+
+#[derive(Error, Debug)]
+pub enum WorkflowsError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("CD error: {0}")]
+    CdError(#[from] crate::cd::CdError),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] crate::config::ConfigError),
+    #[error("Generated workflows are out of date, run `cast workflows generate`: {}", join_paths(.0))]
+    OutOfDate(Vec<PathBuf>),
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A Cast project and the facts about it that shape its jobs
+#[derive(Debug)]
+struct WorkflowProject {
+    /// Path relative to the monorepo root, always `/` separated
+    path: String,
+    config: CastConfig,
+    has_cargo_toml: bool,
+    has_package_json: bool,
+    has_package_lock: bool,
+    uses_playwright: bool,
+}
+
+impl WorkflowProject {
+    fn load(repo_root: &Path, relative: &Path) -> Result<Self, WorkflowsError> {
+        let dir = repo_root.join(relative);
+        let package_json = fs::read_to_string(dir.join("package.json")).ok();
+        Ok(Self {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            config: CastConfig::load_from_dir(&dir)?,
+            has_cargo_toml: dir.join("Cargo.toml").exists(),
+            has_package_lock: dir.join("package-lock.json").exists(),
+            uses_playwright: package_json
+                .as_deref()
+                .is_some_and(|contents| contents.contains("\"@playwright/test\"")),
+            has_package_json: package_json.is_some(),
+        })
+    }
+
+    /// Job id suffix, e.g. `cahokia/web` becomes `cahokia-web`
+    fn slug(&self) -> String {
+        self.path
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+
+    fn is_dioxus(&self) -> bool {
+        self.config.framework.as_deref() == Some("dioxus")
+    }
+
+    fn is_cloudflare_pages(&self) -> bool {
+        self.config.framework.as_deref() == Some("cloudflare-pages")
+    }
+
+    /// Whether `cast cd` does anything for this project
+    fn has_cd(&self) -> bool {
+        self.config.project_type.as_deref() == Some("iac")
+            || self
+                .config
+                .deploys
+                .as_ref()
+                .is_some_and(|deploys| !deploys.is_empty())
+    }
+
+    fn targets(&self) -> &[String] {
+        self.config.targets.as_deref().unwrap_or_default()
+    }
+}
+
+/// Render the generated workflows for the monorepo, keyed by path relative to the root
+///
+/// This:
+/// 1. Finds every Cast project in the monorepo and loads its configuration
+/// 2. Renders `cast-ci.yml` with a job per Rust/TypeScript project (and a target matrix for
+///    projects with `targets`)
+/// 3. Renders `cast-cd.yml` with a job per project that deploys (IAC projects and `deploys` lists)
+pub fn render(
+    working_directory: impl AsRef<Path>,
+) -> Result<Vec<(PathBuf, String)>, WorkflowsError> {
+    let repo_root = find_monorepo_root(working_directory.as_ref())?;
+    let projects = find_projects(&repo_root)?
+        .iter()
+        .map(|relative| WorkflowProject::load(&repo_root, relative))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(vec![
+        (PathBuf::from(CI_WORKFLOW_PATH), render_ci(&projects)),
+        (PathBuf::from(CD_WORKFLOW_PATH), render_cd(&projects)),
+    ])
+}
+
+/// Write the generated workflows, returning the paths that changed
+pub fn generate(working_directory: impl AsRef<Path>) -> Result<Vec<PathBuf>, WorkflowsError> {
+    let repo_root = find_monorepo_root(working_directory.as_ref())?;
+    let mut written = Vec::new();
+
+    for (relative, contents) in render(&repo_root)? {
+        let path = repo_root.join(&relative);
+        if fs::read_to_string(&path).ok().as_deref() == Some(contents.as_str()) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        written.push(relative);
+    }
+
+    Ok(written)
+}
+
+/// Fail when the committed workflows differ from what `generate` would write
+pub fn check(working_directory: impl AsRef<Path>) -> Result<(), WorkflowsError> {
+    let repo_root = find_monorepo_root(working_directory.as_ref())?;
+    let out_of_date: Vec<PathBuf> = render(&repo_root)?
+        .into_iter()
+        .filter(|(relative, contents)| {
+            fs::read_to_string(repo_root.join(relative)).ok().as_deref() != Some(contents.as_str())
+        })
+        .map(|(relative, _)| relative)
+        .collect();
+
+    if out_of_date.is_empty() {
+        Ok(())
+    } else {
+        Err(WorkflowsError::OutOfDate(out_of_date))
+    }
+}
+
+fn render_ci(projects: &[WorkflowProject]) -> String {
+    let mut yaml = workflow_header("Cast CI", "[opened, synchronize, reopened, closed]");
+    yaml.push_str(
+        "  changes:
+    # Skip if PR was closed without merging
+    if: >-
+      github.event.pull_request.merged == true ||
+      github.event.action != 'closed'
+",
+    );
+    push_changes_job_body(&mut yaml, CI_CHECKOUT_REF, true);
+
+    for project in projects
+        .iter()
+        .filter(|project| project.has_cargo_toml || project.has_package_json)
+    {
+        push_ci_job(&mut yaml, project);
+    }
+
+    yaml
+}
+
+fn render_cd(projects: &[WorkflowProject]) -> String {
+    let mut yaml = workflow_header("Cast CD", "[closed]");
+    yaml.push_str(
+        "  changes:
+    # Only run if the PR was merged
+    if: github.event.pull_request.merged == true
+",
+    );
+    push_changes_job_body(&mut yaml, CD_CHECKOUT_REF, false);
+
+    for project in projects.iter().filter(|project| project.has_cd()) {
+        push_cd_job(&mut yaml, project, projects);
+    }
+
+    yaml
+}
+
+fn workflow_header(name: &str, pull_request_types: &str) -> String {
+    format!(
+        "{GENERATED_HEADER}---
+name: {name}
+
+'on':
+  pull_request:
+    types: {pull_request_types}
+
+permissions:
+  contents: read
+  pull-requests: read
+
+defaults:
+  run:
+    shell: bash
+
+jobs:
+"
+    )
+}
+
+fn push_changes_job_body(yaml: &mut String, checkout_ref: &str, check_workflows: bool) {
+    yaml.push_str(
+        "    runs-on: ubuntu-latest
+    outputs:
+      projects: ${{ steps.changed-projects.outputs.projects }}
+    steps:
+",
+    );
+    push_checkout_step(yaml, checkout_ref, true);
+    push_rust_toolchain_step(yaml, &["rustfmt", "clippy"], None);
+    push_cast_cli_steps(yaml);
+
+    if check_workflows {
+        let _ = writeln!(
+            yaml,
+            "
+      - name: Check generated workflows are up to date
+        run: |
+          \"{CAST_BIN}\" workflows check"
+        );
+    }
+
+    yaml.push_str(
+        "
+      - name: Find projects with changes
+        id: changed-projects
+        run: |
+",
+    );
+    push_script(yaml, FIND_CHANGED_PROJECTS_SCRIPT, 10);
+}
+
+fn push_ci_job(yaml: &mut String, project: &WorkflowProject) {
+    let targets = project.targets();
+    let slug = project.slug();
+    let _ = write!(
+        yaml,
+        "
+  ci-{slug}:
+    name: cast ci ({path})
+    needs: changes
+    if: contains(fromJSON(needs.changes.outputs.projects), '{path}')
+",
+        path = project.path
+    );
+
+    if targets.is_empty() {
+        yaml.push_str("    runs-on: ubuntu-latest\n");
+    } else {
+        yaml.push_str(
+            "    runs-on: ${{ matrix.runner }}
+    strategy:
+      fail-fast: false
+      matrix:
+        include:
+",
+        );
+        for target in targets {
+            let _ = writeln!(
+                yaml,
+                "          - target: {target}\n            runner: {}",
+                runner_for_target(target)
+            );
+        }
+    }
+    yaml.push_str("    steps:\n");
+
+    push_checkout_step(yaml, CI_CHECKOUT_REF, false);
+    let mut components = vec!["rustfmt", "clippy"];
+    if project.config.coverage.is_some() {
+        components.push("llvm-tools");
+    }
+    push_rust_toolchain_step(
+        yaml,
+        &components,
+        (!targets.is_empty()).then_some("${{ matrix.target }}"),
+    );
+    push_cast_cli_steps(yaml);
+
+    if project.has_cargo_toml {
+        let target_suffix = if targets.is_empty() {
+            ""
+        } else {
+            "-${{ matrix.target }}"
+        };
+        let _ = writeln!(
+            yaml,
+            "
+      - name: Cache cargo dependencies
+        uses: actions/cache@v4
+        with:
+          path: |
+            ~/.cargo/registry
+            ~/.cargo/git
+            {path}/target
+          key: ${{{{ runner.os }}}}-cargo-{slug}{target_suffix}-${{{{ hashFiles('{path}/**/Cargo.toml') }}}}",
+            path = project.path
+        );
+    }
+
+    if project.has_package_json {
+        push_setup_node_step(
+            yaml,
+            project
+                .has_package_lock
+                .then(|| format!("{}/package-lock.json", project.path))
+                .as_deref(),
+        );
+    }
+
+    if project.uses_playwright {
+        let _ = writeln!(
+            yaml,
+            "
+      - name: Install Playwright browsers
+        working-directory: {}
+        run: |
+          npm ci --prefer-offline --no-audit
+          npx playwright install --with-deps chromium",
+            project.path
+        );
+    }
+
+    if project.is_dioxus() {
+        let _ = writeln!(
+            yaml,
+            "
+      - name: Cache Dioxus CLI
+        id: cache-dx
+        uses: actions/cache@v4
+        with:
+          path: ~/.cargo/bin/dx
+          key: ${{{{ runner.os }}}}-dioxus-cli-{DIOXUS_CLI_VERSION}
+
+      - name: Install Dioxus CLI
+        if: steps.cache-dx.outputs.cache-hit != 'true'
+        run: |
+          cargo install dioxus-cli --version {DIOXUS_CLI_VERSION}"
+        );
+    }
+
+    let _ = writeln!(
+        yaml,
+        "
+      - name: Run cast ci
+        working-directory: {}
+        run: |
+          \"{CAST_BIN}\" ci",
+        project.path
+    );
+
+    if !targets.is_empty() {
+        let _ = writeln!(
+            yaml,
+            "
+      - name: Build release for ${{{{ matrix.target }}}}
+        working-directory: {}
+        run: |
+          cargo build --release --target ${{{{ matrix.target }}}}",
+            project.path
+        );
+    }
+}
+
+fn push_cd_job(yaml: &mut String, project: &WorkflowProject, projects: &[WorkflowProject]) {
+    let _ = write!(
+        yaml,
+        "
+  cd-{slug}:
+    name: cast cd ({path})
+    needs: changes
+    if: contains(fromJSON(needs.changes.outputs.projects), '{path}')
+    runs-on: ubuntu-latest
+    steps:
+",
+        slug = project.slug(),
+        path = project.path
+    );
+
+    push_checkout_step(yaml, CD_CHECKOUT_REF, false);
+    push_rust_toolchain_step(yaml, &["rustfmt", "clippy"], None);
+    push_cast_cli_steps(yaml);
+
+    // Cloudflare Pages deploys (this project or one it deploys) need wrangler
+    let deploys_cloudflare_pages = project.is_cloudflare_pages()
+        || project
+            .config
+            .deploys
+            .iter()
+            .flatten()
+            .filter_map(|deploy| projects.iter().find(|p| &p.path == deploy))
+            .any(WorkflowProject::is_cloudflare_pages);
+    if deploys_cloudflare_pages {
+        push_setup_node_step(yaml, None);
+        yaml.push_str(
+            "
+      - name: Install Wrangler
+        run: |
+          npm install -g wrangler
+",
+        );
+    }
+
+    let _ = writeln!(
+        yaml,
+        "
+      - name: Run cast cd
+        working-directory: {}
+        env:
+          CLOUDFLARE_API_TOKEN: ${{{{ secrets.GHA_CLOUDFLARE_PAGES_DEPLOY_TOKEN }}}}
+        run: |
+          \"{CAST_BIN}\" cd",
+        project.path
+    );
+}
+
+fn push_checkout_step(yaml: &mut String, checkout_ref: &str, full_history: bool) {
+    yaml.push_str(
+        "      - name: Checkout repository
+        uses: actions/checkout@v6
+        with:
+",
+    );
+    if full_history {
+        yaml.push_str("          fetch-depth: 0\n");
+    }
+    let _ = writeln!(yaml, "          ref: {checkout_ref}");
+}
+
+fn push_rust_toolchain_step(yaml: &mut String, components: &[&str], target: Option<&str>) {
+    let _ = writeln!(
+        yaml,
+        "
+      - name: Setup Rust toolchain
+        uses: actions-rust-lang/setup-rust-toolchain@v1
+        with:
+          toolchain: stable
+          components: {}",
+        components.join(", ")
+    );
+    if let Some(target) = target {
+        let _ = writeln!(yaml, "          target: {target}");
+    }
+}
+
+/// Build the cast CLI, caching its target directory across jobs
+fn push_cast_cli_steps(yaml: &mut String) {
+    yaml.push_str(
+        "
+      - name: Cache cast CLI build
+        uses: actions/cache@v4
+        with:
+          path: cast_cli/target
+          key: ${{ runner.os }}-cast-cli-${{ hashFiles('cast/Cargo.toml', 'cast/src/**', 'cast_cli/Cargo.toml', 'cast_cli/src/**') }}
+
+      - name: Build cast CLI
+        run: |
+          cd cast_cli
+          cargo build --release
+",
+    );
+}
+
+fn push_setup_node_step(yaml: &mut String, package_lock: Option<&str>) {
+    let _ = writeln!(
+        yaml,
+        "
+      - name: Setup Node.js
+        uses: actions/setup-node@v4
+        with:
+          node-version: '{NODE_VERSION}'"
+    );
+    if let Some(package_lock) = package_lock {
+        let _ = writeln!(
+            yaml,
+            "          cache: 'npm'\n          cache-dependency-path: '{package_lock}'"
+        );
+    }
+}
+
+/// Append a script as the body of a `run: |` block at the given indentation
+fn push_script(yaml: &mut String, script: &str, indent: usize) {
+    for line in script.lines() {
+        if line.is_empty() {
+            yaml.push('\n');
+        } else {
+            let _ = writeln!(yaml, "{:indent$}{line}", "");
+        }
+    }
+}
+
+/// GitHub-hosted runner able to build (and run tests for) a target triple
+fn runner_for_target(target: &str) -> &'static str {
+    if target.contains("apple") {
+        "macos-latest"
+    } else if target.contains("windows") {
+        "windows-latest"
+    } else {
+        "ubuntu-latest"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn setup_monorepo() -> TempDir {
+        let tmp_dir = TempDir::new("test_workflows").unwrap();
+        let root = tmp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+
+        let project = |path: &str, cast_toml: &str, files: &[(&str, &str)]| {
+            let dir = root.join(path);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("Cast.toml"), cast_toml).unwrap();
+            for (name, contents) in files {
+                fs::write(dir.join(name), contents).unwrap();
+            }
+        };
+
+        project(
+            "tool",
+            "targets = [\"x86_64-unknown-linux-gnu\", \"aarch64-apple-darwin\"]\n[coverage]\n",
+            &[("Cargo.toml", "[package]\nname = \"tool\"\n")],
+        );
+        project(
+            "site",
+            "framework = \"dioxus\"\ndeploys = [\"site-cloudflare\"]\n",
+            &[
+                ("Cargo.toml", "[package]\nname = \"site\"\n"),
+                (
+                    "package.json",
+                    "{\"devDependencies\": {\"@playwright/test\": \"1.0.0\"}}",
+                ),
+                ("package-lock.json", "{}"),
+            ],
+        );
+        project(
+            "site-cloudflare",
+            "framework = \"cloudflare-pages\"\nproject_type = \"iac\"\n",
+            &[],
+        );
+        project("docs", "", &[]);
+
+        tmp_dir
+    }
+
+    fn rendered(tmp_dir: &TempDir, path: &str) -> String {
+        render(tmp_dir.path())
+            .unwrap()
+            .into_iter()
+            .find(|(relative, _)| relative == Path::new(path))
+            .map(|(_, contents)| contents)
+            .unwrap()
+    }
+
+    fn job<'a>(workflow: &'a serde_yaml::Value, id: &str) -> &'a serde_yaml::Value {
+        &workflow["jobs"][id]
+    }
+
+    #[test]
+    fn test_render_ci_creates_a_job_per_buildable_project() {
+        let tmp_dir = setup_monorepo();
+        let ci = rendered(&tmp_dir, CI_WORKFLOW_PATH);
+        assert!(ci.starts_with(GENERATED_HEADER));
+
+        let workflow: serde_yaml::Value = serde_yaml::from_str(&ci).unwrap();
+        let jobs = workflow["jobs"].as_mapping().unwrap();
+        let ids: Vec<&str> = jobs.keys().filter_map(|key| key.as_str()).collect();
+        // docs has neither Cargo.toml nor package.json and site-cloudflare has nothing to build
+        assert_eq!(ids, vec!["changes", "ci-site", "ci-tool"]);
+
+        let site = job(&workflow, "ci-site");
+        assert_eq!(site["needs"], "changes");
+        assert_eq!(
+            site["if"],
+            "contains(fromJSON(needs.changes.outputs.projects), 'site')"
+        );
+        assert!(ci.contains("cargo install dioxus-cli --version 0.7.2"));
+        assert!(ci.contains("npx playwright install --with-deps chromium"));
+        assert!(ci.contains("cache-dependency-path: 'site/package-lock.json'"));
+        assert!(ci.contains("-cargo-site-${{ hashFiles('site/**/Cargo.toml') }}"));
+        assert!(ci.contains("release/cast\" workflows check"));
+    }
+
+    #[test]
+    fn test_render_ci_uses_a_target_matrix() {
+        let tmp_dir = setup_monorepo();
+        let workflow: serde_yaml::Value =
+            serde_yaml::from_str(&rendered(&tmp_dir, CI_WORKFLOW_PATH)).unwrap();
+        let tool = job(&workflow, "ci-tool");
+
+        assert_eq!(tool["runs-on"], "${{ matrix.runner }}");
+        let include = tool["strategy"]["matrix"]["include"].as_sequence().unwrap();
+        assert_eq!(include.len(), 2);
+        assert_eq!(include[1]["target"], "aarch64-apple-darwin");
+        assert_eq!(include[1]["runner"], "macos-latest");
+
+        let steps = tool["steps"].as_sequence().unwrap();
+        let toolchain = steps
+            .iter()
+            .find(|step| step["name"] == "Setup Rust toolchain")
+            .unwrap();
+        assert_eq!(
+            toolchain["with"]["components"],
+            "rustfmt, clippy, llvm-tools"
+        );
+        assert_eq!(toolchain["with"]["target"], "${{ matrix.target }}");
+        assert_eq!(
+            steps.last().unwrap()["run"],
+            "cargo build --release --target ${{ matrix.target }}\n"
+        );
+    }
+
+    #[test]
+    fn test_render_cd_creates_jobs_for_deploying_projects() {
+        let tmp_dir = setup_monorepo();
+        let cd = rendered(&tmp_dir, CD_WORKFLOW_PATH);
+        let workflow: serde_yaml::Value = serde_yaml::from_str(&cd).unwrap();
+        let ids: Vec<&str> = workflow["jobs"]
+            .as_mapping()
+            .unwrap()
+            .keys()
+            .filter_map(|key| key.as_str())
+            .collect();
+        assert_eq!(ids, vec!["changes", "cd-site", "cd-site-cloudflare"]);
+
+        // site deploys a Cloudflare Pages project, so it needs wrangler too
+        let site_steps = job(&workflow, "cd-site")["steps"].as_sequence().unwrap();
+        assert!(site_steps
+            .iter()
+            .any(|step| step["name"] == "Install Wrangler"));
+        let run_cd = site_steps.last().unwrap();
+        assert_eq!(run_cd["working-directory"], "site");
+        assert_eq!(
+            run_cd["env"]["CLOUDFLARE_API_TOKEN"],
+            "${{ secrets.GHA_CLOUDFLARE_PAGES_DEPLOY_TOKEN }}"
+        );
+        assert!(!cd.contains("release/cast\" workflows check"));
+    }
+
+    #[test]
+    fn test_generate_then_check() {
+        let tmp_dir = setup_monorepo();
+        assert!(matches!(
+            check(tmp_dir.path()),
+            Err(WorkflowsError::OutOfDate(paths)) if paths.len() == 2
+        ));
+
+        let written = generate(tmp_dir.path()).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                PathBuf::from(CI_WORKFLOW_PATH),
+                PathBuf::from(CD_WORKFLOW_PATH)
+            ]
+        );
+        check(tmp_dir.path()).unwrap();
+        assert!(generate(tmp_dir.path()).unwrap().is_empty());
+
+        // Changing a project's configuration makes the committed workflow stale
+        fs::write(
+            tmp_dir.path().join("docs/Cargo.toml"),
+            "[package]\nname = \"docs\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            check(tmp_dir.path()),
+            Err(WorkflowsError::OutOfDate(paths)) if paths == vec![PathBuf::from(CI_WORKFLOW_PATH)]
+        ));
+    }
+
+    #[test]
+    fn test_slug_and_runner_for_target() {
+        let project = WorkflowProject {
+            path: "cahokia/web_app".to_string(),
+            config: CastConfig::default(),
+            has_cargo_toml: true,
+            has_package_json: false,
+            has_package_lock: false,
+            uses_playwright: false,
+        };
+        assert_eq!(project.slug(), "cahokia-web-app");
+        assert_eq!(
+            runner_for_target("x86_64-pc-windows-msvc"),
+            "windows-latest"
+        );
+        assert_eq!(
+            runner_for_target("x86_64-unknown-linux-gnu"),
+            "ubuntu-latest"
+        );
+    }
+}
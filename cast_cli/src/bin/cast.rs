@@ -1,11 +1,15 @@
-use anyhow::Result;
-use cast::args::{execute, Args};
+use cast::args::{run, Args};
 use clap::Parser;
 use std::env;
+use std::process::ExitCode;
 
-fn main() -> Result<()> {
-    let path = env::current_dir()?;
-    let result_message = execute(Args::parse(), path.as_path())?;
-    println!("{}", result_message);
-    Ok(())
+fn main() -> ExitCode {
+    let path = match env::current_dir() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    run(Args::parse(), path.as_path())
 }
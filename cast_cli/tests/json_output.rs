@@ -0,0 +1,38 @@
+use std::fs;
+use std::process::Command;
+
+use serde_json::Value;
+use tempdir::TempDir;
+
+#[test]
+fn test_json_output_keeps_progress_off_stdout() {
+    let tmp_dir = TempDir::new("test_json_output_keeps_progress_off_stdout").unwrap();
+    fs::create_dir_all(tmp_dir.path().join(".git")).unwrap();
+    fs::write(
+        tmp_dir.path().join("workflow.yml"),
+        r#"
+on: pull_request
+jobs:
+  build:
+    steps:
+      - name: Greet
+        run: echo "hello from the step"
+"#,
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cast"))
+        .args(["--output", "json", "workflows", "run", "workflow.yml"])
+        .current_dir(tmp_dir.path())
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let result: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(result["ok"], Value::Bool(true), "{}", stdout);
+    assert_eq!(result["data"]["jobs"][0]["outcome"], "success");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("==> build / Greet"), "{}", stderr);
+    assert!(stderr.contains("hello from the step"), "{}", stderr);
+}
@@ -0,0 +1,191 @@
+import { ChildProcessWithoutNullStreams, spawn } from "child_process";
+
+const HEADER_SEPARATOR = "\r\n\r\n";
+
+export interface SessionState {
+  id: string;
+  name: string | null;
+  status: "active" | "paused" | "stopped";
+  started_at: string;
+  ended_at: string | null;
+}
+
+export interface CommandResult {
+  ok: boolean;
+  message?: string;
+  error?: string;
+  data: unknown;
+}
+
+export interface Issue {
+  id: string;
+  text: string;
+  project: string | null;
+  file: string;
+  line: number;
+  headings: string[];
+}
+
+interface RpcMessage {
+  id?: number;
+  method?: string;
+  params?: unknown;
+  result?: unknown;
+  error?: { code: number; message: string };
+}
+
+type Pending = {
+  resolve: (value: unknown) => void;
+  reject: (reason: Error) => void;
+};
+
+/**
+ * JSON-RPC client for `cast daemon`, which frames messages with
+ * `Content-Length` headers like the Language Server Protocol.
+ */
+export class CastDaemon {
+  private process: ChildProcessWithoutNullStreams;
+  private buffer = Buffer.alloc(0);
+  private nextId = 1;
+  private pending = new Map<number, Pending>();
+  private handlers = new Map<string, (params: unknown) => void>();
+  private exited = false;
+
+  /** Start `cast daemon` in `cwd`. */
+  static start(
+    castPath: string,
+    cwd: string,
+    onExit: (error: Error) => void
+  ): CastDaemon {
+    return new CastDaemon(spawn(castPath, ["daemon"], { cwd }), onExit);
+  }
+
+  constructor(
+    process: ChildProcessWithoutNullStreams,
+    onExit: (error: Error) => void
+  ) {
+    this.process = process;
+    this.process.stdout.on("data", (chunk: Buffer) => this.receive(chunk));
+    // Writes after the daemon exits fail with EPIPE, which `exit` already reports
+    this.process.stdin.on("error", () => {});
+    this.process.stderr.on("data", (chunk: Buffer) =>
+      console.error(`cast daemon: ${chunk.toString()}`)
+    );
+    this.process.on("error", (error) => this.exit(error, onExit));
+    this.process.on("exit", (code) =>
+      this.exit(new Error(`cast daemon exited with code ${code}`), onExit)
+    );
+  }
+
+  request<T>(method: string, params?: unknown): Promise<T> {
+    const id = this.nextId++;
+    return new Promise<T>((resolve, reject) => {
+      if (this.exited) {
+        reject(new Error("cast daemon is not running"));
+        return;
+      }
+      this.pending.set(id, {
+        resolve: (value) => resolve(value as T),
+        reject,
+      });
+      this.send({ jsonrpc: "2.0", id, method, params });
+    });
+  }
+
+  onNotification<T>(method: string, handler: (params: T) => void): void {
+    this.handlers.set(method, (params) => handler(params as T));
+  }
+
+  dispose(): void {
+    if (this.exited) {
+      return;
+    }
+    this.send({ jsonrpc: "2.0", id: this.nextId++, method: "shutdown" });
+    this.send({ jsonrpc: "2.0", method: "exit" });
+    this.process.stdin.end();
+    // An intentional shutdown isn't reported through `onExit`
+    this.exited = true;
+    this.pending.clear();
+  }
+
+  private send(message: object): void {
+    if (this.exited) {
+      return;
+    }
+    const json = JSON.stringify(message);
+    this.process.stdin.write(
+      `Content-Length: ${Buffer.byteLength(json)}${HEADER_SEPARATOR}${json}`
+    );
+  }
+
+  private receive(chunk: Buffer): void {
+    const { messages, rest } = parseMessages(
+      Buffer.concat([this.buffer, chunk])
+    );
+    this.buffer = rest;
+    for (const message of messages as RpcMessage[]) {
+      if (message.id !== undefined && this.pending.has(message.id)) {
+        const pending = this.pending.get(message.id)!;
+        this.pending.delete(message.id);
+        if (message.error) {
+          pending.reject(new Error(message.error.message));
+        } else {
+          pending.resolve(message.result);
+        }
+      } else if (message.method) {
+        this.handlers.get(message.method)?.(message.params);
+      }
+    }
+  }
+
+  private exit(error: Error, onExit: (error: Error) => void): void {
+    if (this.exited) {
+      return;
+    }
+    this.exited = true;
+    for (const pending of this.pending.values()) {
+      pending.reject(error);
+    }
+    this.pending.clear();
+    onExit(error);
+  }
+}
+
+/**
+ * Split complete `Content-Length` framed messages off the front of `buffer`,
+ * returning them with the bytes of any partial message that follows. Bodies
+ * that aren't valid JSON are logged and skipped.
+ */
+export function parseMessages(buffer: Buffer): {
+  messages: unknown[];
+  rest: Buffer;
+} {
+  const messages: unknown[] = [];
+  let rest = buffer;
+  for (;;) {
+    const headerEnd = rest.indexOf(HEADER_SEPARATOR);
+    if (headerEnd === -1) {
+      break;
+    }
+    const headers = rest.subarray(0, headerEnd).toString("ascii");
+    const match = /content-length:\s*(\d+)/i.exec(headers);
+    const bodyStart = headerEnd + HEADER_SEPARATOR.length;
+    if (!match) {
+      // Skip headers we can't use rather than stalling the stream
+      rest = rest.subarray(bodyStart);
+      continue;
+    }
+    const bodyEnd = bodyStart + Number(match[1]);
+    if (rest.length < bodyEnd) {
+      break;
+    }
+    const body = rest.subarray(bodyStart, bodyEnd).toString("utf-8");
+    rest = rest.subarray(bodyEnd);
+    try {
+      messages.push(JSON.parse(body));
+    } catch (error) {
+      console.error(`cast daemon sent a malformed message: ${error}`);
+    }
+  }
+  return { messages, rest };
+}
@@ -1,9 +1,9 @@
 import * as vscode from "vscode";
-import fs from "fs";
+import { CastDaemon, CommandResult, Issue, SessionState } from "./daemon";
 
 let myStatusBarItem: vscode.StatusBarItem;
-
-const SESSIONS_DIRECTORY = ".cast/sessions";
+let daemon: CastDaemon | undefined;
+let session: SessionState | undefined;
 
 export function activate({ subscriptions }: vscode.ExtensionContext) {
   myStatusBarItem = vscode.window.createStatusBarItem(
@@ -12,59 +12,126 @@ export function activate({ subscriptions }: vscode.ExtensionContext) {
   );
   subscriptions.push(myStatusBarItem);
 
-  updateStatusBarItem();
+  const workspaceFolder = vscode.workspace.workspaceFolders?.[0];
+  if (workspaceFolder === undefined) {
+    myStatusBarItem.text = `00:00:00`;
+    myStatusBarItem.hide();
+    return;
+  }
+
+  startDaemon(workspaceFolder.uri.fsPath);
+
+  const ticker = setInterval(() => renderStatusBarItem(), 1000);
+  subscriptions.push({ dispose: () => clearInterval(ticker) });
+  subscriptions.push(
+    vscode.commands.registerCommand("cast.runCi", runCi),
+    vscode.commands.registerCommand("cast.nextIssue", showNextIssue)
+  );
 }
 
-export function deactivate() {}
+export function deactivate() {
+  daemon?.dispose();
+}
 
-function updateStatusBarItem(): void {
-  if (
-    vscode.workspace.workspaceFolders !== undefined &&
-    vscode.workspace.workspaceFolders.length > 0
-  ) {
-    console.log(vscode.workspace.workspaceFolders);
-    const sessionDirectory = `${vscode.workspace.workspaceFolders[0].uri.path}/${SESSIONS_DIRECTORY}`;
-    fs.readdir(sessionDirectory, (err, files) => {
-      if (!err && files.length > 0) {
-        const sortedFiles = files.sort();
-        const sessionLogFilePath = `${sessionDirectory}/${sortedFiles[sortedFiles.length - 1]}`;
-        const sessionLogContent = fs.readFileSync(sessionLogFilePath, "utf-8");
-        const sessionStart = getSessionStart(sessionLogContent);
-        if (sessionStart) {
-          const elapsed = Math.floor(
-            (Date.now() - sessionStart.getTime()) / 1000
-          );
-          const hours = Math.floor(elapsed / 3600);
-          const minutes = Math.floor((elapsed % 3600) / 60);
-          const seconds = elapsed % 60;
-          myStatusBarItem.text = `${String(hours).padStart(2, "0")}:${String(
-            minutes
-          ).padStart(2, "0")}:${String(seconds).padStart(2, "0")}`;
-        }
-      }
-    });
+/**
+ * Session state comes from `cast daemon`, which pushes changes, so session
+ * logs are only ever parsed by cast itself.
+ */
+function startDaemon(workspacePath: string): void {
+  const castPath = vscode.workspace
+    .getConfiguration("cast")
+    .get<string>("path", "cast");
 
-    myStatusBarItem.show();
-  } else {
-    myStatusBarItem.text = `00:00:00`;
+  daemon = CastDaemon.start(castPath, workspacePath, (error) => {
+    daemon = undefined;
+    setSession(null);
+    vscode.window.showWarningMessage(
+      `${error.message}. The Cast extension needs a cast binary with \`cast daemon\`.`
+    );
+  });
+  daemon.onNotification<SessionState | null>("session/didChange", (state) =>
+    setSession(state)
+  );
+  daemon
+    .request("initialize")
+    .then(() => daemon?.request<SessionState | null>("session/state"))
+    .then((state) => setSession(state ?? null))
+    .catch((error: Error) => console.error(error.message));
+}
+
+function setSession(state: SessionState | null): void {
+  session = state ?? undefined;
+  renderStatusBarItem();
+}
+
+function renderStatusBarItem(): void {
+  if (session === undefined) {
     myStatusBarItem.hide();
+    return;
   }
-  setTimeout(() => updateStatusBarItem(), 1000);
+
+  const end =
+    session.status === "active" || session.ended_at === null
+      ? Date.now()
+      : Date.parse(session.ended_at);
+  const elapsed = Math.max(
+    0,
+    Math.floor((end - Date.parse(session.started_at)) / 1000)
+  );
+  const hours = Math.floor(elapsed / 3600);
+  const minutes = Math.floor((elapsed % 3600) / 60);
+  const seconds = elapsed % 60;
+  const time = `${String(hours).padStart(2, "0")}:${String(minutes).padStart(
+    2,
+    "0"
+  )}:${String(seconds).padStart(2, "0")}`;
+
+  myStatusBarItem.text =
+    session.status === "active" ? time : `${time} (${session.status})`;
+  myStatusBarItem.tooltip = session.name
+    ? `Cast session ${session.name}`
+    : "Cast session";
+  myStatusBarItem.show();
 }
 
-export function getSessionStart(sessionLog: string): Date | undefined {
-  const lines = sessionLog.split("\n");
-  for (const line of lines) {
-    if (!line.trim()) {
-      continue;
-    }
-    const [date, type] = line.split(",");
-    if (!date || !type) {
-      continue;
-    }
-    if (type === "Start") {
-      return new Date(Date.parse(date));
-    }
+async function runCi(): Promise<void> {
+  if (!daemon) {
+    vscode.window.showWarningMessage("Running CI needs `cast daemon`.");
+    return;
+  }
+  let result: CommandResult;
+  try {
+    result = await vscode.window.withProgress(
+      { location: vscode.ProgressLocation.Notification, title: "cast ci" },
+      () => daemon!.request<CommandResult>("ci/run")
+    );
+  } catch (error) {
+    vscode.window.showErrorMessage(`cast ci failed: ${(error as Error).message}`);
+    return;
   }
-  return undefined;
+  if (result.ok) {
+    vscode.window.showInformationMessage(result.message ?? "CI passed");
+  } else {
+    vscode.window.showErrorMessage(result.error ?? "CI failed");
+  }
+}
+
+async function showNextIssue(): Promise<void> {
+  if (!daemon) {
+    vscode.window.showWarningMessage("Finding issues needs `cast daemon`.");
+    return;
+  }
+  const issue = await daemon.request<Issue | null>("issues/next");
+  if (!issue) {
+    vscode.window.showInformationMessage("No open issues found");
+    return;
+  }
+  const root = vscode.workspace.workspaceFolders![0].uri;
+  const document = await vscode.workspace.openTextDocument(
+    vscode.Uri.joinPath(root, issue.file)
+  );
+  const position = new vscode.Position(issue.line - 1, 0);
+  await vscode.window.showTextDocument(document, {
+    selection: new vscode.Range(position, position),
+  });
 }
@@ -0,0 +1,131 @@
+import * as assert from 'assert';
+import { ChildProcessWithoutNullStreams } from 'child_process';
+import { EventEmitter } from 'events';
+import { PassThrough } from 'stream';
+
+import { CastDaemon, parseMessages } from '../daemon';
+
+function frame(body: string): Buffer {
+	return Buffer.from(`Content-Length: ${Buffer.byteLength(body)}\r\n\r\n${body}`);
+}
+
+function fakeDaemonProcess(): ChildProcessWithoutNullStreams {
+	return Object.assign(new EventEmitter(), {
+		stdin: new PassThrough(),
+		stdout: new PassThrough(),
+		stderr: new PassThrough(),
+	}) as unknown as ChildProcessWithoutNullStreams;
+}
+
+suite('parseMessages', () => {
+	test('should wait for the rest of a partial header', () => {
+		const partial = Buffer.from('Content-Length: 2\r\n');
+		const { messages, rest } = parseMessages(partial);
+		assert.deepStrictEqual(messages, []);
+		assert.deepStrictEqual(rest, partial);
+
+		const completed = parseMessages(Buffer.concat([rest, Buffer.from('\r\n{}')]));
+		assert.deepStrictEqual(completed.messages, [{}]);
+		assert.strictEqual(completed.rest.length, 0);
+	});
+
+	test('should wait for the rest of a partial body', () => {
+		const message = frame('{"id":1,"result":null}');
+		const partial = message.subarray(0, message.length - 3);
+		const { messages, rest } = parseMessages(partial);
+		assert.deepStrictEqual(messages, []);
+		assert.deepStrictEqual(rest, partial);
+	});
+
+	test('should split several messages in one chunk', () => {
+		const next = frame('{"id":4}');
+		const chunk = Buffer.concat([
+			frame('{"id":1}'),
+			frame('{"id":2}'),
+			frame('{"id":3}'),
+			next.subarray(0, 5),
+		]);
+		const { messages, rest } = parseMessages(chunk);
+		assert.deepStrictEqual(messages, [{ id: 1 }, { id: 2 }, { id: 3 }]);
+		assert.deepStrictEqual(rest, next.subarray(0, 5));
+	});
+
+	test('should count the body length in bytes', () => {
+		const body = '{"text":"héllo ✓ 🧳"}';
+		assert.notStrictEqual(Buffer.byteLength(body), body.length);
+		const message = frame(body);
+		// Split inside the last multi-byte character
+		const split = message.length - 4;
+
+		const first = parseMessages(message.subarray(0, split));
+		assert.deepStrictEqual(first.messages, []);
+		const second = parseMessages(Buffer.concat([first.rest, message.subarray(split)]));
+		assert.deepStrictEqual(second.messages, [{ text: 'héllo ✓ 🧳' }]);
+		assert.strictEqual(second.rest.length, 0);
+	});
+
+	test('should skip a malformed body without throwing', () => {
+		const chunk = Buffer.concat([frame('{"id":'), frame('{"id":2}')]);
+		const { messages, rest } = parseMessages(chunk);
+		assert.deepStrictEqual(messages, [{ id: 2 }]);
+		assert.strictEqual(rest.length, 0);
+	});
+});
+
+suite('CastDaemon', () => {
+	test('should match responses to requests by id', async () => {
+		const child = fakeDaemonProcess();
+		const sent: Buffer[] = [];
+		child.stdin.on('data', (chunk: Buffer) => sent.push(chunk));
+		const daemon = new CastDaemon(child, () => {});
+
+		const state = daemon.request<string>('session/state');
+		const issue = daemon.request<string>('issues/next');
+		const failed = assert.rejects(daemon.request<string>('ci/run'), /ci failed/);
+		await new Promise((resolve) => setImmediate(resolve));
+		const requests = parseMessages(Buffer.concat(sent)).messages as {
+			id: number;
+			method: string;
+		}[];
+		assert.deepStrictEqual(
+			requests.map((request) => request.method),
+			['session/state', 'issues/next', 'ci/run']
+		);
+
+		// Answer out of order, with a notification in between
+		const notified: unknown[] = [];
+		daemon.onNotification('session/didChange', (params) => notified.push(params));
+		child.stdout.write(
+			Buffer.concat([
+				frame(JSON.stringify({ jsonrpc: '2.0', id: requests[1].id, result: 'issue' })),
+				frame(JSON.stringify({ jsonrpc: '2.0', method: 'session/didChange', params: null })),
+				frame(
+					JSON.stringify({
+						jsonrpc: '2.0',
+						id: requests[2].id,
+						error: { code: -32603, message: 'ci failed' },
+					})
+				),
+				frame(JSON.stringify({ jsonrpc: '2.0', id: requests[0].id, result: 'state' })),
+			])
+		);
+
+		assert.strictEqual(await state, 'state');
+		assert.strictEqual(await issue, 'issue');
+		await failed;
+		assert.deepStrictEqual(notified, [null]);
+		daemon.dispose();
+	});
+
+	test('should reject pending requests when the daemon exits', async () => {
+		const child = fakeDaemonProcess();
+		const exits: Error[] = [];
+		const daemon = new CastDaemon(child, (error) => exits.push(error));
+
+		const state = daemon.request('session/state');
+		child.emit('exit', 1);
+		await assert.rejects(state, /exited with code 1/);
+		assert.strictEqual(exits.length, 1);
+		await assert.rejects(daemon.request('session/state'), /not running/);
+	});
+});
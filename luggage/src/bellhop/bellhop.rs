@@ -1,25 +1,43 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use axum::{
     Json, Router,
-    extract::State,
-    http::StatusCode,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_MATCH},
+    },
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use surrealdb::engine::local::Db;
+use serde_json::{Map, Value};
+use surrealdb::engine::{local::Db, remote::ws::Client};
+use tokio::sync::mpsc;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 use uuid::Uuid;
 
 use crate::{
     closet::{
-        closet::{Closet, ClosetBuiltinType, ClosetCreator},
-        providers::surrealdb::SurrealDbClosetProvider,
+        archive::{self, ImportReport},
+        closet::{Closet, ClosetBuiltinType, ClosetExecutionType, ClosetProvider},
+        history::{self, CubeRevision},
+        providers::{
+            filesystem::FileSystemClosetProvider, sqlite::SqliteClosetProvider,
+            surrealdb::SurrealDbClosetProvider,
+        },
+        query::{self, CubeFilter, CubePage, CubeQuery, CubeSort, MAX_QUERY_LIMIT},
     },
     core::core::LuggageId,
-    cube::cube::{Cube, CubeHeader},
+    cube::{
+        cube::{Cube, CubeDefinition, CubeHeader, CubeRegistration},
+        migration::{self, INITIAL_VERSION},
+        validation,
+    },
     error::LuggageError,
 };
 
@@ -31,9 +49,21 @@ use super::startup::StartupConfiguration;
     paths(
         health_check,
         create_cube,
+        list_cubes,
+        read_cube,
+        replace_cube,
+        patch_cube,
+        delete_cube,
+        list_revisions,
+        read_revision,
+        restore_revision,
+        migrate_cubes,
+        list_closets,
+        export_closet,
+        import_closet,
     ),
     components(
-        schemas(Health, CreateCube, BellhopHeader, CubeHeader)
+        schemas(Health, ClosetHealth, CreateCube, ReplaceCube, CubeContent, CubeList, ErrorResponse, BellhopHeader, CubeHeader, ClosetListing, CubeRevision, RevisionListing, MigrationReport, FailedMigration, ImportReport, ClosetExecutionType, ClosetBuiltinType)
     ),
     tags(
         (name = "bellhop", description = "Bellhop API endpoints")
@@ -50,50 +80,93 @@ struct ApiDoc;
 pub struct AppState {
     pub root_closet_id: Uuid,
     pub closet_registry: HashMap<Uuid, Closet>,
-    pub closet_providers: HashMap<Uuid, SurrealDbClosetProvider<Db>>, // TODO: Make the provider abstract
+    pub closet_providers: HashMap<Uuid, Arc<dyn ClosetProvider>>,
 }
 
 pub async fn app(config: Option<StartupConfiguration>) -> Result<Router, LuggageError> {
-    let root_closet_id = Uuid::now_v7();
+    Ok(router(state(config).await?).await)
+}
+
+/// Open the root closet and the closet of the startup configuration.
+pub async fn state(config: Option<StartupConfiguration>) -> Result<AppState, LuggageError> {
     let root_closet = Closet::default();
-    let root_closet_provider = SurrealDbClosetProvider::<Db>::new("bellhop", "bellhop").await?;
+    let root_closet_id = root_closet.closet_id();
+    let root_closet_provider: Arc<dyn ClosetProvider> =
+        Arc::new(SurrealDbClosetProvider::<Db>::new("bellhop", "bellhop").await?);
     let mut closet_registry: HashMap<Uuid, Closet> = HashMap::new();
     closet_registry.insert(root_closet_id, root_closet);
-    let mut closet_providers: HashMap<Uuid, SurrealDbClosetProvider<Db>> = HashMap::new();
+    let mut closet_providers: HashMap<Uuid, Arc<dyn ClosetProvider>> = HashMap::new();
     closet_providers.insert(root_closet_id, root_closet_provider);
 
     if let Some(c) = config
         && let Some(closet) = c.closet
     {
-        let additional_closet_id = Uuid::now_v7();
+        let additional_closet_id = closet.closet_id();
+        if additional_closet_id == root_closet_id {
+            return Err(LuggageError::ClosetConfiguration(format!(
+                "{} is the name of the root closet",
+                closet.name
+            )));
+        }
         closet_registry.insert(additional_closet_id, closet.clone());
         if let Some(t) = closet.builtin_type {
-            let closet_provider = match t {
+            let closet_provider: Arc<dyn ClosetProvider> = match t {
                 ClosetBuiltinType::LocalSurrealDb => {
-                    SurrealDbClosetProvider::<Db>::new("bellhop", "bellhop").await?
+                    Arc::new(SurrealDbClosetProvider::<Db>::new("bellhop", "bellhop").await?)
                 }
                 ClosetBuiltinType::RemoteSurrealDb => {
-                    // TODO: Actually connect to a remote db
-                    SurrealDbClosetProvider::<Db>::new("bellhop", "bellhop").await?
+                    let connection = closet.connection.unwrap_or_default();
+                    Arc::new(SurrealDbClosetProvider::<Client>::connect(&connection).await?)
+                }
+                ClosetBuiltinType::LocalFileSystem => {
+                    let connection = closet.connection.unwrap_or_default();
+                    Arc::new(FileSystemClosetProvider::connect(&connection)?)
+                }
+                ClosetBuiltinType::Sqlite => {
+                    let connection = closet.connection.unwrap_or_default();
+                    Arc::new(SqliteClosetProvider::connect(&connection)?)
                 }
             };
             closet_providers.insert(additional_closet_id, closet_provider);
         }
     }
 
-    Ok(router(AppState {
+    Ok(AppState {
         root_closet_id,
         closet_registry,
         closet_providers,
     })
-    .await)
 }
 
 async fn router(state: AppState) -> Router {
     Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route("/", get(health_check))
+        .route("/v1/closets", get(list_closets))
+        .route("/v1/closets/{id}/export", get(export_closet))
+        .route(
+            "/v1/closets/{id}/import",
+            post(import_closet).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/v1/migrate/{definition}", post(migrate_cubes))
         .route("/v1/cube", post(create_cube))
+        .route("/v1/cube/{definition}", get(list_cubes))
+        .route(
+            "/v1/cube/{definition}/{id}",
+            get(read_cube)
+                .put(replace_cube)
+                .patch(patch_cube)
+                .delete(delete_cube),
+        )
+        .route("/v1/cube/{definition}/{id}/revisions", get(list_revisions))
+        .route(
+            "/v1/cube/{definition}/{id}/revisions/{revision}",
+            get(read_revision),
+        )
+        .route(
+            "/v1/cube/{definition}/{id}/revisions/{revision}/restore",
+            post(restore_revision),
+        )
         .with_state(state)
 }
 
@@ -101,9 +174,56 @@ pub async fn listener() -> tokio::net::TcpListener {
     tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap()
 }
 
-#[derive(Serialize, utoipa::ToSchema)]
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct ErrorResponse {
+    error: String,
+    /// Validation errors of the content, one per failed JSON Schema check, or the
+    /// `<definition>/<id>` of every cube an import collided with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    details: Vec<String>,
+}
+
+impl IntoResponse for LuggageError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            LuggageError::Unknown | LuggageError::ClosetConfiguration(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            LuggageError::NotFound | LuggageError::ClosetNotFound(_) => StatusCode::NOT_FOUND,
+            LuggageError::Conflict
+            | LuggageError::RevisionMismatch { .. }
+            | LuggageError::ImportCollision(_) => StatusCode::CONFLICT,
+            LuggageError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            LuggageError::InvalidContent(_)
+            | LuggageError::UnknownDefinition(_)
+            | LuggageError::ValidationFailed(_)
+            | LuggageError::InvalidArchive(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        let error = self.to_string();
+        let details = match self {
+            LuggageError::ValidationFailed(details) | LuggageError::ImportCollision(details) => {
+                details
+            }
+            _ => Vec::new(),
+        };
+        (status, Json(ErrorResponse { error, details })).into_response()
+    }
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
 struct Health {
     description: String,
+    closets: Vec<ClosetHealth>,
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct ClosetHealth {
+    #[schema(value_type = String)]
+    id: LuggageId,
+    name: String,
+    healthy: bool,
+    /// Why the closet is unhealthy
+    error: Option<String>,
 }
 
 /// Health check endpoint
@@ -111,14 +231,41 @@ struct Health {
     get,
     path = "/",
     responses(
-        (status = 200, description = "Service is healthy", body = Health)
+        (status = 200, description = "Service and every closet are healthy", body = Health),
+        (status = 503, description = "A closet is unreachable", body = Health)
     ),
     tag = "bellhop"
 )]
-async fn health_check() -> Json<Health> {
-    Json(Health {
-        description: "belayon".into(),
-    })
+async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<Health>) {
+    let mut closets = Vec::new();
+    for (id, closet) in &state.closet_registry {
+        let result = match state.closet_providers.get(id) {
+            Some(provider) => provider.health().await,
+            None => Err(LuggageError::ClosetConfiguration(
+                "closet has no builtin type".into(),
+            )),
+        };
+        closets.push(ClosetHealth {
+            id: *id,
+            name: closet.name.clone(),
+            healthy: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+    closets.sort_by_key(|closet| closet.id);
+
+    let status = if closets.iter().all(|closet| closet.healthy) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(Health {
+            description: "belayon".into(),
+            closets,
+        }),
+    )
 }
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
@@ -134,34 +281,970 @@ struct CreateCube {
     content: String,
 }
 
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct ReplaceCube {
+    bellhop_header: BellhopHeader,
+    content: String,
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct CubeContent {
+    cube_header: CubeHeader,
+    #[schema(value_type = Object)]
+    content: Value,
+    /// Revision of the content, also sent as the `ETag` of responses about a single cube
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    revision: Option<u64>,
+}
+
+/// Query parameters selecting the closet of a cube operation without a request body
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ClosetQuery {
+    /// ID of the closet to use, defaulting to the root closet
+    #[param(value_type = Option<String>)]
+    closet_id: Option<LuggageId>,
+}
+
+/// Find the provider of the closet with `closet_id`, or of the root closet when it is `None`.
+fn closet_provider(
+    state: &AppState,
+    closet_id: Option<LuggageId>,
+) -> Result<&dyn ClosetProvider, LuggageError> {
+    let closet_id = closet_id.unwrap_or(state.root_closet_id);
+    state
+        .closet_providers
+        .get(&closet_id)
+        .map(|provider| provider.as_ref())
+        .ok_or_else(|| LuggageError::ClosetNotFound(closet_id.to_string()))
+}
+
+/// Parse the JSON content string of a request.
+fn parse_content(content: &str) -> Result<Value, LuggageError> {
+    serde_json::from_str(content).map_err(|e| LuggageError::InvalidContent(e.to_string()))
+}
+
+/// Read the content of a cube, or fail with [`LuggageError::NotFound`].
+async fn read_content(
+    provider: &dyn ClosetProvider,
+    header: CubeHeader,
+) -> Result<Value, LuggageError> {
+    let cube = provider.read_value(header).await?;
+    cube.content.ok_or(LuggageError::NotFound)
+}
+
+/// Read the revision a write expects from its `If-Match` header, where `*` matches any revision.
+fn expected_revision(headers: &HeaderMap) -> Result<Option<u64>, LuggageError> {
+    let Some(value) = headers.get(IF_MATCH) else {
+        return Ok(None);
+    };
+    let invalid = || LuggageError::InvalidQuery("If-Match must be a cube revision".into());
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    let revision = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+    revision.parse().map(Some).map_err(|_| invalid())
+}
+
+/// Headers carrying a cube's revision as its `ETag`, for use in `If-Match`.
+fn etag(revision: Option<u64>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(revision) = revision
+        && let Ok(value) = HeaderValue::from_str(&format!("\"{}\"", revision))
+    {
+        headers.insert(ETAG, value);
+    }
+    headers
+}
+
+fn revision_response(revision: CubeRevision) -> (HeaderMap, Json<CubeContent>) {
+    (
+        etag(Some(revision.revision)),
+        Json(CubeContent {
+            cube_header: revision.cube,
+            content: revision.content,
+            revision: Some(revision.revision),
+        }),
+    )
+}
+
+/// Apply an RFC 7396 JSON merge patch to `target`.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Definitions Bellhop itself relies on, so they validate before any definition is stored.
+fn builtin_definition(definition: LuggageId) -> Option<CubeDefinition> {
+    let schema = if definition == CubeDefinition::id() {
+        CubeDefinition::schema()
+    } else if definition == Closet::id() {
+        Closet::schema()
+    } else {
+        return None;
+    };
+    Some(CubeDefinition {
+        id: definition.to_string(),
+        schema,
+        version: INITIAL_VERSION.into(),
+        migrations: Vec::new(),
+    })
+}
+
+/// Find a definition, which is stored as a `CubeDefinition` cube with the definition's ID, in the
+/// cube's closet or the root closet.
+async fn find_definition(
+    state: &AppState,
+    provider: &dyn ClosetProvider,
+    definition: LuggageId,
+) -> Result<CubeDefinition, LuggageError> {
+    if let Some(builtin) = builtin_definition(definition) {
+        return Ok(builtin);
+    }
+
+    let header = CubeHeader {
+        id: definition,
+        definition: CubeDefinition::id(),
+    };
+    for provider in [provider, closet_provider(state, None)?] {
+        if let Some(content) = provider.read_value(header.clone()).await?.content {
+            return Ok(serde_json::from_value(content)?);
+        }
+    }
+    Err(LuggageError::UnknownDefinition(definition))
+}
+
+/// Read a cube's content upgraded to the current version of its definition, with its latest
+/// revision.
+async fn read_current(
+    state: &AppState,
+    provider: &dyn ClosetProvider,
+    header: &CubeHeader,
+) -> Result<(Value, Option<u64>), LuggageError> {
+    let content = read_content(provider, header.clone()).await?;
+    let (revision, written) = match history::latest(provider, header).await? {
+        Some(latest) => (Some(latest.revision), latest.definition_version),
+        None => (None, None),
+    };
+    let content = match find_definition(state, provider, header.definition).await {
+        Ok(definition) => migration::migrate(&definition, content, written.as_deref())?,
+        // Content outlives a deleted definition, as it was stored
+        Err(LuggageError::UnknownDefinition(_)) => content,
+        Err(e) => return Err(e),
+    };
+    Ok((content, revision))
+}
+
+/// Run a listing query on a definition's cubes upgraded to its current version, so filters and
+/// sorting see the content reads return, also before `/v1/migrate` rewrote them.
+async fn query_current(
+    state: &AppState,
+    provider: &dyn ClosetProvider,
+    definition: LuggageId,
+    query: CubeQuery,
+) -> Result<CubePage<Value>, LuggageError> {
+    let current = match find_definition(state, provider, definition).await {
+        Ok(current) if !current.migrations.is_empty() => current,
+        // Without migrations stored content is current, so the closet can run the query
+        Ok(_) | Err(LuggageError::UnknownDefinition(_)) => {
+            return provider.query_values(definition, query).await;
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut cubes = Vec::new();
+    let mut all = CubeQuery {
+        limit: MAX_QUERY_LIMIT,
+        ..Default::default()
+    };
+    loop {
+        let page = provider.query_values(definition, all.clone()).await?;
+        for cube in page.cubes {
+            let Some(content) = cube.content else {
+                continue;
+            };
+            let written = history::latest(provider, &cube.header)
+                .await?
+                .and_then(|latest| latest.definition_version);
+            let content = migration::migrate(&current, content, written.as_deref())?;
+            cubes.push(Cube::new(cube.header, content));
+        }
+        match page.next {
+            Some(next) => all.after = Some(next),
+            None => return query::apply(&query, cubes),
+        }
+    }
+}
+
+/// Check content against the schema of its definition, and that new definitions can be used to
+/// validate cubes in turn. Returns the definition, whose version writes record.
+async fn validate_content(
+    state: &AppState,
+    provider: &dyn ClosetProvider,
+    header: &CubeHeader,
+    content: &Value,
+) -> Result<CubeDefinition, LuggageError> {
+    if header.definition == CubeRevision::id() {
+        return Err(LuggageError::InvalidContent(
+            "cube revisions are recorded by luggage and can not be written".into(),
+        ));
+    }
+    let cube_definition = find_definition(state, provider, header.definition).await?;
+    validation::validate(&validation::compile(&cube_definition.schema)?, content)?;
+
+    if header.definition == CubeDefinition::id() {
+        let definition: CubeDefinition = serde_json::from_value(content.clone())?;
+        if LuggageId::try_parse(&definition.id).ok() != Some(header.id) {
+            return Err(LuggageError::InvalidContent(
+                "a cube definition's id must match its cube id".into(),
+            ));
+        }
+        validation::compile(&definition.schema)?;
+        migration::check(&definition)?;
+
+        if let Some(stored) = provider.read_value(header.clone()).await?.content {
+            let stored: CubeDefinition = serde_json::from_value(stored)?;
+            if migration::parse_version(&definition.version)?
+                < migration::parse_version(&stored.version)?
+            {
+                return Err(LuggageError::InvalidContent(format!(
+                    "a cube definition's version can not go back from {} to {}",
+                    stored.version, definition.version
+                )));
+            }
+        }
+    }
+    Ok(cube_definition)
+}
+
 /// Create a new cube
 #[utoipa::path(
     post,
     path = "/v1/cube",
     request_body = CreateCube,
     responses(
-        (status = 201, description = "Cube created successfully", body = CubeHeader),
-        (status = 500, description = "Internal server error", body = CubeHeader)
+        (status = 201, description = "Cube created successfully, with its first revision as the `ETag`", body = CubeHeader),
+        (status = 404, description = "Closet not found", body = ErrorResponse),
+        (status = 409, description = "Cube already exists", body = ErrorResponse),
+        (status = 422, description = "Content is not valid JSON or does not match its definition", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     tag = "bellhop"
 )]
 async fn create_cube(
     State(state): State<AppState>,
     Json(payload): Json<CreateCube>,
-) -> (StatusCode, Json<CubeHeader>) {
-    if let Some(provider) = state.closet_providers.get(&state.root_closet_id) {
-        let cube = Cube::new(payload.cube_header.clone(), payload.content);
-        let _ = provider.create(cube).await;
-        (StatusCode::CREATED, Json(payload.cube_header))
-    } else {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(payload.cube_header))
+) -> Result<(StatusCode, HeaderMap, Json<CubeHeader>), LuggageError> {
+    let provider = closet_provider(&state, payload.bellhop_header.closet_id)?;
+    let content = parse_content(&payload.content)?;
+    let definition = validate_content(&state, provider, &payload.cube_header, &content).await?;
+    match read_content(provider, payload.cube_header.clone()).await {
+        Ok(_) => return Err(LuggageError::Conflict),
+        Err(LuggageError::NotFound) => {}
+        Err(e) => return Err(e),
+    }
+    let revision = history::create(
+        provider,
+        Cube::new(payload.cube_header, content),
+        Some(definition.version),
+    )
+    .await?;
+    Ok((
+        StatusCode::CREATED,
+        etag(Some(revision.revision)),
+        Json(revision.cube),
+    ))
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct CubeList {
+    cubes: Vec<CubeContent>,
+    /// Number of cubes matching the filters, across every page
+    count: usize,
+    /// Pass as `after` to get the next page, absent on the last page
+    #[schema(value_type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next: Option<LuggageId>,
+}
+
+fn parse_id(name: &str, value: &str) -> Result<LuggageId, LuggageError> {
+    LuggageId::try_parse(value)
+        .map_err(|_| LuggageError::InvalidQuery(format!("{} is not a valid ID", name)))
+}
+
+/// Read the closet and cube query of a listing from its query parameters.
+///
+/// Filter values are read as JSON when they parse, so `filter.done=true` matches a boolean, and as
+/// strings otherwise.
+fn list_query(
+    params: HashMap<String, String>,
+) -> Result<(Option<LuggageId>, CubeQuery), LuggageError> {
+    let mut closet_id = None;
+    let mut query = CubeQuery::default();
+    for (name, value) in params {
+        match name.as_str() {
+            "closet_id" => closet_id = Some(parse_id(&name, &value)?),
+            "after" => query.after = Some(parse_id(&name, &value)?),
+            "limit" => {
+                query.limit = value.parse().map_err(|_| {
+                    LuggageError::InvalidQuery("limit must be a positive number".into())
+                })?
+            }
+            "sort" => {
+                query.sort = Some(match value.strip_prefix('-') {
+                    Some(field) => CubeSort {
+                        field: field.into(),
+                        descending: true,
+                    },
+                    None => CubeSort {
+                        field: value,
+                        descending: false,
+                    },
+                })
+            }
+            _ => match name.strip_prefix("filter.") {
+                Some(field) => query.filters.push(CubeFilter {
+                    field: field.into(),
+                    value: serde_json::from_str(&value).unwrap_or(Value::String(value)),
+                }),
+                None => {
+                    return Err(LuggageError::InvalidQuery(format!(
+                        "unknown parameter {}",
+                        name
+                    )));
+                }
+            },
+        }
     }
+    // Keep filters in a stable order, parameters arrive in hash map order
+    query.filters.sort_by(|a, b| a.field.cmp(&b.field));
+    query.validate()?;
+    Ok((closet_id, query))
+}
+
+/// List the cubes of a definition, a page at a time
+#[utoipa::path(
+    get,
+    path = "/v1/cube/{definition}",
+    params(
+        ("definition" = String, Path, description = "ID of the cubes' definition"),
+        ("closet_id" = Option<String>, Query, description = "ID of the closet to use, defaulting to the root closet"),
+        ("filter.{field}" = Option<String>, Query, description = "Only list cubes whose content has this value at the dot separated field, e.g. `filter.address.city=Paris`"),
+        ("sort" = Option<String>, Query, description = "Content field to sort by, prefixed with `-` for descending order. Cubes are sorted by ID, which is creation order, by default"),
+        ("after" = Option<String>, Query, description = "`next` of the previous page"),
+        ("limit" = Option<usize>, Query, description = "Number of cubes per page, at most 1000"),
+    ),
+    responses(
+        (status = 200, description = "A page of cubes", body = CubeList),
+        (status = 400, description = "Query parameters are not valid", body = ErrorResponse),
+        (status = 404, description = "Closet not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn list_cubes(
+    State(state): State<AppState>,
+    Path(definition): Path<LuggageId>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<CubeList>, LuggageError> {
+    let (closet_id, query) = list_query(params)?;
+    let provider = closet_provider(&state, closet_id)?;
+    let page = query_current(&state, provider, definition, query).await?;
+    let cubes = page
+        .cubes
+        .into_iter()
+        .filter_map(|cube| {
+            Some(CubeContent {
+                cube_header: cube.header,
+                content: cube.content?,
+                revision: None,
+            })
+        })
+        .collect();
+    Ok(Json(CubeList {
+        cubes,
+        count: page.count,
+        next: page.next,
+    }))
+}
+
+/// Read a cube
+#[utoipa::path(
+    get,
+    path = "/v1/cube/{definition}/{id}",
+    params(
+        ("definition" = String, Path, description = "ID of the cube's definition"),
+        ("id" = String, Path, description = "ID of the cube"),
+        ClosetQuery
+    ),
+    responses(
+        (status = 200, description = "Cube found", body = CubeContent),
+        (status = 404, description = "Cube or closet not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn read_cube(
+    State(state): State<AppState>,
+    Path((definition, id)): Path<(LuggageId, LuggageId)>,
+    Query(query): Query<ClosetQuery>,
+) -> Result<(HeaderMap, Json<CubeContent>), LuggageError> {
+    let provider = closet_provider(&state, query.closet_id)?;
+    let cube_header = CubeHeader { id, definition };
+    let (content, revision) = read_current(&state, provider, &cube_header).await?;
+    Ok((
+        etag(revision),
+        Json(CubeContent {
+            cube_header,
+            content,
+            revision,
+        }),
+    ))
+}
+
+/// Replace the content of a cube
+#[utoipa::path(
+    put,
+    path = "/v1/cube/{definition}/{id}",
+    params(
+        ("definition" = String, Path, description = "ID of the cube's definition"),
+        ("id" = String, Path, description = "ID of the cube"),
+        ("If-Match" = Option<String>, Header, description = "Only replace the cube if it is still at this revision")
+    ),
+    request_body = ReplaceCube,
+    responses(
+        (status = 200, description = "Cube replaced", body = CubeContent),
+        (status = 400, description = "If-Match is not a revision", body = ErrorResponse),
+        (status = 404, description = "Cube or closet not found", body = ErrorResponse),
+        (status = 409, description = "Cube is not at the If-Match revision", body = ErrorResponse),
+        (status = 422, description = "Content is not valid JSON or does not match its definition", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn replace_cube(
+    State(state): State<AppState>,
+    Path((definition, id)): Path<(LuggageId, LuggageId)>,
+    headers: HeaderMap,
+    Json(payload): Json<ReplaceCube>,
+) -> Result<(HeaderMap, Json<CubeContent>), LuggageError> {
+    let provider = closet_provider(&state, payload.bellhop_header.closet_id)?;
+    let expected = expected_revision(&headers)?;
+    let cube_header = CubeHeader { id, definition };
+    let content = parse_content(&payload.content)?;
+    read_content(provider, cube_header.clone()).await?;
+    let definition = validate_content(&state, provider, &cube_header, &content).await?;
+    let revision = history::update(
+        provider,
+        Cube::new(cube_header, content),
+        expected,
+        Some(definition.version),
+    )
+    .await?;
+    Ok(revision_response(revision))
+}
+
+/// Update a cube with a JSON merge patch (RFC 7396)
+#[utoipa::path(
+    patch,
+    path = "/v1/cube/{definition}/{id}",
+    params(
+        ("definition" = String, Path, description = "ID of the cube's definition"),
+        ("id" = String, Path, description = "ID of the cube"),
+        ClosetQuery,
+        ("If-Match" = Option<String>, Header, description = "Only patch the cube if it is still at this revision")
+    ),
+    request_body(content = serde_json::Value, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Cube patched", body = CubeContent),
+        (status = 400, description = "If-Match is not a revision", body = ErrorResponse),
+        (status = 404, description = "Cube or closet not found", body = ErrorResponse),
+        (status = 409, description = "Cube is not at the If-Match revision", body = ErrorResponse),
+        (status = 422, description = "Patched content does not match its definition", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn patch_cube(
+    State(state): State<AppState>,
+    Path((definition, id)): Path<(LuggageId, LuggageId)>,
+    Query(query): Query<ClosetQuery>,
+    headers: HeaderMap,
+    Json(patch): Json<Value>,
+) -> Result<(HeaderMap, Json<CubeContent>), LuggageError> {
+    let provider = closet_provider(&state, query.closet_id)?;
+    let expected = expected_revision(&headers)?;
+    let cube_header = CubeHeader { id, definition };
+    let (mut content, _) = read_current(&state, provider, &cube_header).await?;
+    merge_patch(&mut content, &patch);
+    let definition = validate_content(&state, provider, &cube_header, &content).await?;
+    let revision = history::update(
+        provider,
+        Cube::new(cube_header, content),
+        expected,
+        Some(definition.version),
+    )
+    .await?;
+    Ok(revision_response(revision))
+}
+
+/// Delete a cube
+#[utoipa::path(
+    delete,
+    path = "/v1/cube/{definition}/{id}",
+    params(
+        ("definition" = String, Path, description = "ID of the cube's definition"),
+        ("id" = String, Path, description = "ID of the cube"),
+        ClosetQuery
+    ),
+    responses(
+        (status = 204, description = "Cube deleted"),
+        (status = 404, description = "Cube or closet not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn delete_cube(
+    State(state): State<AppState>,
+    Path((definition, id)): Path<(LuggageId, LuggageId)>,
+    Query(query): Query<ClosetQuery>,
+) -> Result<StatusCode, LuggageError> {
+    let provider = closet_provider(&state, query.closet_id)?;
+    let cube_header = CubeHeader { id, definition };
+    read_content(provider, cube_header.clone()).await?;
+    provider.delete_value(cube_header).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct RevisionListing {
+    revision: u64,
+    #[schema(value_type = String)]
+    timestamp: DateTime<Utc>,
+}
+
+/// List the revisions of a cube, oldest first
+#[utoipa::path(
+    get,
+    path = "/v1/cube/{definition}/{id}/revisions",
+    params(
+        ("definition" = String, Path, description = "ID of the cube's definition"),
+        ("id" = String, Path, description = "ID of the cube"),
+        ClosetQuery
+    ),
+    responses(
+        (status = 200, description = "Revisions of the cube, including after it was deleted", body = Vec<RevisionListing>),
+        (status = 404, description = "Cube has no revisions or closet not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn list_revisions(
+    State(state): State<AppState>,
+    Path((definition, id)): Path<(LuggageId, LuggageId)>,
+    Query(query): Query<ClosetQuery>,
+) -> Result<Json<Vec<RevisionListing>>, LuggageError> {
+    let provider = closet_provider(&state, query.closet_id)?;
+    let revisions = history::revisions(provider, &CubeHeader { id, definition }).await?;
+    if revisions.is_empty() {
+        return Err(LuggageError::NotFound);
+    }
+    Ok(Json(
+        revisions
+            .into_iter()
+            .map(|revision| RevisionListing {
+                revision: revision.revision,
+                timestamp: revision.timestamp,
+            })
+            .collect(),
+    ))
+}
+
+/// Read one revision of a cube
+#[utoipa::path(
+    get,
+    path = "/v1/cube/{definition}/{id}/revisions/{revision}",
+    params(
+        ("definition" = String, Path, description = "ID of the cube's definition"),
+        ("id" = String, Path, description = "ID of the cube"),
+        ("revision" = u64, Path, description = "Revision number, starting at 1"),
+        ClosetQuery
+    ),
+    responses(
+        (status = 200, description = "Revision found", body = CubeRevision),
+        (status = 404, description = "Revision or closet not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn read_revision(
+    State(state): State<AppState>,
+    Path((definition, id, revision)): Path<(LuggageId, LuggageId, u64)>,
+    Query(query): Query<ClosetQuery>,
+) -> Result<Json<CubeRevision>, LuggageError> {
+    let provider = closet_provider(&state, query.closet_id)?;
+    let revision = history::revision(provider, &CubeHeader { id, definition }, revision).await?;
+    Ok(Json(revision))
+}
+
+/// Restore the content of an old revision as a new revision, recreating the cube if it was deleted
+#[utoipa::path(
+    post,
+    path = "/v1/cube/{definition}/{id}/revisions/{revision}/restore",
+    params(
+        ("definition" = String, Path, description = "ID of the cube's definition"),
+        ("id" = String, Path, description = "ID of the cube"),
+        ("revision" = u64, Path, description = "Revision number to restore"),
+        ClosetQuery,
+        ("If-Match" = Option<String>, Header, description = "Only restore if the cube is still at this revision")
+    ),
+    responses(
+        (status = 200, description = "Revision restored", body = CubeContent),
+        (status = 400, description = "If-Match is not a revision", body = ErrorResponse),
+        (status = 404, description = "Revision or closet not found", body = ErrorResponse),
+        (status = 409, description = "Cube is not at the If-Match revision", body = ErrorResponse),
+        (status = 422, description = "Restored content, upgraded to the current definition version, does not match it", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn restore_revision(
+    State(state): State<AppState>,
+    Path((definition, id, revision)): Path<(LuggageId, LuggageId, u64)>,
+    Query(query): Query<ClosetQuery>,
+    headers: HeaderMap,
+) -> Result<(HeaderMap, Json<CubeContent>), LuggageError> {
+    let provider = closet_provider(&state, query.closet_id)?;
+    let expected = expected_revision(&headers)?;
+    let cube_header = CubeHeader { id, definition };
+    let mut restored = history::revision(provider, &cube_header, revision).await?;
+    let definition = find_definition(&state, provider, definition).await?;
+    restored.content = migration::migrate(
+        &definition,
+        restored.content,
+        restored.definition_version.as_deref(),
+    )?;
+    let definition = validate_content(&state, provider, &cube_header, &restored.content).await?;
+    restored.definition_version = Some(definition.version);
+    let revision = history::restore(provider, restored, expected).await?;
+    Ok(revision_response(revision))
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct FailedMigration {
+    #[schema(value_type = String)]
+    id: LuggageId,
+    error: String,
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct MigrationReport {
+    /// Definition version the cubes were upgraded to
+    version: String,
+    migrated: usize,
+    /// Cubes that were already at the definition version
+    unchanged: usize,
+    /// Cubes left as they were, because their upgraded content does not match the definition
+    failed: Vec<FailedMigration>,
+}
+
+/// Upgrade and validate one cube, recording it as a new revision.
+async fn migrate_cube(
+    provider: &dyn ClosetProvider,
+    definition: &CubeDefinition,
+    validator: &jsonschema::Validator,
+    cube: Cube<Value>,
+    written: Option<String>,
+) -> Result<(), LuggageError> {
+    let content = migration::migrate(
+        definition,
+        cube.content.unwrap_or_default(),
+        written.as_deref(),
+    )?;
+    validation::validate(validator, &content)?;
+    history::update(
+        provider,
+        Cube::new(cube.header, content),
+        None,
+        Some(definition.version.clone()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Rewrite every cube of a definition written under an older version of it
+#[utoipa::path(
+    post,
+    path = "/v1/migrate/{definition}",
+    params(
+        ("definition" = String, Path, description = "ID of the definition whose cubes to upgrade"),
+        ClosetQuery
+    ),
+    responses(
+        (status = 200, description = "Cubes were upgraded, apart from those listed as failed", body = MigrationReport),
+        (status = 404, description = "Closet not found", body = ErrorResponse),
+        (status = 422, description = "Definition not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn migrate_cubes(
+    State(state): State<AppState>,
+    Path(definition): Path<LuggageId>,
+    Query(query): Query<ClosetQuery>,
+) -> Result<Json<MigrationReport>, LuggageError> {
+    let provider = closet_provider(&state, query.closet_id)?;
+    let current = find_definition(&state, provider, definition).await?;
+    let validator = validation::compile(&current.schema)?;
+
+    let mut report = MigrationReport {
+        version: current.version.clone(),
+        migrated: 0,
+        unchanged: 0,
+        failed: Vec::new(),
+    };
+    let mut query = CubeQuery {
+        limit: MAX_QUERY_LIMIT,
+        ..Default::default()
+    };
+    loop {
+        // Pages are in ID order, which rewriting content doesn't change
+        let page = provider.query_values(definition, query.clone()).await?;
+        for cube in page.cubes {
+            let written = history::latest(provider, &cube.header)
+                .await?
+                .and_then(|latest| latest.definition_version);
+            if !migration::is_outdated(&current, written.as_deref())? {
+                report.unchanged += 1;
+                continue;
+            }
+            let id = cube.header.id;
+            match migrate_cube(provider, &current, &validator, cube, written).await {
+                Ok(()) => report.migrated += 1,
+                Err(e) => {
+                    let error = match e {
+                        LuggageError::ValidationFailed(details) => details.join("; "),
+                        e => e.to_string(),
+                    };
+                    report.failed.push(FailedMigration { id, error });
+                }
+            }
+        }
+        match page.next {
+            Some(next) => query.after = Some(next),
+            None => return Ok(Json(report)),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+struct ClosetListing {
+    #[schema(value_type = String)]
+    id: LuggageId,
+    name: String,
+    /// Whether cubes without a `closet_id` are stored in this closet
+    root: bool,
+    execution_type: ClosetExecutionType,
+    builtin_type: Option<ClosetBuiltinType>,
+}
+
+/// List registered closets
+#[utoipa::path(
+    get,
+    path = "/v1/closets",
+    responses(
+        (status = 200, description = "Registered closets", body = Vec<ClosetListing>)
+    ),
+    tag = "bellhop"
+)]
+async fn list_closets(State(state): State<AppState>) -> Json<Vec<ClosetListing>> {
+    let mut closets: Vec<ClosetListing> = state
+        .closet_registry
+        .iter()
+        .map(|(id, closet)| ClosetListing {
+            id: *id,
+            name: closet.name.clone(),
+            root: *id == state.root_closet_id,
+            execution_type: closet.execution_type.clone(),
+            builtin_type: closet.builtin_type.clone(),
+        })
+        .collect();
+    // The root closet comes first
+    closets.sort_by_key(|closet| (!closet.root, closet.name.clone()));
+    Json(closets)
+}
+
+/// Find a closet by its ID or name.
+pub fn find_closet(state: &AppState, closet: &str) -> Result<LuggageId, LuggageError> {
+    if let Ok(id) = LuggageId::try_parse(closet) {
+        return match state.closet_registry.contains_key(&id) {
+            true => Ok(id),
+            false => Err(LuggageError::ClosetNotFound(closet.into())),
+        };
+    }
+    state
+        .closet_registry
+        .iter()
+        .find(|(_, registered)| registered.name == closet)
+        .map(|(id, _)| *id)
+        .ok_or_else(|| LuggageError::ClosetNotFound(closet.into()))
+}
+
+/// Every definition stored in a closet, followed by those of the root closet.
+async fn stored_definitions(
+    state: &AppState,
+    provider: &dyn ClosetProvider,
+) -> Result<Vec<CubeDefinition>, LuggageError> {
+    let mut definitions = Vec::new();
+    for provider in [provider, closet_provider(state, None)?] {
+        let mut query = CubeQuery {
+            limit: MAX_QUERY_LIMIT,
+            ..Default::default()
+        };
+        loop {
+            let page = provider
+                .query_values(CubeDefinition::id(), query.clone())
+                .await?;
+            for content in page.cubes.into_iter().filter_map(|cube| cube.content) {
+                definitions.push(serde_json::from_value(content)?);
+            }
+            match page.next {
+                Some(next) => query.after = Some(next),
+                None => break,
+            }
+        }
+    }
+    Ok(definitions)
+}
+
+/// Start exporting a closet, whose archive arrives in chunks. An error ends the archive early,
+/// without its manifest.
+pub async fn export_archive(
+    state: &AppState,
+    closet_id: LuggageId,
+) -> Result<mpsc::Receiver<Result<Vec<u8>, LuggageError>>, LuggageError> {
+    let closet = state
+        .closet_registry
+        .get(&closet_id)
+        .ok_or_else(|| LuggageError::ClosetNotFound(closet_id.to_string()))?;
+    let provider = state
+        .closet_providers
+        .get(&closet_id)
+        .cloned()
+        .ok_or_else(|| LuggageError::ClosetNotFound(closet_id.to_string()))?;
+    let definitions = stored_definitions(state, provider.as_ref()).await?;
+
+    let name = closet.name.clone();
+    let (sender, receiver) = mpsc::channel(16);
+    tokio::spawn(async move {
+        if let Err(e) = archive::export(provider.as_ref(), &name, &definitions, &sender).await {
+            let _ = sender.send(Err(e)).await;
+        }
+    });
+    Ok(receiver)
+}
+
+/// Import an archive into a closet, storing the archived definitions neither the closet nor the
+/// root closet has.
+pub async fn import_archive(
+    state: &AppState,
+    closet_id: LuggageId,
+    archive: &[u8],
+) -> Result<ImportReport, LuggageError> {
+    let provider = closet_provider(state, Some(closet_id))?;
+    let mut archive = archive::read(archive)?;
+    let mut missing = Vec::new();
+    for definition in std::mem::take(&mut archive.definitions) {
+        let id = LuggageId::try_parse(&definition.id).map_err(|_| {
+            LuggageError::InvalidArchive(format!("{} is not a valid definition ID", definition.id))
+        })?;
+        match find_definition(state, provider, id).await {
+            Ok(_) => {}
+            Err(LuggageError::UnknownDefinition(_)) => missing.push(definition),
+            Err(e) => return Err(e),
+        }
+    }
+    archive.definitions = missing;
+    archive::import(provider, archive).await
+}
+
+/// Download every cube of a closet, with the definitions they use, as a tar archive
+#[utoipa::path(
+    get,
+    path = "/v1/closets/{id}/export",
+    params(
+        ("id" = String, Path, description = "ID of the closet to export")
+    ),
+    responses(
+        (status = 200, description = "Archive of `cubes/<definition>/<id>.json` and `definitions/<id>.json` files, with a `manifest.json` of their SHA-256 checksums", body = String, content_type = "application/x-tar"),
+        (status = 404, description = "Closet not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn export_closet(
+    State(state): State<AppState>,
+    Path(closet_id): Path<LuggageId>,
+) -> Result<Response, LuggageError> {
+    let receiver = export_archive(&state, closet_id).await?;
+    let chunks = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+    let headers = [
+        (CONTENT_TYPE, "application/x-tar".to_string()),
+        (
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.tar\"", closet_id),
+        ),
+    ];
+    Ok((headers, Body::from_stream(chunks)).into_response())
+}
+
+/// Recreate the cubes of an exported archive in a closet, keeping their IDs
+#[utoipa::path(
+    post,
+    path = "/v1/closets/{id}/import",
+    params(
+        ("id" = String, Path, description = "ID of the closet to import into")
+    ),
+    request_body(content = String, content_type = "application/x-tar", description = "Archive made by an export"),
+    responses(
+        (status = 201, description = "Cubes were imported", body = ImportReport),
+        (status = 404, description = "Closet not found", body = ErrorResponse),
+        (status = 409, description = "Cubes of the archive already exist in the closet, none were imported", body = ErrorResponse),
+        (status = 422, description = "Archive is not valid or does not match its manifest", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "bellhop"
+)]
+async fn import_closet(
+    State(state): State<AppState>,
+    Path(closet_id): Path<LuggageId>,
+    archive: Bytes,
+) -> Result<(StatusCode, Json<ImportReport>), LuggageError> {
+    let report = import_archive(&state, closet_id, &archive).await?;
+    Ok((StatusCode::CREATED, Json(report)))
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        closet::closet::Closet,
+        closet::closet::{Closet, ClosetConnection},
         cube::cube::{CubeDefinition, CubeRegistration, CubeSchema},
         error::Result,
     };
@@ -194,7 +1277,7 @@ mod tests {
         let cube = CreateCube {
             bellhop_header: BellhopHeader { closet_id: None },
             cube_header: CubeHeader {
-                id: Uuid::now_v7(),
+                id: CubeDefinition::id(),
                 definition: CubeDefinition::id(),
             },
             content: json!({
@@ -218,11 +1301,7 @@ mod tests {
                 id: Uuid::now_v7(),
                 definition: Closet::id(),
             },
-            content: json!({
-                "id": Closet::id(),
-                "schema": Closet::schema()
-            })
-            .to_string(),
+            content: serde_json::to_string(&Closet::default())?,
         };
         let response = server.post("/v1/cube").json(&cube).await;
         response.assert_status(StatusCode::CREATED);
@@ -233,11 +1312,10 @@ mod tests {
     async fn create_test_content_in_default_provider() -> Result<()> {
         let test_name = "create_test_content_using_default_provider";
         let server = TestServer::new(app(None).await?)?;
-        let test_cube_definition_id = Uuid::now_v7();
         let test_cube_definition = CreateCube {
             bellhop_header: BellhopHeader { closet_id: None },
             cube_header: CubeHeader {
-                id: test_cube_definition_id,
+                id: TestContent::id(),
                 definition: CubeDefinition::id(),
             },
             content: json!({
@@ -263,12 +1341,648 @@ mod tests {
             content: serde_json::to_string(&test_content)
                 .expect("test_content should serialize to json."),
         };
-        let test_response = server.post("/v1/cube").json(&test_cube_definition).await;
+        let test_response = server.post("/v1/cube").json(&test_cube).await;
         test_response.assert_status(StatusCode::CREATED);
 
         Ok(())
     }
 
+    /// Store the definition of `TestContent` in the root closet.
+    async fn register_test_content(server: &TestServer) {
+        let definition = CreateCube {
+            bellhop_header: BellhopHeader { closet_id: None },
+            cube_header: CubeHeader {
+                id: TestContent::id(),
+                definition: CubeDefinition::id(),
+            },
+            content: json!({
+                "id": TestContent::id(),
+                "schema": TestContent::schema()
+            })
+            .to_string(),
+        };
+        let response = server.post("/v1/cube").json(&definition).await;
+        response.assert_status(StatusCode::CREATED);
+    }
+
+    fn test_cube(content: Value) -> CreateCube {
+        CreateCube {
+            bellhop_header: BellhopHeader { closet_id: None },
+            cube_header: CubeHeader::new(TestContent::id()),
+            content: content.to_string(),
+        }
+    }
+
+    fn cube_path(header: &CubeHeader) -> String {
+        format!("/v1/cube/{}/{}", header.definition, header.id)
+    }
+
+    #[tokio::test]
+    async fn create_then_read_cube() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let content = json!({ "name": "create_then_read_cube", "todos": ["todo_one"] });
+        let cube = test_cube(content.clone());
+        let create_response = server.post("/v1/cube").json(&cube).await;
+        create_response.assert_status(StatusCode::CREATED);
+
+        let response = server.get(&cube_path(&cube.cube_header)).await;
+        response.assert_status(StatusCode::OK);
+        let read: CubeContent = response.json();
+        assert_eq!(read.cube_header.id, cube.cube_header.id);
+        assert_eq!(read.content, content);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_existing_cube_conflicts() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let cube = test_cube(json!({ "name": "create_existing_cube_conflicts", "todos": [] }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CONFLICT);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_cube_with_invalid_content_is_unprocessable() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let mut cube = test_cube(Value::Null);
+        cube.content = "{ not json".into();
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_cube_not_matching_definition_is_unprocessable() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let cube = test_cube(json!({ "name": 1 }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        let error: ErrorResponse = response.json();
+        assert_eq!(error.details.len(), 2);
+
+        let response = server.get(&cube_path(&cube.cube_header)).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_cube_with_unknown_definition_is_unprocessable() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let cube = test_cube(json!({ "name": "unregistered", "todos": [] }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_definition_with_invalid_schema_is_unprocessable() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let definition_id = Uuid::now_v7();
+        let mut definition = test_cube(json!({
+            "id": definition_id,
+            "schema": json!({ "type": 5 }).to_string()
+        }));
+        definition.cube_header = CubeHeader {
+            id: definition_id,
+            definition: CubeDefinition::id(),
+        };
+        let response = server.post("/v1/cube").json(&definition).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        // The definition's content must name the cube it is stored as
+        definition.content = json!({ "id": Uuid::now_v7(), "schema": "{}" }).to_string();
+        let response = server.post("/v1/cube").json(&definition).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_missing_cube_is_not_found() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let header = CubeHeader::new(TestContent::id());
+        let response = server.get(&cube_path(&header)).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replace_cube_content() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let cube = test_cube(json!({ "name": "original", "todos": [] }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let replacement = json!({ "name": "replaced", "todos": ["todo_one"] });
+        let response = server
+            .put(&cube_path(&cube.cube_header))
+            .json(&ReplaceCube {
+                bellhop_header: BellhopHeader { closet_id: None },
+                content: replacement.to_string(),
+            })
+            .await;
+        response.assert_status(StatusCode::OK);
+
+        let read: CubeContent = server.get(&cube_path(&cube.cube_header)).await.json();
+        assert_eq!(read.content, replacement);
+
+        let missing = CubeHeader::new(TestContent::id());
+        let response = server
+            .put(&cube_path(&missing))
+            .json(&ReplaceCube {
+                bellhop_header: BellhopHeader { closet_id: None },
+                content: replacement.to_string(),
+            })
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn patch_cube_merges_content() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let cube = test_cube(json!({ "name": "original", "todos": ["todo_one"], "done": false }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response = server
+            .patch(&cube_path(&cube.cube_header))
+            .json(&json!({ "name": "patched", "done": null }))
+            .await;
+        response.assert_status(StatusCode::OK);
+
+        let read: CubeContent = server.get(&cube_path(&cube.cube_header)).await.json();
+        assert_eq!(
+            read.content,
+            json!({ "name": "patched", "todos": ["todo_one"] })
+        );
+
+        let response = server
+            .patch(&cube_path(&cube.cube_header))
+            .json(&json!({ "todos": null }))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_cube_then_read_is_not_found() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let cube = test_cube(json!({ "name": "delete_cube_then_read_is_not_found", "todos": [] }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response = server.delete(&cube_path(&cube.cube_header)).await;
+        response.assert_status(StatusCode::NO_CONTENT);
+
+        let response = server.get(&cube_path(&cube.cube_header)).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        let response = server.delete(&cube_path(&cube.cube_header)).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_cubes_pages_through_filtered_cubes() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        for (name, todos) in [("b", 1), ("a", 0), ("c", 1), ("d", 1)] {
+            let todos: Vec<String> = (0..todos).map(|_| "x".to_string()).collect();
+            let cube = test_cube(json!({ "name": name, "todos": todos }));
+            let response = server.post("/v1/cube").json(&cube).await;
+            response.assert_status(StatusCode::CREATED);
+        }
+
+        let path = format!("/v1/cube/{}", TestContent::id());
+        let response = server.get(&path).await;
+        response.assert_status(StatusCode::OK);
+        let list: CubeList = response.json();
+        assert_eq!(list.count, 4);
+        assert!(list.next.is_none());
+
+        let query = "filter.todos.0=x&sort=-name&limit=2";
+        let first: CubeList = server.get(&format!("{}?{}", path, query)).await.json();
+        assert_eq!(first.count, 3);
+        let next = first.next.expect("first page should have a next page");
+        let second: CubeList = server
+            .get(&format!("{}?{}&after={}", path, query, next))
+            .await
+            .json();
+        let names: Vec<Value> = first
+            .cubes
+            .iter()
+            .chain(&second.cubes)
+            .map(|cube| cube.content["name"].clone())
+            .collect();
+        assert_eq!(names, vec![json!("d"), json!("c"), json!("b")]);
+        assert!(second.next.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_cubes_with_invalid_query_is_bad_request() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let path = format!("/v1/cube/{}", TestContent::id());
+        for query in ["limit=0", "sort=name;", "after=later", "unknown=1"] {
+            let response = server.get(&format!("{}?{}", path, query)).await;
+            response.assert_status(StatusCode::BAD_REQUEST);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stale_if_match_conflicts() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let cube = test_cube(json!({ "name": "stale_if_match_conflicts", "todos": [] }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(response.header(ETAG), "\"1\"");
+
+        let path = cube_path(&cube.cube_header);
+        let replace = ReplaceCube {
+            bellhop_header: BellhopHeader { closet_id: None },
+            content: json!({ "name": "replaced", "todos": [] }).to_string(),
+        };
+        let response = server
+            .put(&path)
+            .add_header(IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&replace)
+            .await;
+        response.assert_status(StatusCode::OK);
+        assert_eq!(response.json::<CubeContent>().revision, Some(2));
+
+        let response = server
+            .patch(&path)
+            .add_header(IF_MATCH, HeaderValue::from_static("\"1\""))
+            .json(&json!({ "name": "lost" }))
+            .await;
+        response.assert_status(StatusCode::CONFLICT);
+
+        let response = server.get(&path).await;
+        assert_eq!(response.header(ETAG), "\"2\"");
+        assert_eq!(response.json::<CubeContent>().content["name"], "replaced");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restore_old_revision() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        register_test_content(&server).await;
+        let cube = test_cube(json!({ "name": "original", "todos": [] }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+        let path = cube_path(&cube.cube_header);
+        let response = server
+            .patch(&path)
+            .json(&json!({ "name": "patched" }))
+            .await;
+        response.assert_status(StatusCode::OK);
+
+        let revisions: Vec<RevisionListing> =
+            server.get(&format!("{}/revisions", path)).await.json();
+        let numbers: Vec<u64> = revisions.iter().map(|r| r.revision).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        let first: CubeRevision = server.get(&format!("{}/revisions/1", path)).await.json();
+        assert_eq!(first.content["name"], "original");
+
+        let response = server.delete(&path).await;
+        response.assert_status(StatusCode::NO_CONTENT);
+        let response = server.post(&format!("{}/revisions/1/restore", path)).await;
+        response.assert_status(StatusCode::OK);
+        assert_eq!(response.json::<CubeContent>().revision, Some(3));
+
+        let restored: CubeContent = server.get(&path).await.json();
+        assert_eq!(restored.content["name"], "original");
+        let response = server.get(&format!("{}/revisions/9", path)).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn revisions_can_not_be_written_directly() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let mut cube = test_cube(json!({}));
+        cube.cube_header = CubeHeader::new(CubeRevision::id());
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cubes_of_older_definition_versions_are_upgraded() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let definition = CubeHeader::new(CubeDefinition::id());
+        let mut register = test_cube(json!({
+            "id": definition.id,
+            "schema": json!({ "type": "object", "required": ["title"] }).to_string()
+        }));
+        register.cube_header = definition.clone();
+        let response = server.post("/v1/cube").json(&register).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let mut cube = test_cube(json!({ "title": "old" }));
+        cube.cube_header = CubeHeader::new(definition.id);
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let upgrade = ReplaceCube {
+            bellhop_header: BellhopHeader { closet_id: None },
+            content: json!({
+                "id": definition.id,
+                "schema": json!({ "type": "object", "required": ["name", "done"] }).to_string(),
+                "version": "2.0.0",
+                "migrations": [{
+                    "version": "2.0.0",
+                    "steps": [
+                        { "op": "rename", "field": "title", "to": "name" },
+                        { "op": "default", "field": "done", "value": false }
+                    ]
+                }]
+            })
+            .to_string(),
+        };
+        let response = server.put(&cube_path(&definition)).json(&upgrade).await;
+        response.assert_status(StatusCode::OK);
+
+        let upgraded = json!({ "name": "old", "done": false });
+        let read: CubeContent = server.get(&cube_path(&cube.cube_header)).await.json();
+        assert_eq!(read.content, upgraded);
+        let list: CubeList = server
+            .get(&format!(
+                "/v1/cube/{}?filter.name=old&sort=-done",
+                definition.id
+            ))
+            .await
+            .json();
+        assert_eq!(list.count, 1);
+        assert_eq!(list.cubes[0].content, upgraded);
+
+        let response = server.post(&format!("/v1/migrate/{}", definition.id)).await;
+        response.assert_status(StatusCode::OK);
+        let report: MigrationReport = response.json();
+        assert_eq!((report.migrated, report.unchanged), (1, 0));
+        assert!(report.failed.is_empty());
+        let list: CubeList = server
+            .get(&format!("/v1/cube/{}", definition.id))
+            .await
+            .json();
+        assert_eq!(list.cubes[0].content, upgraded);
+
+        let report: MigrationReport = server
+            .post(&format!("/v1/migrate/{}", definition.id))
+            .await
+            .json();
+        assert_eq!((report.migrated, report.unchanged), (0, 1));
+
+        let mut downgrade: Value = serde_json::from_str(&upgrade.content)?;
+        downgrade["version"] = json!("1.5.0");
+        downgrade["migrations"] = json!([]);
+        let downgrade = ReplaceCube {
+            content: downgrade.to_string(),
+            ..upgrade
+        };
+        let response = server.put(&cube_path(&definition)).json(&downgrade).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    async fn app_with_second_closet() -> Result<Router> {
+        app(Some(StartupConfiguration {
+            name: None,
+            closet: Some(Closet {
+                name: "second_closet".into(),
+                execution_type: ClosetExecutionType::Builtin,
+                builtin_type: Some(ClosetBuiltinType::LocalSurrealDb),
+                connection: None,
+            }),
+        }))
+        .await
+    }
+
+    #[tokio::test]
+    async fn list_closets_includes_configured_closets() -> Result<()> {
+        let server = TestServer::new(app_with_second_closet().await?)?;
+        let response = server.get("/v1/closets").await;
+        response.assert_status(StatusCode::OK);
+        let closets: Vec<ClosetListing> = response.json();
+        assert_eq!(closets.len(), 2);
+        assert!(closets[0].root);
+        assert_eq!(closets[0].name, Closet::default().name);
+        assert!(!closets[1].root);
+        assert_eq!(closets[1].name, "second_closet");
+        assert_eq!(
+            closets[1].builtin_type,
+            Some(ClosetBuiltinType::LocalSurrealDb)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn closet_ids_are_stable_across_restarts() -> Result<()> {
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let server = TestServer::new(app_with_second_closet().await?)?;
+            let closets: Vec<ClosetListing> = server.get("/v1/closets").await.json();
+            ids.push(closets.iter().map(|closet| closet.id).collect::<Vec<_>>());
+        }
+        assert_eq!(ids[0], ids[1]);
+        assert_ne!(ids[0][0], ids[0][1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn find_closet_by_id_or_name() -> Result<()> {
+        let state = state(Some(StartupConfiguration {
+            name: None,
+            closet: Some(Closet {
+                name: "second_closet".into(),
+                execution_type: ClosetExecutionType::Builtin,
+                builtin_type: Some(ClosetBuiltinType::LocalSurrealDb),
+                connection: None,
+            }),
+        }))
+        .await?;
+        let id = find_closet(&state, "second_closet")?;
+        assert_eq!(find_closet(&state, &id.to_string())?, id);
+        for unknown in ["missing_closet".to_string(), Uuid::now_v7().to_string()] {
+            assert!(matches!(
+                find_closet(&state, &unknown),
+                Err(LuggageError::ClosetNotFound(closet)) if closet == unknown
+            ));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cube_is_routed_to_chosen_closet() -> Result<()> {
+        let server = TestServer::new(app_with_second_closet().await?)?;
+        register_test_content(&server).await;
+        let closets: Vec<ClosetListing> = server.get("/v1/closets").await.json();
+        let second_closet_id = closets[1].id;
+
+        let mut cube = test_cube(json!({ "name": "cube_is_routed_to_chosen_closet", "todos": [] }));
+        cube.bellhop_header.closet_id = Some(second_closet_id);
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let path = cube_path(&cube.cube_header);
+        let response = server.get(&path).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        let response = server
+            .get(&format!("{}?closet_id={}", path, second_closet_id))
+            .await;
+        response.assert_status(StatusCode::OK);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_then_import_into_another_closet() -> Result<()> {
+        let server = TestServer::new(app_with_second_closet().await?)?;
+        register_test_content(&server).await;
+        let closets: Vec<ClosetListing> = server.get("/v1/closets").await.json();
+        let import_path = format!("/v1/closets/{}/import", closets[1].id);
+        let cube =
+            test_cube(json!({ "name": "export_then_import_into_another_closet", "todos": [] }));
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response = server
+            .get(&format!("/v1/closets/{}/export", closets[0].id))
+            .await;
+        response.assert_status(StatusCode::OK);
+        assert_eq!(response.header(CONTENT_TYPE), "application/x-tar");
+        let exported = response.as_bytes().clone();
+
+        let response = server.post(&import_path).bytes(exported.clone()).await;
+        response.assert_status(StatusCode::CREATED);
+        let report: ImportReport = response.json();
+        // The definition and the cube, with a revision each
+        assert_eq!(report.cubes, 4);
+        assert_eq!(report.definitions, 0);
+        let response = server
+            .get(&format!(
+                "{}?closet_id={}",
+                cube_path(&cube.cube_header),
+                closets[1].id
+            ))
+            .await;
+        response.assert_status(StatusCode::OK);
+        assert_eq!(response.json::<CubeContent>().revision, Some(1));
+
+        let response = server.post(&import_path).bytes(exported).await;
+        response.assert_status(StatusCode::CONFLICT);
+        let error: ErrorResponse = response.json();
+        assert_eq!(error.details.len(), 4);
+
+        let response = server
+            .post(&import_path)
+            .bytes(Bytes::from_static(b"not an archive"))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cube_is_stored_in_file_system_closet() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("luggage-bellhop-{}", Uuid::now_v7()));
+        let server = TestServer::new(
+            app(Some(StartupConfiguration {
+                name: None,
+                closet: Some(Closet {
+                    name: "file_system_closet".into(),
+                    execution_type: ClosetExecutionType::Builtin,
+                    builtin_type: Some(ClosetBuiltinType::LocalFileSystem),
+                    connection: Some(ClosetConnection {
+                        path: Some(root.clone()),
+                        ..Default::default()
+                    }),
+                }),
+            }))
+            .await?,
+        )?;
+        register_test_content(&server).await;
+        let closets: Vec<ClosetListing> = server.get("/v1/closets").await.json();
+
+        let mut cube =
+            test_cube(json!({ "name": "cube_is_stored_in_file_system_closet", "todos": [] }));
+        cube.bellhop_header.closet_id = Some(closets[1].id);
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::CREATED);
+        assert!(
+            root.join(cube.cube_header.definition.to_string())
+                .join(format!("{}.json", cube.cube_header.id))
+                .exists()
+        );
+
+        std::fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unknown_closet_is_not_found() -> Result<()> {
+        let server = TestServer::new(app(None).await?)?;
+        let mut cube = test_cube(json!({ "name": "unknown_closet_is_not_found" }));
+        cube.bellhop_header.closet_id = Some(Uuid::now_v7());
+        let response = server.post("/v1/cube").json(&cube).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        let response = server
+            .get(&format!(
+                "{}?closet_id={}",
+                cube_path(&cube.cube_header),
+                Uuid::now_v7()
+            ))
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_patch_follows_rfc_7396() {
+        let mut target = json!({ "a": "b", "c": { "d": "e", "f": "g" } });
+        merge_patch(&mut target, &json!({ "a": "z", "c": { "f": null } }));
+        assert_eq!(target, json!({ "a": "z", "c": { "d": "e" } }));
+
+        merge_patch(&mut target, &json!(["replaced"]));
+        assert_eq!(target, json!(["replaced"]));
+    }
+
+    #[tokio::test]
+    async fn health_check_reports_closets() -> Result<()> {
+        let server = TestServer::new(app_with_second_closet().await?)?;
+        let response = server.get("/").await;
+        response.assert_status(StatusCode::OK);
+        let health: Health = response.json();
+        assert_eq!(health.closets.len(), 2);
+        assert!(health.closets.iter().all(|closet| closet.healthy));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remote_closet_requires_url() {
+        let result = app(Some(StartupConfiguration {
+            name: None,
+            closet: Some(Closet {
+                name: "remote_closet".into(),
+                execution_type: ClosetExecutionType::Builtin,
+                builtin_type: Some(ClosetBuiltinType::RemoteSurrealDb),
+                connection: None,
+            }),
+        }))
+        .await;
+        assert!(matches!(result, Err(LuggageError::ClosetConfiguration(_))));
+    }
+
     #[tokio::test]
     async fn swagger_ui_is_accessible() -> Result<()> {
         let server = TestServer::new(app(None).await?).unwrap();
@@ -0,0 +1,437 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::Read,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use crate::{
+    closet::{
+        closet::{Closet, ClosetProvider},
+        history::CubeRevision,
+        query::{CubeQuery, MAX_QUERY_LIMIT},
+    },
+    core::core::LuggageId,
+    cube::cube::{Cube, CubeDefinition, CubeHeader, CubeRegistration},
+    error::LuggageError,
+};
+
+/// Version of the archive layout, raised when it changes in a way older readers can't handle
+pub const ARCHIVE_FORMAT: u32 = 1;
+const MANIFEST_PATH: &str = "manifest.json";
+const DEFINITIONS_DIRECTORY: &str = "definitions/";
+const CUBES_DIRECTORY: &str = "cubes/";
+
+/// Describes an archive, stored as `manifest.json` next to
+/// `definitions/<definition id>.json` and `cubes/<definition id>/<cube id>.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchiveManifest {
+    pub format: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Name of the exported closet
+    pub closet: String,
+    /// SHA-256 of every other file in the archive, by path
+    pub files: BTreeMap<String, String>,
+}
+
+/// The contents of an archive whose checksums have been verified.
+#[derive(Debug)]
+pub struct Archive {
+    pub manifest: ArchiveManifest,
+    pub definitions: Vec<CubeDefinition>,
+    pub cubes: Vec<Cube<Value>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, utoipa::ToSchema)]
+pub struct ImportReport {
+    pub cubes: usize,
+    /// Definitions that were only in the archive and are now stored in the closet
+    pub definitions: usize,
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn invalid(message: impl Into<String>) -> LuggageError {
+    LuggageError::InvalidArchive(message.into())
+}
+
+/// Writes a tar archive entry by entry, handing out the bytes written so far so the archive can be
+/// streamed while it is built.
+struct ArchiveBuilder {
+    builder: tar::Builder<Vec<u8>>,
+    files: BTreeMap<String, String>,
+    exported_at: DateTime<Utc>,
+}
+
+impl ArchiveBuilder {
+    fn new() -> Self {
+        Self {
+            builder: tar::Builder::new(Vec::new()),
+            files: BTreeMap::new(),
+            exported_at: Utc::now(),
+        }
+    }
+
+    fn append_entry(&mut self, path: &str, data: &[u8]) -> Result<Vec<u8>, LuggageError> {
+        let mut header = tar::Header::new_ustar();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(self.exported_at.timestamp().max(0) as u64);
+        self.builder.append_data(&mut header, path, data)?;
+        Ok(std::mem::take(self.builder.get_mut()))
+    }
+
+    fn append(&mut self, path: String, value: &impl Serialize) -> Result<Vec<u8>, LuggageError> {
+        let data = serde_json::to_vec_pretty(value)?;
+        let chunk = self.append_entry(&path, &data)?;
+        self.files.insert(path, sha256(&data));
+        Ok(chunk)
+    }
+
+    fn finish(mut self, closet: &str) -> Result<Vec<u8>, LuggageError> {
+        let manifest = ArchiveManifest {
+            format: ARCHIVE_FORMAT,
+            exported_at: self.exported_at,
+            closet: closet.into(),
+            files: std::mem::take(&mut self.files),
+        };
+        let mut chunk = self.append_entry(MANIFEST_PATH, &serde_json::to_vec_pretty(&manifest)?)?;
+        chunk.extend(self.builder.into_inner()?);
+        Ok(chunk)
+    }
+}
+
+async fn send(
+    chunks: &mpsc::Sender<Result<Vec<u8>, LuggageError>>,
+    chunk: Vec<u8>,
+) -> Result<(), LuggageError> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    // The receiver is gone when a download is cancelled
+    chunks
+        .send(Ok(chunk))
+        .await
+        .map_err(|_| LuggageError::Unknown)
+}
+
+/// Export the cubes of a closet as a tar archive, sending it in chunks as it is built.
+///
+/// Every cube of the closet is exported, including every revision and cubes whose definition was
+/// deleted. The schemas of `definitions` are stored in the archive so it can be imported anywhere.
+pub async fn export<P>(
+    provider: &P,
+    closet: &str,
+    definitions: &[CubeDefinition],
+    chunks: &mpsc::Sender<Result<Vec<u8>, LuggageError>>,
+) -> Result<(), LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let mut builder = ArchiveBuilder::new();
+    let builtin = [CubeDefinition::id(), Closet::id(), CubeRevision::id()];
+    let mut archived = HashSet::new();
+    for definition in definitions {
+        let id = LuggageId::try_parse(&definition.id).map_err(|_| LuggageError::Unknown)?;
+        if builtin.contains(&id) || !archived.insert(id) {
+            continue;
+        }
+        let path = format!("{}{}.json", DEFINITIONS_DIRECTORY, id);
+        send(chunks, builder.append(path, definition)?).await?;
+    }
+
+    let mut definition_ids = provider.definitions().await?;
+    definition_ids.sort();
+    for definition in definition_ids {
+        let mut query = CubeQuery {
+            limit: MAX_QUERY_LIMIT,
+            ..Default::default()
+        };
+        loop {
+            let page = provider.query_values(definition, query.clone()).await?;
+            for cube in &page.cubes {
+                let path = format!(
+                    "{}{}/{}.json",
+                    CUBES_DIRECTORY, cube.header.definition, cube.header.id
+                );
+                send(chunks, builder.append(path, cube)?).await?;
+            }
+            match page.next {
+                Some(next) => query.after = Some(next),
+                None => break,
+            }
+        }
+    }
+
+    send(chunks, builder.finish(closet)?).await
+}
+
+/// Read an archive, checking that it holds exactly the files of its manifest with their checksums.
+pub fn read(archive: &[u8]) -> Result<Archive, LuggageError> {
+    let not_tar = |e: std::io::Error| invalid(format!("not a tar archive: {}", e));
+    let mut files = BTreeMap::new();
+    let mut entries = tar::Archive::new(archive);
+    for entry in entries.entries().map_err(not_tar)? {
+        let mut entry = entry.map_err(not_tar)?;
+        let path = entry
+            .path()
+            .map_err(not_tar)?
+            .to_string_lossy()
+            .into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).map_err(not_tar)?;
+        files.insert(path, data);
+    }
+
+    let manifest = files
+        .remove(MANIFEST_PATH)
+        .ok_or_else(|| invalid("the archive has no manifest"))?;
+    let manifest: ArchiveManifest = serde_json::from_slice(&manifest)
+        .map_err(|e| invalid(format!("the manifest is not valid: {}", e)))?;
+    if manifest.format > ARCHIVE_FORMAT {
+        return Err(invalid(format!(
+            "archive format {} is newer than this luggage supports",
+            manifest.format
+        )));
+    }
+    if let Some(path) = files
+        .keys()
+        .find(|path| !manifest.files.contains_key(*path))
+    {
+        return Err(invalid(format!("{} is not in the manifest", path)));
+    }
+
+    let mut definitions = Vec::new();
+    let mut cubes = Vec::new();
+    for (path, checksum) in &manifest.files {
+        let data = files
+            .get(path)
+            .ok_or_else(|| invalid(format!("{} is missing", path)))?;
+        if sha256(data) != *checksum {
+            return Err(invalid(format!("the checksum of {} does not match", path)));
+        }
+        let parse_error = |e: serde_json::Error| invalid(format!("{} is not valid: {}", path, e));
+        if path.starts_with(DEFINITIONS_DIRECTORY) {
+            definitions.push(serde_json::from_slice(data).map_err(parse_error)?);
+        } else if path.starts_with(CUBES_DIRECTORY) {
+            cubes.push(serde_json::from_slice(data).map_err(parse_error)?);
+        }
+    }
+    Ok(Archive {
+        manifest,
+        definitions,
+        cubes,
+    })
+}
+
+/// Recreate the cubes of an archive in a closet, keeping their IDs and revisions.
+///
+/// Nothing is written when any of the cubes already exists. Cubes are stored as they were exported,
+/// without validating them against their definitions. Archived definitions the closet doesn't
+/// have are stored too, so drop those the caller finds elsewhere first.
+pub async fn import<P>(provider: &P, archive: Archive) -> Result<ImportReport, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let mut collisions = Vec::new();
+    for cube in &archive.cubes {
+        if provider
+            .read_value(cube.header.clone())
+            .await?
+            .content
+            .is_some()
+        {
+            collisions.push(format!("{}/{}", cube.header.definition, cube.header.id));
+        }
+    }
+    if !collisions.is_empty() {
+        return Err(LuggageError::ImportCollision(collisions));
+    }
+
+    let archived: HashSet<(LuggageId, LuggageId)> = archive
+        .cubes
+        .iter()
+        .map(|cube| (cube.header.definition, cube.header.id))
+        .collect();
+    let mut report = ImportReport::default();
+    for definition in archive.definitions {
+        let id = LuggageId::try_parse(&definition.id)
+            .map_err(|_| invalid(format!("{} is not a valid definition ID", definition.id)))?;
+        let header = CubeHeader {
+            id,
+            definition: CubeDefinition::id(),
+        };
+        if archived.contains(&(header.definition, header.id))
+            || provider.read_value(header.clone()).await?.content.is_some()
+        {
+            continue;
+        }
+        provider
+            .create_value(Cube::new(header, serde_json::to_value(definition)?))
+            .await?;
+        report.definitions += 1;
+    }
+
+    for cube in archive.cubes {
+        provider.create_value(cube).await?;
+        report.cubes += 1;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::{
+        closet::{
+            closet::{ClosetCreator, ClosetDeleter, ClosetReader, ClosetUpdater},
+            history,
+            providers::sqlite::SqliteClosetProvider,
+        },
+        error::Result,
+    };
+
+    use super::*;
+
+    fn test_definition() -> CubeDefinition {
+        CubeDefinition {
+            id: Uuid::now_v7().to_string(),
+            schema: json!({ "type": "object" }).to_string(),
+            version: "1.0.0".into(),
+            migrations: Vec::new(),
+        }
+    }
+
+    async fn export_to_bytes(
+        closet: &SqliteClosetProvider,
+        definitions: &[CubeDefinition],
+    ) -> Result<Vec<u8>> {
+        let (sender, mut receiver) = mpsc::channel(4);
+        let exporting = async {
+            let result = export(closet, "test", definitions, &sender).await;
+            drop(sender);
+            result
+        };
+        let collecting = async {
+            let mut bytes = Vec::new();
+            while let Some(chunk) = receiver.recv().await {
+                bytes.extend(chunk?);
+            }
+            Ok::<_, LuggageError>(bytes)
+        };
+        let (exported, bytes) = tokio::join!(exporting, collecting);
+        exported?;
+        bytes
+    }
+
+    #[tokio::test]
+    async fn export_then_import_keeps_ids_and_history() -> Result<()> {
+        let definition = test_definition();
+        let definition_id = Uuid::try_parse(&definition.id).map_err(|_| LuggageError::Unknown)?;
+        let source = SqliteClosetProvider::open_in_memory()?;
+        let header = source
+            .create(Cube::new(
+                CubeHeader::new(definition_id),
+                json!({ "name": "first" }),
+            ))
+            .await?;
+        source
+            .update(Cube::new(header.clone(), json!({ "name": "second" })))
+            .await?;
+
+        let bytes = export_to_bytes(&source, std::slice::from_ref(&definition)).await?;
+        let archive = read(&bytes)?;
+        assert_eq!(archive.manifest.closet, "test");
+        assert_eq!(archive.definitions.len(), 1);
+        // The cube and its two revisions
+        assert_eq!(archive.cubes.len(), 3);
+        assert_eq!(archive.manifest.files.len(), 4);
+
+        let target = SqliteClosetProvider::open_in_memory()?;
+        let report = import(&target, archive).await?;
+        assert_eq!(
+            report,
+            ImportReport {
+                cubes: 3,
+                definitions: 1
+            }
+        );
+        let imported: Cube<Value> = target.read(header.clone()).await?;
+        assert_eq!(imported.content, Some(json!({ "name": "second" })));
+        assert_eq!(history::revisions(&target, &header).await?.len(), 2);
+
+        let result = import(&target, read(&bytes)?).await;
+        let Err(LuggageError::ImportCollision(collisions)) = result else {
+            panic!("importing twice should collide");
+        };
+        assert_eq!(collisions.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_keeps_cubes_of_deleted_definitions() -> Result<()> {
+        let definition = test_definition();
+        let definition_header = CubeHeader {
+            id: Uuid::try_parse(&definition.id).map_err(|_| LuggageError::Unknown)?,
+            definition: CubeDefinition::id(),
+        };
+        let source = SqliteClosetProvider::open_in_memory()?;
+        source
+            .create(Cube::new(definition_header.clone(), definition))
+            .await?;
+        let header = source
+            .create(Cube::new(
+                CubeHeader::new(definition_header.id),
+                json!({ "name": "orphan" }),
+            ))
+            .await?;
+        source.delete(definition_header).await?;
+
+        let archive = read(&export_to_bytes(&source, &[]).await?)?;
+        assert!(archive.definitions.is_empty());
+        assert!(archive.cubes.iter().any(|cube| {
+            cube.header.id == header.id && cube.content == Some(json!({ "name": "orphan" }))
+        }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tampered_archive_is_rejected() -> Result<()> {
+        let source = SqliteClosetProvider::open_in_memory()?;
+        source
+            .create(Cube::new(
+                CubeHeader::new(Uuid::now_v7()),
+                json!({ "name": "original" }),
+            ))
+            .await?;
+        let bytes = export_to_bytes(&source, &[]).await?;
+
+        let original = b"\"original\"";
+        let position = bytes
+            .windows(original.len())
+            .position(|window| window == original)
+            .ok_or(LuggageError::NotFound)?;
+        let mut tampered = bytes.clone();
+        tampered[position + 1] = b'O';
+        assert!(matches!(
+            read(&tampered),
+            Err(LuggageError::InvalidArchive(_))
+        ));
+        assert!(matches!(
+            read(b"not an archive"),
+            Err(LuggageError::InvalidArchive(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,32 +1,73 @@
+use std::path::PathBuf;
+
+use futures::future::BoxFuture;
 use schemars::{JsonSchema, schema_for};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::{
+    closet::{
+        history,
+        query::{CubePage, CubeQuery},
+    },
     core::core::LuggageId,
     cube::cube::{Cube, CubeHeader, CubeRegistration, CubeSchema},
     error::LuggageError,
 };
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema, Default)]
+#[derive(
+    Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema, Default, utoipa::ToSchema,
+)]
 pub enum ClosetBuiltinType {
     #[default]
     LocalSurrealDb,
     RemoteSurrealDb,
+    LocalFileSystem,
+    Sqlite,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema, Default)]
+#[derive(
+    Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema, Default, utoipa::ToSchema,
+)]
 pub enum ClosetExecutionType {
     #[default]
     Builtin,
     Plugin,
 }
 
+/// Names of the environment variables holding a closet's credentials, so secrets stay out of
+/// the configuration file.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, JsonSchema)]
+pub struct ClosetCredentials {
+    pub username_env: String,
+    pub password_env: String,
+}
+
+/// Where a closet's data lives, for builtin types that aren't in memory.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, JsonSchema, Default)]
+#[serde(default)]
+pub struct ClosetConnection {
+    /// Address of the server, e.g. `127.0.0.1:8000`
+    pub url: Option<String>,
+    pub namespace: Option<String>,
+    pub database: Option<String>,
+    /// Credentials to sign in with, or `None` to connect anonymously
+    pub credentials: Option<ClosetCredentials>,
+    /// Folder of a file system closet, or file of a SQLite closet
+    pub path: Option<PathBuf>,
+    /// Whether a file system closet writes indented JSON
+    pub pretty: bool,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug, JsonSchema)]
 pub struct Closet {
     pub name: String,
     pub execution_type: ClosetExecutionType,
     pub builtin_type: Option<ClosetBuiltinType>,
+    #[serde(default)]
+    pub connection: Option<ClosetConnection>,
 }
 
 impl Default for Closet {
@@ -35,10 +76,21 @@ impl Default for Closet {
             name: "default-luggage-closet".into(),
             execution_type: Default::default(),
             builtin_type: Default::default(),
+            connection: Default::default(),
         }
     }
 }
 
+impl Closet {
+    /// ID of the closet, derived from its name so it stays the same across restarts and between
+    /// Bellhop and the command line.
+    pub fn closet_id(&self) -> LuggageId {
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&Sha256::digest(self.name.as_bytes())[..16]);
+        uuid::Builder::from_custom_bytes(bytes).into_uuid()
+    }
+}
+
 impl CubeRegistration for Closet {
     fn id() -> LuggageId {
         Uuid::try_parse("0194f284-68d6-7072-805f-878ac5e94c7e").unwrap_or_default()
@@ -48,6 +100,56 @@ impl CubeRegistration for Closet {
     }
 }
 
+/// Object-safe storage for cubes, working on JSON content.
+///
+/// Providers implement this trait, and get the typed [`ClosetCreator`], [`ClosetReader`],
+/// [`ClosetUpdater`], [`ClosetDeleter`] and [`ClosetQuerier`] helpers for free. Bellhop keeps
+/// providers as `Arc<dyn ClosetProvider>` so closets backed by different storage can share one
+/// registry.
+///
+/// These methods only touch the current content. The typed creator and updater also record a
+/// [`history::CubeRevision`] for every write.
+pub trait ClosetProvider: Send + Sync {
+    /// Store a new cube.
+    fn create_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>>;
+
+    /// Read a cube, with `None` content when it does not exist.
+    fn read_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<Cube<Value>, LuggageError>>;
+
+    /// Replace the content of an existing cube.
+    fn update_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>>;
+
+    /// Delete a cube.
+    fn delete_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<(), LuggageError>>;
+
+    /// List a page of the cubes of one definition.
+    ///
+    /// Providers that can't filter in storage can run the query with [`crate::closet::query::apply`].
+    fn query_values(
+        &self,
+        definition: LuggageId,
+        query: CubeQuery,
+    ) -> BoxFuture<'_, Result<CubePage<Value>, LuggageError>>;
+
+    /// List the definitions that have cubes stored in the closet, in no particular order.
+    fn definitions(&self) -> BoxFuture<'_, Result<Vec<LuggageId>, LuggageError>>;
+
+    /// Check that the closet's storage is reachable.
+    fn health(&self) -> BoxFuture<'_, Result<(), LuggageError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn to_value_cube<T>(cube: Cube<T>) -> Result<Cube<Value>, LuggageError>
+where
+    T: Serialize,
+{
+    Ok(Cube {
+        header: cube.header,
+        content: cube.content.map(serde_json::to_value).transpose()?,
+    })
+}
+
 pub trait ClosetCreator {
     fn create<T>(
         &self,
@@ -81,3 +183,97 @@ pub trait ClosetDeleter {
         header: CubeHeader,
     ) -> impl std::future::Future<Output = Result<(), LuggageError>> + Send;
 }
+
+pub trait ClosetQuerier {
+    fn query<T>(
+        &self,
+        definition: LuggageId,
+        query: CubeQuery,
+    ) -> impl std::future::Future<Output = Result<CubePage<T>, LuggageError>> + Send
+    where
+        T: for<'a> Deserialize<'a> + Send;
+}
+
+impl<P> ClosetCreator for P
+where
+    P: ClosetProvider + ?Sized,
+{
+    async fn create<T>(&self, cube: Cube<T>) -> Result<CubeHeader, LuggageError>
+    where
+        T: Serialize + Send + 'static,
+    {
+        Ok(history::create(self, to_value_cube(cube)?, None)
+            .await?
+            .cube)
+    }
+}
+
+impl<P> ClosetReader for P
+where
+    P: ClosetProvider + ?Sized,
+{
+    async fn read<T>(&self, header: CubeHeader) -> Result<Cube<T>, LuggageError>
+    where
+        T: for<'a> Deserialize<'a> + Send,
+    {
+        let cube = self.read_value(header).await?;
+        Ok(Cube {
+            header: cube.header,
+            content: cube.content.map(serde_json::from_value).transpose()?,
+        })
+    }
+}
+
+impl<P> ClosetUpdater for P
+where
+    P: ClosetProvider + ?Sized,
+{
+    async fn update<T>(&self, cube: Cube<T>) -> Result<CubeHeader, LuggageError>
+    where
+        T: Serialize + Send + 'static,
+    {
+        Ok(history::update(self, to_value_cube(cube)?, None, None)
+            .await?
+            .cube)
+    }
+}
+
+impl<P> ClosetDeleter for P
+where
+    P: ClosetProvider + ?Sized,
+{
+    async fn delete(&self, header: CubeHeader) -> Result<(), LuggageError> {
+        self.delete_value(header).await
+    }
+}
+
+impl<P> ClosetQuerier for P
+where
+    P: ClosetProvider + ?Sized,
+{
+    async fn query<T>(
+        &self,
+        definition: LuggageId,
+        query: CubeQuery,
+    ) -> Result<CubePage<T>, LuggageError>
+    where
+        T: for<'a> Deserialize<'a> + Send,
+    {
+        let page = self.query_values(definition, query).await?;
+        let cubes = page
+            .cubes
+            .into_iter()
+            .map(|cube| {
+                Ok(Cube {
+                    header: cube.header,
+                    content: cube.content.map(serde_json::from_value).transpose()?,
+                })
+            })
+            .collect::<Result<_, LuggageError>>()?;
+        Ok(CubePage {
+            cubes,
+            count: page.count,
+            next: page.next,
+        })
+    }
+}
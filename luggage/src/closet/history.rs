@@ -0,0 +1,446 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex as StdMutex, PoisonError, Weak},
+};
+
+use chrono::{DateTime, Utc};
+use schemars::{JsonSchema, schema_for};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use uuid::Uuid;
+
+use crate::{
+    closet::closet::ClosetProvider,
+    core::core::LuggageId,
+    cube::cube::{Cube, CubeHeader, CubeRegistration, CubeSchema},
+    error::LuggageError,
+};
+
+/// One immutable state of a cube's content.
+///
+/// Every create and update stores a revision as a cube of its own, next to the cube in the same
+/// closet, so any provider keeps history without changes to its storage. Revisions are numbered
+/// from 1 without gaps, stored under IDs derived from the cube and the number, and survive
+/// deleting the cube.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, utoipa::ToSchema)]
+pub struct CubeRevision {
+    pub cube: CubeHeader,
+    pub revision: u64,
+    #[schemars(with = "String")]
+    #[schema(value_type = String)]
+    pub timestamp: DateTime<Utc>,
+    #[schema(value_type = Object)]
+    pub content: Value,
+    /// Version of the cube's definition the content was written under, or `None` when the writer
+    /// didn't know it, which reads as the initial version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition_version: Option<String>,
+}
+
+impl CubeRegistration for CubeRevision {
+    fn id() -> LuggageId {
+        Uuid::try_parse("019a1e4c-2b7d-7f31-9c55-6d0b8e2f4a19").unwrap_or_default()
+    }
+    fn schema() -> CubeSchema {
+        serde_json::to_string_pretty(&schema_for!(CubeRevision)).unwrap_or_default()
+    }
+}
+
+/// Header of a cube's revision, with an ID derived from the cube and the revision number, so each
+/// revision is found with a single read.
+fn revision_header(header: &CubeHeader, revision: u64) -> CubeHeader {
+    let digest = Sha256::new()
+        .chain_update(header.definition.as_bytes())
+        .chain_update(header.id.as_bytes())
+        .chain_update(revision.to_be_bytes())
+        .finalize();
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    CubeHeader {
+        id: uuid::Builder::from_custom_bytes(bytes).into_uuid(),
+        definition: CubeRevision::id(),
+    }
+}
+
+async fn read_revision<P>(
+    provider: &P,
+    header: &CubeHeader,
+    revision: u64,
+) -> Result<Option<CubeRevision>, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let cube = provider
+        .read_value(revision_header(header, revision))
+        .await?;
+    Ok(cube.content.map(serde_json::from_value).transpose()?)
+}
+
+/// The newest revision of a cube, or `None` before it was first stored.
+pub async fn latest<P>(
+    provider: &P,
+    header: &CubeHeader,
+) -> Result<Option<CubeRevision>, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let Some(mut found) = read_revision(provider, header, 1).await? else {
+        return Ok(None);
+    };
+    // Revisions are numbered without gaps, so double the number until one is missing and search
+    // between the last two, which takes a logarithmic number of reads
+    let mut missing = 2;
+    while let Some(revision) = read_revision(provider, header, missing).await? {
+        found = revision;
+        missing *= 2;
+    }
+    while missing - found.revision > 1 {
+        let middle = found.revision + (missing - found.revision) / 2;
+        match read_revision(provider, header, middle).await? {
+            Some(revision) => found = revision,
+            None => missing = middle,
+        }
+    }
+    Ok(Some(found))
+}
+
+/// One revision of a cube, or [`LuggageError::NotFound`].
+pub async fn revision<P>(
+    provider: &P,
+    header: &CubeHeader,
+    revision: u64,
+) -> Result<CubeRevision, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    read_revision(provider, header, revision)
+        .await?
+        .ok_or(LuggageError::NotFound)
+}
+
+/// Every revision of a cube, oldest first.
+pub async fn revisions<P>(
+    provider: &P,
+    header: &CubeHeader,
+) -> Result<Vec<CubeRevision>, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let mut revisions = Vec::new();
+    while let Some(revision) = read_revision(provider, header, revisions.len() as u64 + 1).await? {
+        revisions.push(revision);
+    }
+    Ok(revisions)
+}
+
+/// Writers waiting on or holding each cube, by definition and ID.
+type Writers = HashMap<(LuggageId, LuggageId), Weak<Mutex<()>>>;
+
+static WRITERS: LazyLock<StdMutex<Writers>> = LazyLock::new(Default::default);
+
+/// Wait until no other write to the cube is running in this process, so checking its current
+/// revision and recording the next one can't interleave with another writer.
+async fn lock(header: &CubeHeader) -> OwnedMutexGuard<()> {
+    let writer = {
+        let mut writers = WRITERS.lock().unwrap_or_else(PoisonError::into_inner);
+        // Cubes nobody is writing anymore
+        writers.retain(|_, writer| writer.strong_count() > 0);
+        let key = (header.definition, header.id);
+        match writers.get(&key).and_then(Weak::upgrade) {
+            Some(writer) => writer,
+            None => {
+                let writer = Arc::new(Mutex::new(()));
+                writers.insert(key, Arc::downgrade(&writer));
+                writer
+            }
+        }
+    };
+    writer.lock_owned().await
+}
+
+/// Fail with [`LuggageError::RevisionMismatch`] unless the cube is at the `expected` revision.
+fn check_revision(expected: Option<u64>, current: u64) -> Result<(), LuggageError> {
+    match expected {
+        Some(expected) if expected != current => {
+            Err(LuggageError::RevisionMismatch { expected, current })
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn record<P>(
+    provider: &P,
+    header: CubeHeader,
+    revision: u64,
+    content: Value,
+    definition_version: Option<String>,
+) -> Result<CubeRevision, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let revision = CubeRevision {
+        cube: header,
+        revision,
+        timestamp: Utc::now(),
+        content,
+        definition_version,
+    };
+    provider
+        .create_value(Cube::new(
+            revision_header(&revision.cube, revision.revision),
+            serde_json::to_value(&revision)?,
+        ))
+        .await?;
+    Ok(revision)
+}
+
+/// Store a new cube and its first revision, or the next one when a cube with the same header was
+/// deleted.
+pub async fn create<P>(
+    provider: &P,
+    cube: Cube<Value>,
+    definition_version: Option<String>,
+) -> Result<CubeRevision, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let _writing = lock(&cube.header).await;
+    create_locked(provider, cube, definition_version).await
+}
+
+async fn create_locked<P>(
+    provider: &P,
+    cube: Cube<Value>,
+    definition_version: Option<String>,
+) -> Result<CubeRevision, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let current = latest(provider, &cube.header)
+        .await?
+        .map_or(0, |r| r.revision);
+    let header = provider.create_value(cube.clone()).await?;
+    record(
+        provider,
+        header,
+        current + 1,
+        cube.content.unwrap_or_default(),
+        definition_version,
+    )
+    .await
+}
+
+/// Replace a cube's content, recording a new revision.
+///
+/// With `expected`, the update only goes through when the cube is still at that revision. Writes
+/// to one cube wait for each other within this process, so two writers can't both get past the
+/// check.
+pub async fn update<P>(
+    provider: &P,
+    cube: Cube<Value>,
+    expected: Option<u64>,
+    definition_version: Option<String>,
+) -> Result<CubeRevision, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let _writing = lock(&cube.header).await;
+    update_locked(provider, cube, expected, definition_version).await
+}
+
+async fn update_locked<P>(
+    provider: &P,
+    cube: Cube<Value>,
+    expected: Option<u64>,
+    definition_version: Option<String>,
+) -> Result<CubeRevision, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let current = latest(provider, &cube.header)
+        .await?
+        .map_or(0, |r| r.revision);
+    check_revision(expected, current)?;
+    let header = provider.update_value(cube.clone()).await?;
+    record(
+        provider,
+        header,
+        current + 1,
+        cube.content.unwrap_or_default(),
+        definition_version,
+    )
+    .await
+}
+
+/// Make an old revision's content current again as a new revision, recreating the cube if it was
+/// deleted. The new revision keeps the restored revision's definition version.
+pub async fn restore<P>(
+    provider: &P,
+    restored: CubeRevision,
+    expected: Option<u64>,
+) -> Result<CubeRevision, LuggageError>
+where
+    P: ClosetProvider + ?Sized,
+{
+    let _writing = lock(&restored.cube).await;
+    let cube = Cube::new(restored.cube.clone(), restored.content);
+    let definition_version = restored.definition_version;
+    if provider.read_value(restored.cube).await?.content.is_some() {
+        update_locked(provider, cube, expected, definition_version).await
+    } else {
+        let current = latest(provider, &cube.header)
+            .await?
+            .map_or(0, |r| r.revision);
+        check_revision(expected, current)?;
+        create_locked(provider, cube, definition_version).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+    use serde_json::json;
+
+    use crate::{
+        closet::{
+            closet::{ClosetCreator, ClosetDeleter, ClosetReader, ClosetUpdater},
+            providers::sqlite::SqliteClosetProvider,
+        },
+        error::Result,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn updates_keep_every_revision() -> Result<()> {
+        let closet = SqliteClosetProvider::open_in_memory()?;
+        let header = closet
+            .create(Cube::new(
+                CubeHeader::new(Uuid::now_v7()),
+                json!({ "n": 1 }),
+            ))
+            .await?;
+        closet
+            .update(Cube::new(header.clone(), json!({ "n": 2 })))
+            .await?;
+        let third = update(
+            &closet,
+            Cube::new(header.clone(), json!({ "n": 3 })),
+            Some(2),
+            Some("2.0.0".into()),
+        )
+        .await?;
+        assert_eq!(third.revision, 3);
+        assert_eq!(
+            latest(&closet, &header)
+                .await?
+                .and_then(|r| r.definition_version),
+            Some("2.0.0".into())
+        );
+
+        let history = revisions(&closet, &header).await?;
+        let numbers: Vec<u64> = history.iter().map(|r| r.revision).collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+        assert!(history[0].timestamp <= history[2].timestamp);
+        assert_eq!(
+            revision(&closet, &header, 1).await?.content,
+            json!({ "n": 1 })
+        );
+        assert!(matches!(
+            revision(&closet, &header, 4).await,
+            Err(LuggageError::NotFound)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stale_revision_conflicts() -> Result<()> {
+        let closet = SqliteClosetProvider::open_in_memory()?;
+        let created = create(
+            &closet,
+            Cube::new(CubeHeader::new(Uuid::now_v7()), json!("first")),
+            None,
+        )
+        .await?;
+        update(
+            &closet,
+            Cube::new(created.cube.clone(), json!("second")),
+            Some(1),
+            None,
+        )
+        .await?;
+
+        let result = update(
+            &closet,
+            Cube::new(created.cube.clone(), json!("lost")),
+            Some(1),
+            None,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(LuggageError::RevisionMismatch {
+                expected: 1,
+                current: 2
+            })
+        ));
+        let cube: Cube<String> = closet.read(created.cube).await?;
+        assert_eq!(cube.content.as_deref(), Some("second"));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn concurrent_updates_record_each_revision_once() -> Result<()> {
+        let closet = SqliteClosetProvider::open_in_memory()?;
+        let created = create(
+            &closet,
+            Cube::new(CubeHeader::new(Uuid::now_v7()), json!(0)),
+            None,
+        )
+        .await?;
+
+        let results = join_all((1..=8).map(|n| {
+            update(
+                &closet,
+                Cube::new(created.cube.clone(), json!(n)),
+                Some(1),
+                None,
+            )
+        }))
+        .await;
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results.iter().all(|result| matches!(
+            result,
+            Ok(_)
+                | Err(LuggageError::RevisionMismatch {
+                    expected: 1,
+                    current: 2
+                })
+        )));
+        let numbers: Vec<u64> = revisions(&closet, &created.cube)
+            .await?
+            .iter()
+            .map(|r| r.revision)
+            .collect();
+        assert_eq!(numbers, vec![1, 2]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restore_recreates_deleted_cube() -> Result<()> {
+        let closet = SqliteClosetProvider::open_in_memory()?;
+        let header = closet
+            .create(Cube::new(CubeHeader::new(Uuid::now_v7()), json!("kept")))
+            .await?;
+        closet.delete(header.clone()).await?;
+
+        let first = revision(&closet, &header, 1).await?;
+        let restored = restore(&closet, first, None).await?;
+        assert_eq!(restored.revision, 2);
+        let cube: Cube<String> = closet.read(header).await?;
+        assert_eq!(cube.content.as_deref(), Some("kept"));
+        Ok(())
+    }
+}
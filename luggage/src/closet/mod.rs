@@ -1,4 +1,7 @@
 #![allow(clippy::module_inception)]
 
+pub mod archive;
 pub mod closet;
+pub mod history;
 pub mod providers;
+pub mod query;
@@ -0,0 +1,377 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use futures::future::BoxFuture;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    closet::{
+        closet::{ClosetConnection, ClosetProvider},
+        query::{self, CubePage, CubeQuery},
+    },
+    core::core::LuggageId,
+    cube::cube::{Cube, CubeHeader},
+    error::LuggageError,
+};
+
+/// Stores each cube as `<root>/<definition>/<id>.json`, with its header embedded, so a closet can
+/// be synced with any file sync tool.
+#[derive(Clone)]
+pub struct FileSystemClosetProvider {
+    root: PathBuf,
+    /// Write indented JSON with sorted keys, which diffs well in git
+    pretty: bool,
+}
+
+impl FileSystemClosetProvider {
+    pub fn new(root: impl Into<PathBuf>, pretty: bool) -> Result<Self, LuggageError> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root, pretty })
+    }
+
+    /// Open the folder described by a closet's connection settings.
+    pub fn connect(connection: &ClosetConnection) -> Result<Self, LuggageError> {
+        let root = connection.path.clone().ok_or_else(|| {
+            LuggageError::ClosetConfiguration("file system closets need a path".into())
+        })?;
+        Self::new(root, connection.pretty)
+    }
+
+    fn cube_path(&self, header: &CubeHeader) -> PathBuf {
+        self.root
+            .join(header.definition.to_string())
+            .join(format!("{}.json", header.id))
+    }
+
+    fn write(&self, cube: &Cube<Value>) -> Result<(), LuggageError> {
+        let json = if self.pretty {
+            // serde_json maps are sorted, so pretty files are stable between writes
+            serde_json::to_string_pretty(cube)? + "\n"
+        } else {
+            serde_json::to_string(cube)?
+        };
+        write_atomically(&self.cube_path(&cube.header), json.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Write to a temporary file next to `path` and rename it into place, so readers and sync tools
+/// never see a partially written cube.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(directory)?;
+    let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or("cube");
+    let temporary_path = directory.join(format!(".{}.{}.tmp", file_name, Uuid::now_v7()));
+    fs::write(&temporary_path, contents)?;
+    fs::rename(&temporary_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary_path);
+    })
+}
+
+/// Run blocking file system work off the async runtime.
+async fn blocking<T, F>(work: F) -> Result<T, LuggageError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, LuggageError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|_| LuggageError::Unknown)?
+}
+
+impl ClosetProvider for FileSystemClosetProvider {
+    fn create_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>> {
+        let closet = self.clone();
+        Box::pin(blocking(move || {
+            if closet.cube_path(&cube.header).exists() {
+                return Err(LuggageError::Conflict);
+            }
+            closet.write(&cube)?;
+            Ok(cube.header)
+        }))
+    }
+
+    fn read_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<Cube<Value>, LuggageError>> {
+        let closet = self.clone();
+        Box::pin(blocking(move || {
+            let json = match fs::read_to_string(closet.cube_path(&header)) {
+                Ok(json) => json,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return Ok(Cube {
+                        header,
+                        content: None,
+                    });
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let saved: Cube<Value> = serde_json::from_str(&json)?;
+            Ok(Cube {
+                header,
+                content: saved.content,
+            })
+        }))
+    }
+
+    fn update_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>> {
+        let closet = self.clone();
+        Box::pin(blocking(move || {
+            if !closet.cube_path(&cube.header).exists() {
+                return Err(LuggageError::NotFound);
+            }
+            closet.write(&cube)?;
+            Ok(cube.header)
+        }))
+    }
+
+    fn delete_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<(), LuggageError>> {
+        let closet = self.clone();
+        Box::pin(blocking(move || {
+            match fs::remove_file(closet.cube_path(&header)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        }))
+    }
+
+    fn query_values(
+        &self,
+        definition: LuggageId,
+        query: CubeQuery,
+    ) -> BoxFuture<'_, Result<CubePage<Value>, LuggageError>> {
+        let directory = self.root.join(definition.to_string());
+        Box::pin(blocking(move || {
+            let entries = match fs::read_dir(&directory) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return query::apply(&query, vec![]);
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let mut cubes = Vec::new();
+            for entry in entries {
+                let path = entry?.path();
+                // Skip temporary files of writes in progress and anything else synced in
+                let is_cube = path
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+                    && path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .is_some_and(|stem| Uuid::try_parse(stem).is_ok());
+                if is_cube {
+                    let cube: Cube<Value> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+                    cubes.push(cube);
+                }
+            }
+            query::apply(&query, cubes)
+        }))
+    }
+
+    fn definitions(&self) -> BoxFuture<'_, Result<Vec<LuggageId>, LuggageError>> {
+        let root = self.root.clone();
+        Box::pin(blocking(move || {
+            let mut definitions = Vec::new();
+            for entry in fs::read_dir(&root)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                // Skip other folders synced into the closet
+                if let Some(definition) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| Uuid::try_parse(name).ok())
+                {
+                    definitions.push(definition);
+                }
+            }
+            Ok(definitions)
+        }))
+    }
+
+    fn health(&self) -> BoxFuture<'_, Result<(), LuggageError>> {
+        let root = self.root.clone();
+        Box::pin(blocking(move || {
+            if fs::metadata(&root)?.is_dir() {
+                Ok(())
+            } else {
+                Err(LuggageError::ClosetConfiguration(format!(
+                    "{} is not a directory",
+                    root.display()
+                )))
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        closet::closet::{
+            ClosetCreator, ClosetDeleter, ClosetQuerier, ClosetReader, ClosetUpdater,
+        },
+        closet::{history::CubeRevision, query::CubeSort},
+        cube::cube::CubeRegistration,
+        error::Result,
+    };
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct TestContent {
+        name: String,
+    }
+
+    fn test_root(test_name: &str) -> PathBuf {
+        env::temp_dir().join(format!("luggage-{}-{}", test_name, Uuid::now_v7()))
+    }
+
+    #[tokio::test]
+    async fn create_then_read_test_content() -> Result<()> {
+        let root = test_root("create_then_read_test_content");
+        let closet = FileSystemClosetProvider::new(&root, false)?;
+        let test_cube = Cube {
+            header: CubeHeader::new(Uuid::now_v7()),
+            content: Some(TestContent {
+                name: "test".into(),
+            }),
+        };
+        let saved_header = closet.create(test_cube.clone()).await?;
+
+        let path = root
+            .join(saved_header.definition.to_string())
+            .join(format!("{}.json", saved_header.id));
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        assert_eq!(saved["header"]["id"], saved_header.id.to_string());
+
+        let saved_cube: Cube<TestContent> = closet.read(saved_header.clone()).await?;
+        assert_eq!(&test_cube.content, &saved_cube.content);
+        assert!(matches!(
+            closet.create(test_cube).await,
+            Err(LuggageError::Conflict)
+        ));
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_then_delete_test_content() -> Result<()> {
+        let root = test_root("update_then_delete_test_content");
+        let closet = FileSystemClosetProvider::new(&root, true)?;
+        let test_cube = Cube {
+            header: CubeHeader::new(Uuid::now_v7()),
+            content: Some(TestContent {
+                name: "original".into(),
+            }),
+        };
+        let saved_header = closet.create(test_cube).await?;
+
+        let updated_cube = Cube {
+            header: saved_header.clone(),
+            content: Some(TestContent {
+                name: "updated".into(),
+            }),
+        };
+        closet.update(updated_cube.clone()).await?;
+        let read_cube: Cube<TestContent> = closet.read(saved_header.clone()).await?;
+        assert_eq!(&updated_cube.content, &read_cube.content);
+
+        // Only the cube itself is left behind, without temporary files
+        let definition_directory = root.join(saved_header.definition.to_string());
+        assert_eq!(fs::read_dir(&definition_directory)?.count(), 1);
+
+        closet.delete(saved_header.clone()).await?;
+        let read_cube: Cube<TestContent> = closet.read(saved_header.clone()).await?;
+        assert_eq!(None, read_cube.content);
+        assert!(matches!(
+            closet.update(updated_cube).await,
+            Err(LuggageError::NotFound)
+        ));
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_pages_through_sorted_cubes() -> Result<()> {
+        let root = test_root("query_pages_through_sorted_cubes");
+        let closet = FileSystemClosetProvider::new(&root, false)?;
+        let definition = Uuid::now_v7();
+        for name in ["b", "c", "a"] {
+            closet
+                .create(Cube::new(
+                    CubeHeader::new(definition),
+                    TestContent { name: name.into() },
+                ))
+                .await?;
+        }
+
+        let query = CubeQuery {
+            sort: Some(CubeSort {
+                field: "name".into(),
+                descending: false,
+            }),
+            limit: 2,
+            ..Default::default()
+        };
+        let first: CubePage<TestContent> = closet.query(definition, query.clone()).await?;
+        let second: CubePage<TestContent> = closet
+            .query(
+                definition,
+                CubeQuery {
+                    after: first.next,
+                    ..query
+                },
+            )
+            .await?;
+        let names: Vec<String> = first
+            .cubes
+            .into_iter()
+            .chain(second.cubes)
+            .filter_map(|cube| cube.content.map(|content| content.name))
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(second.count, 3);
+        assert_eq!(second.next, None);
+
+        let empty: CubePage<TestContent> =
+            closet.query(Uuid::now_v7(), CubeQuery::default()).await?;
+        assert_eq!(empty.count, 0);
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn definitions_are_the_cube_folders() -> Result<()> {
+        let root = test_root("definitions_are_the_cube_folders");
+        let closet = FileSystemClosetProvider::new(&root, false)?;
+        let header = closet
+            .create(Cube::new(
+                CubeHeader::new(Uuid::now_v7()),
+                TestContent {
+                    name: "test".into(),
+                },
+            ))
+            .await?;
+        fs::create_dir(root.join(".git"))?;
+
+        let mut definitions = closet.definitions().await?;
+        definitions.sort();
+        let mut expected = vec![header.definition, CubeRevision::id()];
+        expected.sort();
+        assert_eq!(definitions, expected);
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+}
@@ -1 +1,3 @@
+pub mod filesystem;
+pub mod sqlite;
 pub mod surrealdb;
@@ -0,0 +1,339 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use futures::future::BoxFuture;
+use rusqlite::{Connection, ErrorCode, OptionalExtension, params};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    closet::{
+        closet::{ClosetConnection, ClosetProvider},
+        query::{self, CubePage, CubeQuery},
+    },
+    core::core::LuggageId,
+    cube::cube::{Cube, CubeHeader},
+    error::LuggageError,
+};
+
+/// Migrations of the provider's own schema, applied in order and tracked with `user_version`.
+///
+/// Never edit a released migration, append a new one instead.
+const MIGRATIONS: &[&str] = &["CREATE TABLE cubes (
+        definition TEXT NOT NULL,
+        id TEXT NOT NULL,
+        content TEXT,
+        PRIMARY KEY (definition, id)
+    );"];
+
+impl From<rusqlite::Error> for LuggageError {
+    fn from(_value: rusqlite::Error) -> Self {
+        LuggageError::Unknown
+    }
+}
+
+/// Stores cubes in a single SQLite file, with one row per cube and its content as JSON.
+#[derive(Clone)]
+pub struct SqliteClosetProvider {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteClosetProvider {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LuggageError> {
+        Self::migrate(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, LuggageError> {
+        Self::migrate(Connection::open_in_memory()?)
+    }
+
+    /// Open the file described by a closet's connection settings.
+    pub fn connect(connection: &ClosetConnection) -> Result<Self, LuggageError> {
+        let path = connection.path.as_ref().ok_or_else(|| {
+            LuggageError::ClosetConfiguration("SQLite closets need a path".into())
+        })?;
+        Self::open(path)
+    }
+
+    fn migrate(mut connection: Connection) -> Result<Self, LuggageError> {
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > MIGRATIONS.len() {
+            return Err(LuggageError::ClosetConfiguration(format!(
+                "SQLite closet schema version {} is newer than this luggage supports",
+                version
+            )));
+        }
+
+        let transaction = connection.transaction()?;
+        for migration in &MIGRATIONS[version..] {
+            transaction.execute_batch(migration)?;
+        }
+        transaction.pragma_update(None, "user_version", MIGRATIONS.len())?;
+        transaction.commit()?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Run blocking database work off the async runtime.
+    fn with_connection<T, F>(&self, work: F) -> BoxFuture<'static, Result<T, LuggageError>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, LuggageError> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let connection = connection.lock().map_err(|_| LuggageError::Unknown)?;
+                work(&connection)
+            })
+            .await
+            .map_err(|_| LuggageError::Unknown)?
+        })
+    }
+}
+
+fn content_json(content: &Option<Value>) -> Result<Option<String>, LuggageError> {
+    Ok(content.as_ref().map(serde_json::to_string).transpose()?)
+}
+
+impl ClosetProvider for SqliteClosetProvider {
+    fn create_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>> {
+        self.with_connection(move |connection| {
+            let inserted = connection.execute(
+                "INSERT INTO cubes (definition, id, content) VALUES (?1, ?2, ?3)",
+                params![
+                    cube.header.definition.to_string(),
+                    cube.header.id.to_string(),
+                    content_json(&cube.content)?
+                ],
+            );
+            match inserted {
+                Ok(_) => Ok(cube.header),
+                Err(rusqlite::Error::SqliteFailure(e, _))
+                    if e.code == ErrorCode::ConstraintViolation =>
+                {
+                    Err(LuggageError::Conflict)
+                }
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    fn read_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<Cube<Value>, LuggageError>> {
+        self.with_connection(move |connection| {
+            let content: Option<Option<String>> = connection
+                .query_row(
+                    "SELECT content FROM cubes WHERE definition = ?1 AND id = ?2",
+                    params![header.definition.to_string(), header.id.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let content = content
+                .flatten()
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?;
+            Ok(Cube { header, content })
+        })
+    }
+
+    fn update_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>> {
+        self.with_connection(move |connection| {
+            let updated = connection.execute(
+                "UPDATE cubes SET content = ?3 WHERE definition = ?1 AND id = ?2",
+                params![
+                    cube.header.definition.to_string(),
+                    cube.header.id.to_string(),
+                    content_json(&cube.content)?
+                ],
+            )?;
+            match updated {
+                0 => Err(LuggageError::NotFound),
+                _ => Ok(cube.header),
+            }
+        })
+    }
+
+    fn delete_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<(), LuggageError>> {
+        self.with_connection(move |connection| {
+            connection.execute(
+                "DELETE FROM cubes WHERE definition = ?1 AND id = ?2",
+                params![header.definition.to_string(), header.id.to_string()],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn query_values(
+        &self,
+        definition: LuggageId,
+        query: CubeQuery,
+    ) -> BoxFuture<'_, Result<CubePage<Value>, LuggageError>> {
+        self.with_connection(move |connection| {
+            let mut statement =
+                connection.prepare("SELECT id, content FROM cubes WHERE definition = ?1")?;
+            let rows = statement.query_map(params![definition.to_string()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })?;
+            let mut cubes = Vec::new();
+            for row in rows {
+                let (id, content) = row?;
+                let id = Uuid::try_parse(&id).map_err(|_| LuggageError::Unknown)?;
+                cubes.push(Cube {
+                    header: CubeHeader { id, definition },
+                    content: content
+                        .map(|json| serde_json::from_str(&json))
+                        .transpose()?,
+                });
+            }
+            query::apply(&query, cubes)
+        })
+    }
+
+    fn definitions(&self) -> BoxFuture<'_, Result<Vec<LuggageId>, LuggageError>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare("SELECT DISTINCT definition FROM cubes")?;
+            let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+            let mut definitions = Vec::new();
+            for row in rows {
+                definitions.push(Uuid::try_parse(&row?).map_err(|_| LuggageError::Unknown)?);
+            }
+            Ok(definitions)
+        })
+    }
+
+    fn health(&self) -> BoxFuture<'_, Result<(), LuggageError>> {
+        self.with_connection(|connection| {
+            connection.query_row("SELECT 1", [], |_| Ok(()))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use serde::{Deserialize, Serialize};
+    use uuid::Uuid;
+
+    use crate::{
+        closet::closet::{
+            ClosetCreator, ClosetDeleter, ClosetQuerier, ClosetReader, ClosetUpdater,
+        },
+        closet::query::CubeFilter,
+        error::Result,
+    };
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct TestContent {
+        name: String,
+    }
+
+    #[tokio::test]
+    async fn create_update_then_delete_test_content() -> Result<()> {
+        let closet = SqliteClosetProvider::open_in_memory()?;
+        let test_cube = Cube {
+            header: CubeHeader::new(Uuid::now_v7()),
+            content: Some(TestContent {
+                name: "original".into(),
+            }),
+        };
+        let saved_header = closet.create(test_cube.clone()).await?;
+        let saved_cube: Cube<TestContent> = closet.read(saved_header.clone()).await?;
+        assert_eq!(&test_cube.content, &saved_cube.content);
+        assert!(matches!(
+            closet.create(test_cube).await,
+            Err(LuggageError::Conflict)
+        ));
+
+        let updated_cube = Cube {
+            header: saved_header.clone(),
+            content: Some(TestContent {
+                name: "updated".into(),
+            }),
+        };
+        closet.update(updated_cube.clone()).await?;
+        let read_cube: Cube<TestContent> = closet.read(saved_header.clone()).await?;
+        assert_eq!(&updated_cube.content, &read_cube.content);
+
+        closet.delete(saved_header.clone()).await?;
+        let read_cube: Cube<TestContent> = closet.read(saved_header).await?;
+        assert_eq!(None, read_cube.content);
+        assert!(matches!(
+            closet.update(updated_cube).await,
+            Err(LuggageError::NotFound)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reopening_keeps_cubes_and_schema_version() -> Result<()> {
+        let path = env::temp_dir().join(format!("luggage-{}.sqlite", Uuid::now_v7()));
+        let test_cube = Cube {
+            header: CubeHeader::new(Uuid::now_v7()),
+            content: Some(TestContent {
+                name: "persisted".into(),
+            }),
+        };
+        SqliteClosetProvider::open(&path)?
+            .create(test_cube.clone())
+            .await?;
+
+        let closet = SqliteClosetProvider::open(&path)?;
+        let saved_cube: Cube<TestContent> = closet.read(test_cube.header.clone()).await?;
+        assert_eq!(&test_cube.content, &saved_cube.content);
+
+        let version: usize =
+            Connection::open(&path)?.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(version, MIGRATIONS.len());
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_filters_cubes_of_one_definition() -> Result<()> {
+        let closet = SqliteClosetProvider::open_in_memory()?;
+        let definition = Uuid::now_v7();
+        for name in ["first", "second", "first"] {
+            closet
+                .create(Cube::new(
+                    CubeHeader::new(definition),
+                    TestContent { name: name.into() },
+                ))
+                .await?;
+        }
+        closet
+            .create(Cube::new(
+                CubeHeader::new(Uuid::now_v7()),
+                TestContent {
+                    name: "first".into(),
+                },
+            ))
+            .await?;
+
+        let page: CubePage<TestContent> = closet
+            .query(
+                definition,
+                CubeQuery {
+                    filters: vec![CubeFilter {
+                        field: "name".into(),
+                        value: "first".into(),
+                    }],
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(page.count, 2);
+        assert_eq!(page.cubes.len(), 2);
+        assert!(page.cubes[0].header.id < page.cubes[1].header.id);
+        assert_eq!(page.next, None);
+        Ok(())
+    }
+}
@@ -1,4 +1,8 @@
+use std::{collections::HashMap, env};
+
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use surrealdb::{
     Error, // TODO: Convert errors to luggage errors
     RecordId,
@@ -9,9 +13,14 @@ use surrealdb::{
     },
     opt::auth::Root,
 };
+use uuid::Uuid;
 
 use crate::{
-    closet::closet::{ClosetCreator, ClosetDeleter, ClosetReader, ClosetUpdater},
+    closet::{
+        closet::{ClosetConnection, ClosetProvider},
+        query::{self, CubePage, CubeQuery},
+    },
+    core::core::LuggageId,
     cube::cube::{Cube, CubeHeader},
     error::LuggageError,
 };
@@ -22,6 +31,18 @@ struct Record {
     id: RecordId,
 }
 
+/// Cube content as stored in SurrealDB.
+///
+/// Content is nested under its own field so that non-object content and content with its own `id`
+/// field do not clash with the record. The cube ID is repeated as a string so queries can page
+/// through cubes in ID order.
+#[derive(Serialize, Deserialize)]
+struct StoredCube {
+    #[serde(default)]
+    cube_id: String,
+    content: Option<Value>,
+}
+
 impl From<surrealdb::Error> for LuggageError {
     fn from(_value: surrealdb::Error) -> Self {
         LuggageError::Unknown
@@ -36,6 +57,10 @@ where
     db: Surreal<T>,
 }
 
+/// Namespace and database used when a closet's connection doesn't name them
+const DEFAULT_NAMESPACE: &str = "bellhop";
+const DEFAULT_DATABASE: &str = "bellhop";
+
 impl SurrealDbClosetProvider<Client> {
     pub async fn new<'a>(
         url: &'a str,
@@ -52,6 +77,61 @@ impl SurrealDbClosetProvider<Client> {
 
         Ok(Self { db })
     }
+
+    /// Connect to the server described by a closet's connection settings.
+    pub async fn connect(connection: &ClosetConnection) -> Result<Self, LuggageError> {
+        Self::connect_with_env(connection, |variable| env::var(variable).ok()).await
+    }
+
+    /// Connect like [`Self::connect`], looking up credential variables with `env` instead of
+    /// the process environment.
+    pub async fn connect_with_env(
+        connection: &ClosetConnection,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, LuggageError> {
+        let url = connection.url.as_deref().ok_or_else(|| {
+            LuggageError::ClosetConfiguration("remote SurrealDB closets need a url".into())
+        })?;
+        let db = Surreal::new::<Ws>(url).await?;
+
+        if let Some(credentials) = &connection.credentials {
+            let username = credential(&env, &credentials.username_env)?;
+            let password = credential(&env, &credentials.password_env)?;
+            db.signin(Root {
+                username: &username,
+                password: &password,
+            })
+            .await?;
+        }
+
+        db.use_ns(connection.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE))
+            .use_db(connection.database.as_deref().unwrap_or(DEFAULT_DATABASE))
+            .await?;
+        Ok(Self { db })
+    }
+}
+
+/// SurrealQL idiom of a dot separated content field, with array indexes as `[n]` and other
+/// segments escaped.
+fn content_idiom(field: &str) -> String {
+    field
+        .split('.')
+        .fold("content".to_string(), |idiom, segment| {
+            if segment.chars().all(|c| c.is_ascii_digit()) {
+                format!("{}[{}]", idiom, segment)
+            } else {
+                format!("{}.`{}`", idiom, segment)
+            }
+        })
+}
+
+fn credential(
+    env: impl Fn(&str) -> Option<String>,
+    variable: &str,
+) -> Result<String, LuggageError> {
+    env(variable).ok_or_else(|| {
+        LuggageError::ClosetConfiguration(format!("environment variable {} is not set", variable))
+    })
 }
 
 impl SurrealDbClosetProvider<Db> {
@@ -62,77 +142,202 @@ impl SurrealDbClosetProvider<Db> {
     }
 }
 
-impl<C> ClosetCreator for SurrealDbClosetProvider<C>
+impl<C> ClosetProvider for SurrealDbClosetProvider<C>
 where
     C: surrealdb::Connection,
 {
-    async fn create<T>(&self, cube: Cube<T>) -> Result<CubeHeader, LuggageError>
-    where
-        T: Serialize + Send + 'static,
-    {
-        let created: Option<Record> = self
-            .db
-            .create((cube.header.definition, cube.header.id))
-            .content(cube.content)
-            .await?;
-        match created {
-            Some(_) => Ok(cube.header),
-            None => Err(LuggageError::Unknown),
-        }
+    fn create_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>> {
+        Box::pin(async move {
+            let created: Option<Record> = self
+                .db
+                .create((cube.header.definition, cube.header.id))
+                .content(StoredCube {
+                    cube_id: cube.header.id.to_string(),
+                    content: cube.content,
+                })
+                .await?;
+            match created {
+                Some(_) => Ok(cube.header),
+                None => Err(LuggageError::Unknown),
+            }
+        })
     }
-}
 
-impl<C> ClosetReader for SurrealDbClosetProvider<C>
-where
-    C: surrealdb::Connection,
-{
-    async fn read<T>(&self, header: CubeHeader) -> Result<Cube<T>, LuggageError>
-    where
-        T: for<'a> Deserialize<'a> + Send,
-    {
-        let saved_content: Option<T> = self.db.select((header.definition, header.id)).await?;
-        Ok(Cube {
-            header,
-            content: saved_content,
+    fn read_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<Cube<Value>, LuggageError>> {
+        Box::pin(async move {
+            let saved: Option<StoredCube> = self.db.select((header.definition, header.id)).await?;
+            Ok(Cube {
+                header,
+                content: saved.and_then(|saved| saved.content),
+            })
         })
     }
-}
 
-impl<C> ClosetUpdater for SurrealDbClosetProvider<C>
-where
-    C: surrealdb::Connection,
-{
-    async fn update<T>(&self, cube: Cube<T>) -> Result<CubeHeader, LuggageError>
-    where
-        T: Serialize + Send + 'static,
-    {
-        let updated: Option<Record> = self
-            .db
-            .update((cube.header.definition, cube.header.id))
-            .content(cube.content)
-            .await?;
-        match updated {
-            Some(_) => Ok(cube.header),
-            None => Err(LuggageError::Unknown),
-        }
+    fn update_value(&self, cube: Cube<Value>) -> BoxFuture<'_, Result<CubeHeader, LuggageError>> {
+        Box::pin(async move {
+            let updated: Option<Record> = self
+                .db
+                .update((cube.header.definition, cube.header.id))
+                .content(StoredCube {
+                    cube_id: cube.header.id.to_string(),
+                    content: cube.content,
+                })
+                .await?;
+            match updated {
+                Some(_) => Ok(cube.header),
+                None => Err(LuggageError::Unknown),
+            }
+        })
     }
-}
 
-impl<C> ClosetDeleter for SurrealDbClosetProvider<C>
-where
-    C: surrealdb::Connection,
-{
-    async fn delete(&self, header: CubeHeader) -> Result<(), LuggageError> {
-        let _deleted: Option<Record> = self.db.delete((header.definition, header.id)).await?;
-        Ok(())
+    fn delete_value(&self, header: CubeHeader) -> BoxFuture<'_, Result<(), LuggageError>> {
+        Box::pin(async move {
+            let _deleted: Option<Record> = self.db.delete((header.definition, header.id)).await?;
+            Ok(())
+        })
+    }
+
+    fn query_values(
+        &self,
+        definition: LuggageId,
+        query: CubeQuery,
+    ) -> BoxFuture<'_, Result<CubePage<Value>, LuggageError>> {
+        Box::pin(async move {
+            // Fields are interpolated into SurrealQL, so they must be plain paths
+            query.validate()?;
+
+            let mut conditions = vec!["content != NONE".to_string()];
+            for (index, filter) in query.filters.iter().enumerate() {
+                conditions.push(format!(
+                    "{} = $filter{}",
+                    content_idiom(&filter.field),
+                    index
+                ));
+            }
+            let count_conditions = conditions.join(" AND ");
+
+            let descending = query.sort.as_ref().is_some_and(|sort| sort.descending);
+            let (direction, comparison) = if descending {
+                ("DESC", "<")
+            } else {
+                ("ASC", ">")
+            };
+            let mut after_value = None;
+            if let Some(after) = query.after {
+                match &query.sort {
+                    None => conditions.push(format!("cube_id {} $after", comparison)),
+                    Some(sort) => {
+                        let cursor = self
+                            .read_value(CubeHeader {
+                                id: after,
+                                definition,
+                            })
+                            .await?;
+                        let content = cursor.content.ok_or_else(|| {
+                            LuggageError::InvalidQuery(format!("cursor {} was not found", after))
+                        })?;
+                        after_value = query::field(&content, &sort.field).cloned();
+                        conditions.push(format!(
+                            "({field} {comparison} $after_value OR \
+                             ({field} = $after_value AND cube_id {comparison} $after))",
+                            field = content_idiom(&sort.field),
+                        ));
+                    }
+                }
+            }
+            let order = match &query.sort {
+                Some(sort) => format!(
+                    "{} {}, cube_id {}",
+                    content_idiom(&sort.field),
+                    direction,
+                    direction
+                ),
+                None => format!("cube_id {}", direction),
+            };
+
+            let sql = format!(
+                "SELECT cube_id, content FROM type::table($table) WHERE {} ORDER BY {} LIMIT $limit;
+                 SELECT count() FROM type::table($table) WHERE {} GROUP ALL;",
+                conditions.join(" AND "),
+                order,
+                count_conditions
+            );
+            let mut request = self
+                .db
+                .query(sql)
+                .bind(("table", definition.to_string()))
+                .bind(("after", query.after.map(|after| after.to_string())))
+                .bind(("after_value", after_value))
+                // One extra row tells whether there is a next page
+                .bind(("limit", query.limit + 1));
+            for (index, filter) in query.filters.into_iter().enumerate() {
+                request = request.bind((format!("filter{}", index), filter.value));
+            }
+            let mut response = request.await?;
+            let rows: Vec<StoredCube> = response.take(0)?;
+            let count: Option<usize> = response.take((1, "count"))?;
+
+            let has_next = rows.len() > query.limit;
+            let cubes = rows
+                .into_iter()
+                .take(query.limit)
+                .map(|row| {
+                    let id = Uuid::try_parse(&row.cube_id).map_err(|_| LuggageError::Unknown)?;
+                    Ok(Cube {
+                        header: CubeHeader { id, definition },
+                        content: row.content,
+                    })
+                })
+                .collect::<Result<Vec<_>, LuggageError>>()?;
+            let next = if has_next {
+                cubes.last().map(|cube| cube.header.id)
+            } else {
+                None
+            };
+            Ok(CubePage {
+                cubes,
+                count: count.unwrap_or_default(),
+                next,
+            })
+        })
+    }
+
+    fn definitions(&self) -> BoxFuture<'_, Result<Vec<LuggageId>, LuggageError>> {
+        Box::pin(async move {
+            // Each definition's cubes are a table named after it
+            let tables: Option<HashMap<String, Value>> =
+                self.db.query("INFO FOR DB").await?.take((0, "tables"))?;
+            Ok(tables
+                .unwrap_or_default()
+                .keys()
+                .filter_map(|table| Uuid::try_parse(table).ok())
+                .collect())
+        })
+    }
+
+    fn health(&self) -> BoxFuture<'_, Result<(), LuggageError>> {
+        Box::pin(async move {
+            self.db.health().await?;
+            Ok(())
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::error::Result;
+    use crate::{
+        closet::closet::{
+            ClosetCreator, ClosetCredentials, ClosetDeleter, ClosetQuerier, ClosetReader,
+            ClosetUpdater,
+        },
+        closet::{
+            history::CubeRevision,
+            query::{CubeFilter, CubeSort},
+        },
+        cube::cube::CubeRegistration,
+        error::Result,
+    };
     use surrealdb::engine::local::Db;
-    use uuid::Uuid;
 
     use super::*;
 
@@ -205,4 +410,143 @@ mod tests {
         assert_eq!(None, read_cube.content);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn typed_helpers_work_through_trait_object() -> Result<()> {
+        let test_name = "typed_helpers_work_through_trait_object";
+        let test_cube = Cube {
+            header: CubeHeader::new(Uuid::now_v7()),
+            content: Some(TestContent {
+                name: "boxed".into(),
+            }),
+        };
+        let closet: Box<dyn ClosetProvider> =
+            Box::new(SurrealDbClosetProvider::<Db>::new(test_name, "test").await?);
+        let saved_header = closet.create(test_cube.clone()).await?;
+        let saved_cube: Cube<TestContent> = closet.read(saved_header.clone()).await?;
+        assert_eq!(&test_cube.content, &saved_cube.content);
+
+        let saved_value = closet.read_value(saved_header).await?;
+        assert_eq!(
+            saved_value.content,
+            Some(serde_json::json!({ "name": "boxed" }))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_filters_sorts_and_pages() -> Result<()> {
+        let test_name = "query_filters_sorts_and_pages";
+        let closet = SurrealDbClosetProvider::<Db>::new(test_name, "test").await?;
+        let definition = Uuid::now_v7();
+        for name in ["b", "skip", "c", "a"] {
+            closet
+                .create(Cube::new(
+                    CubeHeader::new(definition),
+                    TestContent { name: name.into() },
+                ))
+                .await?;
+        }
+
+        let all: CubePage<TestContent> = closet.query(definition, CubeQuery::default()).await?;
+        assert_eq!(all.count, 4);
+        assert!(
+            all.cubes
+                .windows(2)
+                .all(|pair| pair[0].header.id < pair[1].header.id)
+        );
+
+        let query = CubeQuery {
+            filters: vec![CubeFilter {
+                field: "name".into(),
+                value: "skip".into(),
+            }],
+            ..Default::default()
+        };
+        let filtered: CubePage<TestContent> = closet.query(definition, query).await?;
+        assert_eq!(filtered.count, 1);
+
+        let query = CubeQuery {
+            sort: Some(CubeSort {
+                field: "name".into(),
+                descending: true,
+            }),
+            limit: 2,
+            ..Default::default()
+        };
+        let first: CubePage<TestContent> = closet.query(definition, query.clone()).await?;
+        let second: CubePage<TestContent> = closet
+            .query(
+                definition,
+                CubeQuery {
+                    after: first.next,
+                    ..query
+                },
+            )
+            .await?;
+        let names: Vec<String> = first
+            .cubes
+            .into_iter()
+            .chain(second.cubes)
+            .filter_map(|cube| cube.content.map(|content| content.name))
+            .collect();
+        assert_eq!(names, vec!["skip", "c", "b", "a"]);
+        assert_eq!(second.next, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn definitions_are_the_tables() -> Result<()> {
+        let closet =
+            SurrealDbClosetProvider::<Db>::new("definitions_are_the_tables", "test").await?;
+        let header = closet
+            .create(Cube::new(
+                CubeHeader::new(Uuid::now_v7()),
+                TestContent {
+                    name: "test".into(),
+                },
+            ))
+            .await?;
+        let definitions = closet.definitions().await?;
+        assert!(definitions.contains(&header.definition));
+        assert!(definitions.contains(&CubeRevision::id()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connect_requires_url() {
+        let result = SurrealDbClosetProvider::<Client>::connect(&ClosetConnection::default()).await;
+        assert!(matches!(result, Err(LuggageError::ClosetConfiguration(_))));
+    }
+
+    #[ignore = "requires database/start-local.sh"]
+    #[tokio::test]
+    async fn connect_to_local_server() -> Result<()> {
+        let connection = ClosetConnection {
+            url: Some("127.0.0.1:8000".into()),
+            namespace: Some("connect_to_local_server".into()),
+            database: Some("test".into()),
+            credentials: Some(ClosetCredentials {
+                username_env: "LUGGAGE_TEST_SURREAL_USER".into(),
+                password_env: "LUGGAGE_TEST_SURREAL_PASS".into(),
+            }),
+            ..Default::default()
+        };
+        let closet = SurrealDbClosetProvider::<Client>::connect_with_env(&connection, |_| {
+            Some("root".into())
+        })
+        .await?;
+        closet.health().await?;
+
+        let test_cube = Cube {
+            header: CubeHeader::new(Uuid::now_v7()),
+            content: Some(TestContent {
+                name: "remote".into(),
+            }),
+        };
+        let saved_header = closet.create(test_cube.clone()).await?;
+        let saved_cube: Cube<TestContent> = closet.read(saved_header).await?;
+        assert_eq!(&test_cube.content, &saved_cube.content);
+        Ok(())
+    }
 }
@@ -0,0 +1,309 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    core::core::LuggageId,
+    cube::cube::{Cube, CubeHeader},
+    error::LuggageError,
+};
+
+/// Number of cubes in a page when a query doesn't set a limit
+pub const DEFAULT_QUERY_LIMIT: usize = 50;
+/// Largest page a query may ask for
+pub const MAX_QUERY_LIMIT: usize = 1000;
+
+/// Only match cubes whose content has `value` at `field`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CubeFilter {
+    /// Dot separated path into the content, e.g. `address.city`
+    pub field: String,
+    pub value: Value,
+}
+
+/// Order cubes by a content field, with ties broken by cube ID.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CubeSort {
+    /// Dot separated path into the content
+    pub field: String,
+    pub descending: bool,
+}
+
+/// A page of cubes of one definition.
+///
+/// Cubes are ordered by ID unless `sort` is set, and since IDs are UUID v7 that is creation order.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CubeQuery {
+    pub filters: Vec<CubeFilter>,
+    pub sort: Option<CubeSort>,
+    /// ID of the last cube of the previous page
+    pub after: Option<LuggageId>,
+    pub limit: usize,
+}
+
+impl Default for CubeQuery {
+    fn default() -> Self {
+        Self {
+            filters: Vec::new(),
+            sort: None,
+            after: None,
+            limit: DEFAULT_QUERY_LIMIT,
+        }
+    }
+}
+
+impl CubeQuery {
+    /// Check the limit and that fields are plain paths, which providers may put in queries.
+    pub fn validate(&self) -> Result<(), LuggageError> {
+        if self.limit == 0 || self.limit > MAX_QUERY_LIMIT {
+            return Err(LuggageError::InvalidQuery(format!(
+                "limit must be between 1 and {}",
+                MAX_QUERY_LIMIT
+            )));
+        }
+        let fields = self
+            .filters
+            .iter()
+            .map(|filter| &filter.field)
+            .chain(self.sort.iter().map(|sort| &sort.field));
+        for field in fields {
+            let valid = field.split('.').all(|segment| {
+                !segment.is_empty()
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+            if !valid {
+                return Err(LuggageError::InvalidQuery(format!(
+                    "{} is not a valid field",
+                    field
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CubePage<T> {
+    pub cubes: Vec<Cube<T>>,
+    /// Number of cubes matching the filters, across every page
+    pub count: usize,
+    /// Cursor for the next page, or `None` on the last page
+    pub next: Option<LuggageId>,
+}
+
+/// Look up a dot separated field in cube content.
+pub fn field<'a>(content: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(content, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        })
+}
+
+/// Order JSON values of any type: missing, null, booleans, numbers, strings, then arrays and
+/// objects.
+pub fn compare_values(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    fn rank(value: Option<&Value>) -> u8 {
+        match value {
+            None => 0,
+            Some(Value::Null) => 1,
+            Some(Value::Bool(_)) => 2,
+            Some(Value::Number(_)) => 3,
+            Some(Value::String(_)) => 4,
+            Some(Value::Array(_)) => 5,
+            Some(Value::Object(_)) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(a), Some(b)) if rank(Some(a)) == rank(Some(b)) => a.to_string().cmp(&b.to_string()),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Run a query over every cube of a definition, for providers that can't filter in storage.
+pub fn apply(query: &CubeQuery, cubes: Vec<Cube<Value>>) -> Result<CubePage<Value>, LuggageError> {
+    query.validate()?;
+
+    let mut cubes: Vec<(CubeHeader, Value)> = cubes
+        .into_iter()
+        .filter_map(|cube| Some((cube.header, cube.content?)))
+        .filter(|(_, content)| {
+            query
+                .filters
+                .iter()
+                .all(|filter| field(content, &filter.field) == Some(&filter.value))
+        })
+        .collect();
+    let count = cubes.len();
+
+    let descending = query.sort.as_ref().is_some_and(|sort| sort.descending);
+    cubes.sort_by(|(a_header, a), (b_header, b)| {
+        let ordering = match &query.sort {
+            Some(sort) => compare_values(field(a, &sort.field), field(b, &sort.field)),
+            None => Ordering::Equal,
+        }
+        .then(a_header.id.cmp(&b_header.id));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    let start = match query.after {
+        None => 0,
+        Some(after) => match cubes.iter().position(|(header, _)| header.id == after) {
+            Some(position) => position + 1,
+            // Without a sort, a deleted cursor cube still marks a place in ID order
+            None if query.sort.is_none() => cubes
+                .iter()
+                .position(|(header, _)| {
+                    if descending {
+                        header.id < after
+                    } else {
+                        header.id > after
+                    }
+                })
+                .unwrap_or(cubes.len()),
+            None => {
+                return Err(LuggageError::InvalidQuery(format!(
+                    "cursor {} was not found",
+                    after
+                )));
+            }
+        },
+    };
+
+    let remaining = cubes.len().saturating_sub(start);
+    let cubes: Vec<Cube<Value>> = cubes
+        .into_iter()
+        .skip(start)
+        .take(query.limit)
+        .map(|(header, content)| Cube::new(header, content))
+        .collect();
+    let next = if remaining > query.limit {
+        cubes.last().map(|cube| cube.header.id)
+    } else {
+        None
+    };
+    Ok(CubePage { cubes, count, next })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::error::Result;
+
+    use super::*;
+
+    fn test_cubes() -> Vec<Cube<Value>> {
+        let definition = Uuid::now_v7();
+        [
+            ("b", 2, true),
+            ("a", 3, false),
+            ("c", 1, true),
+            ("d", 4, true),
+        ]
+        .into_iter()
+        .map(|(name, rank, done)| {
+            Cube::new(
+                CubeHeader::new(definition),
+                json!({ "name": name, "meta": { "rank": rank }, "done": done }),
+            )
+        })
+        .collect()
+    }
+
+    fn names(page: &CubePage<Value>) -> Vec<&str> {
+        page.cubes
+            .iter()
+            .filter_map(|cube| cube.content.as_ref()?.get("name")?.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn pages_follow_id_order() -> Result<()> {
+        let mut query = CubeQuery {
+            limit: 3,
+            ..Default::default()
+        };
+        let cubes = test_cubes();
+        let first = apply(&query, cubes.clone())?;
+        assert_eq!(names(&first), vec!["b", "a", "c"]);
+        assert_eq!(first.count, 4);
+        assert_eq!(first.next, Some(first.cubes[2].header.id));
+
+        query.after = first.next;
+        let second = apply(&query, cubes)?;
+        assert_eq!(names(&second), vec!["d"]);
+        assert_eq!(second.next, None);
+        Ok(())
+    }
+
+    #[test]
+    fn filters_and_sorts_by_fields() -> Result<()> {
+        let query = CubeQuery {
+            filters: vec![CubeFilter {
+                field: "done".into(),
+                value: json!(true),
+            }],
+            sort: Some(CubeSort {
+                field: "meta.rank".into(),
+                descending: true,
+            }),
+            limit: 2,
+            ..Default::default()
+        };
+        let cubes = test_cubes();
+        let first = apply(&query, cubes.clone())?;
+        assert_eq!(names(&first), vec!["d", "b"]);
+        assert_eq!(first.count, 3);
+
+        let second = apply(
+            &CubeQuery {
+                after: first.next,
+                ..query
+            },
+            cubes,
+        )?;
+        assert_eq!(names(&second), vec!["c"]);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_fields_and_limits() {
+        let query = CubeQuery {
+            sort: Some(CubeSort {
+                field: "name; DELETE".into(),
+                descending: false,
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            query.validate(),
+            Err(LuggageError::InvalidQuery(_))
+        ));
+
+        let query = CubeQuery {
+            limit: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            query.validate(),
+            Err(LuggageError::InvalidQuery(_))
+        ));
+    }
+}
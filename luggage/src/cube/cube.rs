@@ -2,14 +2,27 @@ use schemars::{JsonSchema, schema_for};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::core::core::LuggageId;
+use crate::{
+    core::core::LuggageId,
+    cube::migration::{CubeMigration, INITIAL_VERSION},
+};
 
 pub type CubeSchema = String; // Currently just JSONSchema, later expand to TOML, YAML, etc...
 
-#[derive(Serialize, JsonSchema)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct CubeDefinition {
     pub id: String, // LuggageId // TODO: Return to LuggageId after add uuid1 feature to JsonSchema cargo
     pub schema: CubeSchema,
+    /// Semantic version of the schema
+    #[serde(default = "initial_version")]
+    pub version: String,
+    /// Upgrades of content written under earlier versions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub migrations: Vec<CubeMigration>,
+}
+
+fn initial_version() -> String {
+    INITIAL_VERSION.into()
 }
 
 impl CubeRegistration for CubeDefinition {
@@ -26,10 +39,12 @@ pub trait CubeRegistration {
     fn schema() -> CubeSchema;
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, utoipa::ToSchema)]
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema, utoipa::ToSchema)]
 pub struct CubeHeader {
+    #[schemars(with = "String")]
     #[schema(value_type = String, example = "0194f2fe-6f7a-7dd2-8af3-d6d4c9a2f74a")]
     pub id: LuggageId,
+    #[schemars(with = "String")]
     #[schema(value_type = String, example = "0194f27d-d3d5-7960-b953-e5d3ea1047a6")]
     pub definition: LuggageId,
 }
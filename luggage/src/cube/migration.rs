@@ -0,0 +1,274 @@
+use schemars::JsonSchema;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{closet::query, cube::cube::CubeDefinition, error::LuggageError};
+
+/// Version of definitions that don't declare one, and of content written before versions were
+/// recorded.
+pub const INITIAL_VERSION: &str = "1.0.0";
+
+/// Upgrades content written under an earlier version of a definition to `version`.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct CubeMigration {
+    /// Version the steps upgrade content to
+    pub version: String,
+    pub steps: Vec<MigrationStep>,
+}
+
+/// One change to content, addressing fields with dot separated paths such as `address.city`.
+///
+/// Steps whose source field is missing do nothing, so content that already has the new shape is
+/// left alone.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum MigrationStep {
+    /// Give a field a new name in the same object
+    Rename { field: String, to: String },
+    /// Move a field anywhere in the content, creating objects along the way
+    Move { from: String, to: String },
+    /// Set a field that is missing
+    Default { field: String, value: Value },
+}
+
+pub fn parse_version(version: &str) -> Result<Version, LuggageError> {
+    Version::parse(version).map_err(|e| {
+        LuggageError::InvalidContent(format!("{} is not a semantic version: {}", version, e))
+    })
+}
+
+fn check_path(path: &str) -> Result<(), LuggageError> {
+    if path.split('.').any(str::is_empty) {
+        return Err(LuggageError::InvalidContent(format!(
+            "{} is not a valid field path",
+            path
+        )));
+    }
+    Ok(())
+}
+
+/// Check a definition's version and that its migrations upgrade to distinct versions up to it.
+pub fn check(definition: &CubeDefinition) -> Result<(), LuggageError> {
+    let current = parse_version(&definition.version)?;
+    let mut versions = Vec::new();
+    for migration in &definition.migrations {
+        let version = parse_version(&migration.version)?;
+        if version > current {
+            return Err(LuggageError::InvalidContent(format!(
+                "migration to {} is newer than the definition version {}",
+                version, current
+            )));
+        }
+        if versions.contains(&version) {
+            return Err(LuggageError::InvalidContent(format!(
+                "more than one migration to {}",
+                version
+            )));
+        }
+        versions.push(version);
+
+        for step in &migration.steps {
+            match step {
+                MigrationStep::Rename { field, to } => {
+                    check_path(field)?;
+                    if to.is_empty() || to.contains('.') {
+                        return Err(LuggageError::InvalidContent(format!(
+                            "{} is not a valid field name, use move to change its parent",
+                            to
+                        )));
+                    }
+                }
+                MigrationStep::Move { from, to } => {
+                    check_path(from)?;
+                    check_path(to)?;
+                }
+                MigrationStep::Default { field, .. } => check_path(field)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether content written under `written` is older than the definition, where `None` is
+/// [`INITIAL_VERSION`].
+pub fn is_outdated(
+    definition: &CubeDefinition,
+    written: Option<&str>,
+) -> Result<bool, LuggageError> {
+    let written = parse_version(written.unwrap_or(INITIAL_VERSION))?;
+    Ok(written < parse_version(&definition.version)?)
+}
+
+/// Upgrade content written under `written` to the definition's version, applying every migration
+/// after `written` in version order.
+pub fn migrate(
+    definition: &CubeDefinition,
+    mut content: Value,
+    written: Option<&str>,
+) -> Result<Value, LuggageError> {
+    let written = parse_version(written.unwrap_or(INITIAL_VERSION))?;
+    let current = parse_version(&definition.version)?;
+    if written > current {
+        return Err(LuggageError::InvalidContent(format!(
+            "content was written under version {}, newer than the definition version {}",
+            written, current
+        )));
+    }
+
+    let mut migrations = definition
+        .migrations
+        .iter()
+        .map(|migration| Ok((parse_version(&migration.version)?, migration)))
+        .collect::<Result<Vec<_>, LuggageError>>()?;
+    migrations.retain(|(version, _)| *version > written && *version <= current);
+    migrations.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (_, migration) in migrations {
+        for step in &migration.steps {
+            apply(&mut content, step)?;
+        }
+    }
+    Ok(content)
+}
+
+fn apply(content: &mut Value, step: &MigrationStep) -> Result<(), LuggageError> {
+    match step {
+        MigrationStep::Rename { field, to } => {
+            if let Some(value) = take(content, field) {
+                let renamed = match field.rsplit_once('.') {
+                    Some((parent, _)) => format!("{}.{}", parent, to),
+                    None => to.clone(),
+                };
+                put(content, &renamed, value)?;
+            }
+        }
+        MigrationStep::Move { from, to } => {
+            if let Some(value) = take(content, from) {
+                put(content, to, value)?;
+            }
+        }
+        MigrationStep::Default { field, value } => {
+            if query::field(content, field).is_none() {
+                put(content, field, value.clone())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove the field at `path`, if there is one.
+fn take(content: &mut Value, path: &str) -> Option<Value> {
+    let (parent, name) = match path.rsplit_once('.') {
+        Some((parent, name)) => (
+            parent
+                .split('.')
+                .try_fold(content, |value, segment| value.get_mut(segment))?,
+            name,
+        ),
+        None => (content, path),
+    };
+    parent.as_object_mut()?.remove(name)
+}
+
+/// Set the field at `path`, creating missing objects along the way.
+fn put(content: &mut Value, path: &str, value: Value) -> Result<(), LuggageError> {
+    let mut target = content;
+    for segment in path.split('.') {
+        if target.is_null() {
+            *target = Value::Object(Map::new());
+        }
+        let Value::Object(object) = target else {
+            return Err(LuggageError::InvalidContent(format!(
+                "can not migrate {}, its parent is not an object",
+                path
+            )));
+        };
+        target = object.entry(segment).or_insert(Value::Null);
+    }
+    *target = value;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::error::Result;
+
+    use super::*;
+
+    fn test_definition() -> CubeDefinition {
+        CubeDefinition {
+            id: "0194f2fe-6f7a-7dd2-8af3-d6d4c9a2f74a".into(),
+            schema: "{}".into(),
+            version: "2.1.0".into(),
+            migrations: vec![
+                CubeMigration {
+                    version: "2.1.0".into(),
+                    steps: vec![MigrationStep::Default {
+                        field: "done".into(),
+                        value: json!(false),
+                    }],
+                },
+                CubeMigration {
+                    version: "2.0.0".into(),
+                    steps: vec![
+                        MigrationStep::Rename {
+                            field: "title".into(),
+                            to: "name".into(),
+                        },
+                        MigrationStep::Move {
+                            from: "city".into(),
+                            to: "address.city".into(),
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn migrations_apply_in_version_order() -> Result<()> {
+        let definition = test_definition();
+        check(&definition)?;
+
+        let old = json!({ "title": "home", "city": "Paris" });
+        assert!(is_outdated(&definition, None)?);
+        assert_eq!(
+            migrate(&definition, old, None)?,
+            json!({ "name": "home", "address": { "city": "Paris" }, "done": false })
+        );
+
+        let newer = json!({ "name": "work", "title": "kept", "done": true });
+        assert_eq!(migrate(&definition, newer.clone(), Some("2.0.0"))?, newer);
+        assert!(!is_outdated(&definition, Some("2.1.0"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn content_from_newer_version_is_rejected() {
+        assert!(matches!(
+            migrate(&test_definition(), json!({}), Some("3.0.0")),
+            Err(LuggageError::InvalidContent(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_definitions_are_rejected() {
+        let mut definition = test_definition();
+        definition.version = "two".into();
+        assert!(check(&definition).is_err());
+
+        let mut definition = test_definition();
+        definition.version = "2.0.5".into();
+        assert!(check(&definition).is_err());
+
+        let mut definition = test_definition();
+        definition.migrations[1].steps.push(MigrationStep::Rename {
+            field: "name".into(),
+            to: "address.name".into(),
+        });
+        assert!(check(&definition).is_err());
+    }
+}
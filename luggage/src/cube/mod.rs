@@ -1,3 +1,5 @@
 #![allow(clippy::module_inception)]
 
 pub mod cube;
+pub mod migration;
+pub mod validation;
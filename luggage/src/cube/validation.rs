@@ -0,0 +1,85 @@
+use jsonschema::Validator;
+use serde_json::Value;
+
+use crate::{cube::cube::CubeSchema, error::LuggageError};
+
+/// Compile a cube definition's JSON Schema.
+pub fn compile(schema: &CubeSchema) -> Result<Validator, LuggageError> {
+    let schema: Value = serde_json::from_str(schema)
+        .map_err(|e| LuggageError::InvalidContent(format!("schema is not valid JSON: {}", e)))?;
+    jsonschema::validator_for(&schema).map_err(|e| {
+        LuggageError::InvalidContent(format!("schema is not a valid JSON Schema: {}", e))
+    })
+}
+
+/// Check cube content against a compiled schema, collecting every validation error.
+pub fn validate(validator: &Validator, content: &Value) -> Result<(), LuggageError> {
+    let errors: Vec<String> = validator
+        .iter_errors(content)
+        .map(|e| match e.instance_path.to_string() {
+            path if path.is_empty() => e.to_string(),
+            path => format!("{}: {}", path, e),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(LuggageError::ValidationFailed(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{JsonSchema, schema_for};
+    use serde::Serialize;
+    use serde_json::json;
+
+    use crate::{
+        cube::cube::{CubeDefinition, CubeRegistration},
+        error::Result,
+    };
+
+    use super::*;
+
+    #[derive(Serialize, JsonSchema)]
+    struct TestContent {
+        name: String,
+        todos: Vec<String>,
+    }
+
+    #[test]
+    fn validate_generated_schema() -> Result<()> {
+        let schema = serde_json::to_string(&schema_for!(TestContent))?;
+        let validator = compile(&schema)?;
+        validate(&validator, &json!({ "name": "valid", "todos": [] }))?;
+
+        let result = validate(&validator, &json!({ "name": 1 }));
+        let Err(LuggageError::ValidationFailed(errors)) = result else {
+            panic!("content should not be valid");
+        };
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|e| e.starts_with("/name: ")));
+        Ok(())
+    }
+
+    #[test]
+    fn cube_definition_validates_itself() -> Result<()> {
+        let validator = compile(&CubeDefinition::schema())?;
+        validate(
+            &validator,
+            &json!({ "id": CubeDefinition::id(), "schema": CubeDefinition::schema() }),
+        )
+    }
+
+    #[test]
+    fn invalid_schema_is_rejected() {
+        assert!(matches!(
+            compile(&"{ not json".to_string()),
+            Err(LuggageError::InvalidContent(_))
+        ));
+        assert!(matches!(
+            compile(&json!({ "type": 5 }).to_string()),
+            Err(LuggageError::InvalidContent(_))
+        ));
+    }
+}
@@ -6,6 +6,39 @@ pub enum LuggageError {
     #[doc(hidden)]
     #[error("Unknown error has occurred.")]
     Unknown,
+    /// The requested cube does not exist.
+    #[error("Cube was not found.")]
+    NotFound,
+    /// A closet's configuration can not be used, e.g. its connection settings are missing.
+    #[error("Invalid closet configuration: {0}")]
+    ClosetConfiguration(String),
+    /// No closet with this ID or name is registered.
+    #[error("Closet {0} was not found.")]
+    ClosetNotFound(String),
+    /// A cube with the same header already exists.
+    #[error("Cube already exists.")]
+    Conflict,
+    /// The cube content could not be used, e.g. it is not valid JSON.
+    #[error("Invalid cube content: {0}")]
+    InvalidContent(String),
+    /// No cube definition with this ID is registered.
+    #[error("Cube definition {0} was not found.")]
+    UnknownDefinition(uuid::Uuid),
+    /// The cube content does not match the JSON Schema of its definition.
+    #[error("Cube content does not match its definition.")]
+    ValidationFailed(Vec<String>),
+    /// A cube query can not be run, e.g. it filters on a malformed field.
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    /// The cube was updated since the revision the caller expected.
+    #[error("Cube is at revision {current}, not {expected}.")]
+    RevisionMismatch { expected: u64, current: u64 },
+    /// A closet archive can not be imported, e.g. a file does not match its checksum.
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(String),
+    /// Cubes of an archive already exist in the closet it is imported into.
+    #[error("{} cubes of the archive already exist.", .0.len())]
+    ImportCollision(Vec<String>),
 }
 
 pub type Result<T> = std::result::Result<T, LuggageError>;
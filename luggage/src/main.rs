@@ -1,5 +1,15 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+
 use crate::error::Result;
-use bellhop::bellhop::{app, listener};
+use bellhop::{
+    bellhop::{app, export_archive, find_closet, import_archive, listener, state},
+    startup::get_startup_configuration,
+};
+use clap::{Parser, Subcommand};
 use uuid::Uuid;
 
 pub mod bellhop;
@@ -8,11 +18,65 @@ pub mod core;
 pub mod cube;
 pub mod error;
 
+#[derive(Parser)]
+#[command(version, about = "Luggage portable data platform")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the Bellhop API, which is the default
+    Serve,
+    /// Write every cube of a closet to a tar archive
+    Export {
+        /// ID or name of the closet
+        #[arg(long)]
+        closet: String,
+        /// File to write the archive to, instead of standard output
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Recreate the cubes of an exported archive in a closet
+    Import {
+        /// ID or name of the closet
+        #[arg(long)]
+        closet: String,
+        archive: PathBuf,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    println!("{}", Uuid::now_v7());
-    axum::serve(listener().await, app(None).await?)
-        .await
-        .unwrap();
+    match Cli::parse().command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            println!("{}", Uuid::now_v7());
+            axum::serve(listener().await, app(None).await?)
+                .await
+                .unwrap();
+        }
+        Command::Export { closet, output } => {
+            let state = state(Some(get_startup_configuration()?)).await?;
+            let mut chunks = export_archive(&state, find_closet(&state, &closet)?).await?;
+            let mut writer: Box<dyn Write> = match output {
+                Some(path) => Box::new(File::create(path)?),
+                None => Box::new(io::stdout().lock()),
+            };
+            while let Some(chunk) = chunks.recv().await {
+                writer.write_all(&chunk?)?;
+            }
+            writer.flush()?;
+        }
+        Command::Import { closet, archive } => {
+            let state = state(Some(get_startup_configuration()?)).await?;
+            let closet_id = find_closet(&state, &closet)?;
+            let report = import_archive(&state, closet_id, &fs::read(archive)?).await?;
+            println!(
+                "Imported {} cubes and {} definitions",
+                report.cubes, report.definitions
+            );
+        }
+    }
     return Ok(());
 }
@@ -0,0 +1,31 @@
+use std::fs;
+use std::path::Path;
+use workflow_tests::*;
+
+fn assert_matches_generated(relative: &str) {
+    let repo_root = get_repo_root();
+    let rendered = cast::workflows::render(&repo_root).expect("Failed to render workflows");
+    let (_, expected) = rendered
+        .iter()
+        .find(|(path, _)| path == Path::new(relative))
+        .expect("Workflow is not generated by cast");
+
+    let content =
+        fs::read_to_string(repo_root.join(relative)).expect("Failed to read workflow file");
+
+    assert!(
+        &content == expected,
+        "{} is out of date, run `cast workflows generate` from the repository root",
+        relative
+    );
+}
+
+#[test]
+fn test_cast_ci_workflow_matches_generated() {
+    assert_matches_generated(cast::workflows::CI_WORKFLOW_PATH);
+}
+
+#[test]
+fn test_cast_cd_workflow_matches_generated() {
+    assert_matches_generated(cast::workflows::CD_WORKFLOW_PATH);
+}
@@ -174,3 +174,14 @@ fn test_workflow_has_retry_logic_for_race_condition() {
         "Workflow should retry checking for agent tasks after a delay to handle race conditions"
     );
 }
+
+#[test]
+fn test_workflow_selects_next_issue_with_cast() {
+    let content = fs::read_to_string(get_start_a_new_task_workflow_path())
+        .expect("Failed to read workflow file");
+
+    assert!(
+        content.contains("issues next") && content.contains("## Selected Issue"),
+        "Workflow should append the issue picked by `cast issues next` to the agent prompt"
+    );
+}